@@ -72,7 +72,7 @@ use crate::{
 #[allow(unreachable_pub)]
 pub use coerce::could_coerce;
 #[allow(unreachable_pub)]
-pub use unify::{could_unify, could_unify_deeply};
+pub use unify::{could_unify, could_unify_deeply, normalize_associated_types};
 
 use cast::CastCheck;
 pub(crate) use closure::{CaptureKind, CapturedItem, CapturedItemWithoutTy};
@@ -158,6 +158,21 @@ pub fn could_unify_deeply(
     table.unify_deeply(&ty1_with_vars, &ty2_with_vars)
 }
 
+/// Normalize associated type projections (such as `<S as Trait>::Assoc`) in the type.
+///
+/// Projections that cannot be normalized are replaced with error type.
+pub fn normalize_associated_types(
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    ty: &Canonical<Ty>,
+) -> Ty {
+    let mut table = InferenceTable::new(db, env);
+    let ty = table.instantiate_canonical(ty.clone());
+    let ty = table.normalize_associated_types_in(ty);
+    table.resolve_obligations_as_possible();
+    table.resolve_completely(ty)
+}
+
 pub(crate) fn unify(
     db: &dyn HirDatabase,
     env: Arc<TraitEnvironment>,
@@ -35,6 +35,9 @@ pub mod term_search;
 
 mod display;
 
+#[cfg(test)]
+mod test_db;
+
 use std::{iter, mem::discriminant, ops::ControlFlow};
 
 use arrayvec::ArrayVec;
@@ -43,7 +46,10 @@ use either::Either;
 use hir_def::{
     body::{BodyDiagnostic, SyntheticSyntax},
     data::adt::VariantData,
-    generics::{LifetimeParamData, TypeOrConstParamData, TypeParamProvenance},
+    generics::{
+        LifetimeParamData, TypeOrConstParamData, TypeParamProvenance, WherePredicate,
+        WherePredicateTypeTarget,
+    },
     hir::{BindingAnnotation, BindingId, ExprOrPatId, LabelId, Pat},
     item_tree::ItemTreeNode,
     lang_item::LangItemTarget,
@@ -51,8 +57,9 @@ use hir_def::{
     nameres::{self, diagnostics::DefDiagnostic},
     path::ImportAlias,
     per_ns::PerNs,
-    resolver::{HasResolver, Resolver},
+    resolver::{HasResolver, Resolver, TypeNs},
     src::HasSource as _,
+    type_ref::{TraitBoundModifier, TypeBound},
     AssocItemId, AssocItemLoc, AttrDefId, ConstId, ConstParamId, CrateRootModuleId, DefWithBodyId,
     EnumId, EnumVariantId, ExternCrateId, FunctionId, GenericDefId, GenericParamId, HasModule,
     ImplId, InTypeConstId, ItemContainerId, LifetimeParamId, LocalFieldId, Lookup, MacroExpander,
@@ -73,10 +80,10 @@ use hir_ty::{
     mir::{interpret_mir, MutBorrowKind},
     primitive::UintTy,
     traits::FnTrait,
-    AliasTy, CallableDefId, CallableSig, Canonical, CanonicalVarKinds, Cast, ClosureId, GenericArg,
-    GenericArgData, Interner, ParamKind, QuantifiedWhereClause, Scalar, Substitution,
-    TraitEnvironment, TraitRefExt, Ty, TyBuilder, TyDefId, TyExt, TyKind, ValueTyDefId,
-    WhereClause,
+    AliasEq, AliasTy, CallableDefId, CallableSig, Canonical, CanonicalVarKinds, Cast, ClosureId,
+    GenericArg, GenericArgData, Interner, ParamKind, ProjectionTyExt, QuantifiedWhereClause,
+    Scalar, Substitution, TraitEnvironment, TraitRefExt, Ty, TyBuilder, TyDefId, TyExt, TyKind,
+    ValueTyDefId, WhereClause,
 };
 use itertools::Itertools;
 use nameres::diagnostics::DefDiagnosticKind;
@@ -1900,6 +1907,10 @@ impl Function {
         Type::new_with_resolver_inner(db, &resolver, ty)
     }
 
+    /// Return type of the function instantiated with `generics`.
+    ///
+    /// The type lives in the trait environment of the generics (if there are any), so that the
+    /// impls visible to the caller are visible for the returned type as well.
     // FIXME: Find better API to also handle const generics
     pub fn ret_type_with_args(
         self,
@@ -1912,6 +1923,8 @@ impl Function {
             ItemContainerId::TraitId(it) => Some(it.into()),
             ItemContainerId::ModuleId(_) | ItemContainerId::ExternBlockId(_) => None,
         };
+        let mut generics = generics.peekable();
+        let env = generics.peek().map(|it| it.env.clone());
         let mut generics = generics.map(|it| it.ty);
         let mut filler = |x: &_| match x {
             ParamKind::Type => {
@@ -1927,7 +1940,10 @@ impl Function {
 
         let callable_sig = db.callable_item_signature(self.id.into()).substitute(Interner, &substs);
         let ty = callable_sig.ret().clone();
-        Type::new_with_resolver_inner(db, &resolver, ty)
+        match env {
+            Some(env) => Type { env, ty },
+            None => Type::new_with_resolver_inner(db, &resolver, ty),
+        }
     }
 
     pub fn async_ret_type(self, db: &dyn HirDatabase) -> Option<Type> {
@@ -2042,6 +2058,137 @@ impl Function {
             .collect()
     }
 
+    /// Check if the where clauses of the function (including the ones of parent impl or trait)
+    /// hold when instantiated with `generics`.
+    ///
+    /// Bounds on associated types (such as `where T::Item: Display`) are normalized as part of
+    /// the check. Implicit `Sized` bounds are checked too, and bounds implied by supertraits of
+    /// the bounds in the environment are taken into account.
+    // FIXME: Find better API to also handle const generics
+    pub fn predicates_hold_with_args(
+        self,
+        db: &dyn HirDatabase,
+        generics: impl Iterator<Item = Type>,
+    ) -> bool {
+        let generics: Vec<Type> = generics.collect();
+        let env = match generics.first() {
+            Some(it) => it.env.clone(),
+            None => db.trait_environment(self.id.into()),
+        };
+        let parent_id: Option<GenericDefId> = match self.id.lookup(db.upcast()).container {
+            ItemContainerId::ImplId(it) => Some(it.into()),
+            ItemContainerId::TraitId(it) => Some(it.into()),
+            ItemContainerId::ModuleId(_) | ItemContainerId::ExternBlockId(_) => None,
+        };
+        let mut generics = generics.into_iter().map(|it| it.ty);
+        let mut filler = |x: &_| match x {
+            ParamKind::Type => {
+                generics.next().unwrap_or_else(|| TyKind::Error.intern(Interner)).cast(Interner)
+            }
+            ParamKind::Const(ty) => unknown_const_as_generic(ty.clone()),
+            ParamKind::Lifetime => error_lifetime().cast(Interner),
+        };
+
+        let parent_substs =
+            parent_id.map(|id| TyBuilder::subst_for_def(db, id, None).fill(&mut filler).build());
+        let substs = TyBuilder::subst_for_def(db, self.id, parent_substs).fill(&mut filler).build();
+
+        db.generic_predicates(self.id.into())
+            .iter()
+            .map(|pred| pred.clone().substitute(Interner, &substs))
+            // We do not check lifetime bounds
+            .filter(|pred| {
+                matches!(pred.skip_binders(), WhereClause::Implemented(_) | WhereClause::AliasEq(_))
+            })
+            .all(|pred| {
+                let goal = Canonical {
+                    value: hir_ty::InEnvironment::new(&env.env, pred.cast(Interner)),
+                    binders: CanonicalVarKinds::empty(Interner),
+                };
+                db.trait_solve(env.krate, env.block, goal).is_some()
+            })
+    }
+
+    /// Check if the type parameters of the function itself are inferred from the arguments and
+    /// the generics of the parent impl or trait, so that calls do not have to spell them out.
+    ///
+    /// Besides the parameters in the argument types, this includes the ones only bound by the
+    /// where clauses on inferred types, such as the closure output `B` of
+    /// `F: FnMut(Self::Item) -> B` or the item `T` of `Self: Iterator<Item = &'a T>`.
+    pub fn generics_inferred_from_args(self, db: &dyn HirDatabase) -> bool {
+        let own: Vec<TypeOrConstParamId> = GenericDef::from(self)
+            .type_or_const_params(db)
+            .into_iter()
+            .filter(|it| it.as_type_param(db).is_some())
+            .map(|it| it.id)
+            .collect();
+        if own.is_empty() {
+            return true;
+        }
+        let substs = TyBuilder::placeholder_subst(db, self.id);
+        let sig = db.callable_item_signature(self.id.into()).substitute(Interner, &substs);
+        let mut inferred: FxHashSet<TypeOrConstParamId> = hir_ty::collect_placeholders(&substs, db)
+            .into_iter()
+            .filter(|it| !own.contains(it))
+            .chain(sig.params().iter().flat_map(|it| hir_ty::collect_placeholders(it, db)))
+            .collect();
+
+        let preds: Vec<WhereClause> = db
+            .generic_predicates(self.id.into())
+            .iter()
+            .map(|pred| pred.clone().substitute(Interner, &substs).skip_binders().clone())
+            .collect();
+        loop {
+            let before = inferred.len();
+            for pred in &preds {
+                // Types of where clause are inferred once its self type is
+                let (self_ty, rest) = match pred {
+                    WhereClause::Implemented(it) => {
+                        (it.self_type_parameter(Interner), hir_ty::collect_placeholders(it, db))
+                    }
+                    WhereClause::AliasEq(AliasEq { alias: AliasTy::Projection(it), ty }) => {
+                        (it.self_type_parameter(db), hir_ty::collect_placeholders(ty, db))
+                    }
+                    _ => continue,
+                };
+                if hir_ty::collect_placeholders(&self_ty, db).iter().all(|it| inferred.contains(it))
+                {
+                    inferred.extend(rest);
+                }
+            }
+            if inferred.len() == before {
+                break;
+            }
+        }
+        own.iter().all(|it| inferred.contains(it))
+    }
+
+    /// Check if the function is declared with a `where Self: Sized` bound, which makes it
+    /// uncallable on trait objects.
+    pub fn requires_sized_self(self, db: &dyn HirDatabase) -> bool {
+        let resolver = self.id.resolver(db.upcast());
+        let Some(sized_trait) =
+            db.lang_item(resolver.krate(), LangItem::Sized).and_then(|it| it.as_trait())
+        else {
+            return false;
+        };
+        db.generic_params(self.id.into()).where_predicates.iter().any(|pred| match pred {
+            WherePredicate::TypeBound {
+                target: WherePredicateTypeTarget::TypeRef(target),
+                bound,
+            } => {
+                matches!(&**target, TypeRef::Path(path) if path.is_self_type())
+                    && matches!(
+                        &**bound,
+                        TypeBound::Path(path, TraitBoundModifier::None)
+                            if resolver.resolve_path_in_type_ns_fully(db.upcast(), path)
+                                == Some(TypeNs::TraitId(sized_trait))
+                    )
+            }
+            _ => false,
+        })
+    }
+
     pub fn is_const(self, db: &dyn HirDatabase) -> bool {
         db.function_data(self.id).has_const_kw()
     }
@@ -4291,6 +4438,17 @@ impl Type {
         self.impls_trait(db, copy_trait.into(), &[])
     }
 
+    /// Checks if the type is `Sized`, types are assumed to be sized if there is no `Sized` lang
+    /// item.
+    pub fn is_sized(&self, db: &dyn HirDatabase) -> bool {
+        let lang_item = db.lang_item(self.env.krate, LangItem::Sized);
+        let sized_trait = match lang_item {
+            Some(LangItemTarget::Trait(it)) => it,
+            _ => return true,
+        };
+        self.impls_trait(db, sized_trait.into(), &[])
+    }
+
     pub fn as_callable(&self, db: &dyn HirDatabase) -> Option<Callable> {
         let mut the_ty = &self.ty;
         let callee = match self.ty.kind(Interner) {
@@ -4352,6 +4510,10 @@ impl Type {
         matches!(self.ty.kind(Interner), TyKind::Raw(..))
     }
 
+    pub fn is_mutable_raw_ptr(&self) -> bool {
+        matches!(self.ty.kind(Interner), TyKind::Raw(hir_ty::Mutability::Mut, ..))
+    }
+
     pub fn remove_raw_ptr(&self) -> Option<Type> {
         if let TyKind::Raw(_, ty) = self.ty.kind(Interner) {
             Some(self.derived(ty.clone()))
@@ -4378,7 +4540,13 @@ impl Type {
                     substs.iter(Interner).filter_map(|a| a.ty(Interner)).any(go)
                 }
 
-                TyKind::Array(_ty, len) if len.is_unknown() => true,
+                // Lengths given by constant generic parameters are known
+                TyKind::Array(_ty, len)
+                    if !matches!(len.data(Interner).value, hir_ty::ConstValue::Placeholder(_))
+                        && len.is_unknown() =>
+                {
+                    true
+                }
                 TyKind::Array(ty, _)
                 | TyKind::Slice(ty)
                 | TyKind::Raw(_, ty)
@@ -4436,6 +4604,18 @@ impl Type {
         }
     }
 
+    /// Like [`Type::as_array`], but for the arrays with length given by constant generic parameter
+    /// (`[T; N]`)
+    pub fn as_array_with_const_param_len(
+        &self,
+        db: &dyn HirDatabase,
+    ) -> Option<(Type, ConstParam)> {
+        let TyKind::Array(ty, len) = &self.ty.kind(Interner) else { return None };
+        let hir_ty::ConstValue::Placeholder(idx) = &len.data(Interner).value else { return None };
+        let id = ConstParamId::from_unchecked(hir_ty::from_placeholder_idx(db, *idx));
+        Some((self.derived(ty.clone()), ConstParam { id }))
+    }
+
     pub fn fingerprint_for_trait_impl(&self) -> Option<TyFingerprint> {
         TyFingerprint::for_trait_impl(&self.ty)
     }
@@ -4734,6 +4914,27 @@ impl Type {
         self.ty.dyn_trait().map(Into::into)
     }
 
+    /// If the type is `dyn Trait + ...`, returns all the traits listed in the trait object
+    /// including auto traits such as `Send`.
+    pub fn as_dyn_traits(&self) -> Option<Vec<Trait>> {
+        match self.ty.kind(Interner) {
+            TyKind::Dyn(bounds) => Some(
+                bounds
+                    .bounds
+                    .skip_binders()
+                    .iter(Interner)
+                    .filter_map(|pred| match pred.skip_binders() {
+                        WhereClause::Implemented(trait_ref) => {
+                            Some(Trait::from(trait_ref.hir_trait_id()))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
     /// If a type can be represented as `dyn Trait`, returns all traits accessible via this type,
     /// or an empty iterator otherwise.
     pub fn applicable_inherent_traits<'a>(
@@ -4889,11 +5090,20 @@ impl Type {
     ///
     /// This means that placeholder types are not considered to unify if there are any bounds set on
     /// them. For example `Option<T>` and `Option<U>` do not unify as we cannot show that `T = U`
+    ///
+    /// Lifetimes do not affect the result, `&'a T` unifies with `&'b T`.
     pub fn could_unify_with_deeply(&self, db: &dyn HirDatabase, other: &Type) -> bool {
         let tys = hir_ty::replace_errors_with_variables(&(self.ty.clone(), other.ty.clone()));
         hir_ty::could_unify_deeply(db, self.env.clone(), &tys)
     }
 
+    /// Normalize associated type projections in the type, for example `<S as Trait>::Assoc`
+    /// becomes the type set in the impl of `Trait` for `S`.
+    pub fn normalize(&self, db: &dyn HirDatabase) -> Type {
+        let canonical = hir_ty::replace_errors_with_variables(&self.ty);
+        self.derived(hir_ty::normalize_associated_types(db, self.env.clone(), &canonical))
+    }
+
     pub fn could_coerce_to(&self, db: &dyn HirDatabase, to: &Type) -> bool {
         let tys = hir_ty::replace_errors_with_variables(&(self.ty.clone(), to.ty.clone()));
         hir_ty::could_coerce(db, self.env.clone(), &tys)
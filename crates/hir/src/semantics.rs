@@ -1669,6 +1669,20 @@ pub struct SemanticsScope<'a> {
     resolver: Resolver,
 }
 
+impl<'a> SemanticsScope<'a> {
+    /// Scope of the items visible in `module`, without any locals or generic parameters
+    ///
+    /// Unlike the scopes from [`Semantics`] this needs no syntax, so it can be built by analyses
+    /// working on the database directly, such as term search.
+    pub fn for_module(db: &'a dyn HirDatabase, module: Module) -> Self {
+        SemanticsScope {
+            db,
+            file_id: module.definition_source_file_id(db),
+            resolver: module.id.resolver(db.upcast()),
+        }
+    }
+}
+
 impl SemanticsScope<'_> {
     pub fn module(&self) -> Module {
         Module { id: self.resolver.module() }
@@ -1,22 +1,56 @@
 //! Term search
 
-use hir_def::type_ref::Mutability;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BinaryHeap,
+    fmt,
+    hash::BuildHasherDefault,
+    iter, mem,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use cfg::{CfgAtom, CfgExpr};
+use hir_def::{lang_item::LangItem, type_ref::Mutability, DefWithBodyId};
 use hir_ty::db::HirDatabase;
+use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
-use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 
-use crate::{ModuleDef, ScopeDef, Semantics, SemanticsScope, Type};
+use crate::{
+    Adt, AsAssocItem, AssocItem, AssocItemContainer, AttrsWithOwner, Function, GenericParam,
+    HasAttrs, HasVisibility, ModPath, ModuleDef, Name, PathKind, ScopeDef, Semantics,
+    SemanticsScope, Trait, Type,
+};
 
 mod expr;
-pub use expr::Expr;
+pub use expr::{ArrayLen, Expr};
+
+// Types are hashed by their interned pointers, so keep the insertion order to make the output of
+// term search deterministic
+type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<FxHasher>>;
+type FxIndexSet<K> = IndexSet<K, BuildHasherDefault<FxHasher>>;
 
 mod tactics;
+#[cfg(test)]
+mod tests;
 
 /// Key for lookup table to query new types reached.
 #[derive(Debug, Hash, PartialEq, Eq)]
 enum NewTypesKey {
     ImplMethod,
+    BuilderChain,
     StructProjection,
+    Deref,
+    TraitObject,
+    BoxPin,
+    Conversion,
+    FirstElement,
+    Index,
+    TryOperator,
+    IteratorAdapter,
+    Closure,
 }
 
 /// Helper enum to squash big number of alternative trees into `Many` variant as there is too many
@@ -24,7 +58,7 @@ enum NewTypesKey {
 #[derive(Debug)]
 enum AlternativeExprs {
     /// There are few trees, so we keep track of them all
-    Few(FxHashSet<Expr>),
+    Few(FxIndexSet<Expr>),
     /// There are too many trees to keep track of
     Many,
 }
@@ -52,24 +86,30 @@ impl AlternativeExprs {
         }
     }
 
-    /// Extend alternative expressions
+    /// Extend alternative expressions, returns the trees dropped if they get squashed to `Many`
     ///
     /// # Arguments
     /// `threshold` - threshold value for many trees (more than that is many)
     /// `exprs` - expressions iterator
-    fn extend_with_threshold(&mut self, threshold: usize, exprs: impl Iterator<Item = Expr>) {
+    fn extend_with_threshold(
+        &mut self,
+        threshold: usize,
+        exprs: impl Iterator<Item = Expr>,
+    ) -> Vec<Expr> {
         match self {
             AlternativeExprs::Few(tts) => {
                 for it in exprs {
                     if tts.len() > threshold {
+                        let squashed = mem::take(tts).into_iter().chain(iter::once(it)).collect();
                         *self = AlternativeExprs::Many;
-                        break;
+                        return squashed;
                     }
 
                     tts.insert(it);
                 }
+                Vec::new()
             }
-            AlternativeExprs::Many => (),
+            AlternativeExprs::Many => Vec::new(),
         }
     }
 
@@ -87,10 +127,9 @@ impl AlternativeExprs {
 /// iteration as well as keeping track of which `ScopeDef` items have been used.
 /// Both of them are to speed up the term search by leaving out types / ScopeDefs that likely do
 /// not produce any new results.
-#[derive(Default, Debug)]
 struct LookupTable {
     /// All the `Expr`s in "value" produce the type of "key"
-    data: FxHashMap<Type, AlternativeExprs>,
+    data: FxIndexMap<Type, AlternativeExprs>,
     /// New types reached since last query by the `NewTypesKey`
     new_types: FxHashMap<NewTypesKey, Vec<Type>>,
     /// ScopeDefs that are not interesting any more
@@ -100,27 +139,133 @@ struct LookupTable {
     /// Amount of rounds since scopedef was first used.
     rounds_since_sopedef_hit: FxHashMap<ScopeDef, u32>,
     /// Types queried but not present
-    types_wishlist: FxHashSet<Type>,
+    types_wishlist: FxIndexSet<Type>,
+    /// Types inserted or extended in current round
+    round_types: FxIndexSet<Type>,
+    /// Amount of rounds since type last helped to build a type relevant for the goal
+    rounds_since_type_hit: FxHashMap<Type, u32>,
+    /// Types not used to build new types any more
+    exhausted_types: FxHashSet<Type>,
+    /// Rounds without hits after which types get exhausted, `None` to never exhaust them
+    max_idle_type_rounds: Option<u32>,
     /// Threshold to squash trees to `Many`
     many_threshold: usize,
+    /// Threshold to squash trees of the goal type to `Many`
+    goal_many_threshold: usize,
+    /// Never squash trees of the goal type, from `TermSearchConfig::exhaustive_goal`
+    exhaustive_goal: bool,
+    /// Maximum amount of references to add when searching with `find_autoref`
+    max_autoref_depth: usize,
+    /// Types never inserted to the table
+    forbidden_types: Vec<Type>,
+    /// Type we are searching for
+    goal: Type,
+    /// Predicate to check if reached type can be used in place of wanted type
+    unify: UnifyFn,
+    /// Amount of times `unify` was called when searching the table
+    unify_calls: Cell<usize>,
+    /// Trait implementations checked during the search, shared with the tactics
+    impls: Rc<ImplsCache>,
+    /// Types the closure tactic already searched closure bodies for
+    closure_goals: FxHashSet<Type>,
+    /// Amount of closures the search is for the body of, closures are only built up to
+    /// `TermSearchConfig::max_closure_depth` levels deep
+    closure_depth: usize,
+    /// Time after which no new rounds are started, from `TermSearchConfig::soft_deadline` of the
+    /// outermost search so that the searches for closure bodies do not restart it
+    deadline: Option<Instant>,
+    /// Candidates discarded by the tactics, see `LookupTable::reject`
+    #[cfg(feature = "term-search-dump")]
+    rejections: RefCell<FxIndexSet<(RejectReason, ModuleDef)>>,
 }
 
 impl LookupTable {
     /// Initialize lookup table
-    fn new(many_threshold: usize, goal: Type) -> Self {
-        let mut res = Self { many_threshold, ..Default::default() };
+    fn new(config: &TermSearchConfig, goal: Type, unify: UnifyFn) -> Self {
+        let mut res = Self {
+            data: Default::default(),
+            new_types: Default::default(),
+            exhausted_scopedefs: Default::default(),
+            round_scopedef_hits: Default::default(),
+            rounds_since_sopedef_hit: Default::default(),
+            types_wishlist: Default::default(),
+            round_types: Default::default(),
+            rounds_since_type_hit: Default::default(),
+            exhausted_types: Default::default(),
+            max_idle_type_rounds: config.max_idle_type_rounds,
+            many_threshold: config.many_alternatives_threshold,
+            goal_many_threshold: config.goal_many_alternatives_threshold,
+            exhaustive_goal: config.exhaustive_goal,
+            max_autoref_depth: config.max_autoref_depth,
+            forbidden_types: config.forbidden_types.clone(),
+            goal: goal.clone(),
+            unify,
+            unify_calls: Cell::new(0),
+            impls: Default::default(),
+            closure_goals: Default::default(),
+            closure_depth: 0,
+            deadline: config.soft_deadline.map(|it| Instant::now() + it),
+            #[cfg(feature = "term-search-dump")]
+            rejections: Default::default(),
+        };
         res.new_types.insert(NewTypesKey::ImplMethod, Vec::new());
+        res.new_types.insert(NewTypesKey::BuilderChain, Vec::new());
         res.new_types.insert(NewTypesKey::StructProjection, Vec::new());
+        res.new_types.insert(NewTypesKey::Deref, Vec::new());
+        res.new_types.insert(NewTypesKey::TraitObject, Vec::new());
+        res.new_types.insert(NewTypesKey::BoxPin, Vec::new());
+        res.new_types.insert(NewTypesKey::Conversion, Vec::new());
+        res.new_types.insert(NewTypesKey::FirstElement, Vec::new());
+        res.new_types.insert(NewTypesKey::Index, Vec::new());
+        res.new_types.insert(NewTypesKey::TryOperator, Vec::new());
+        res.new_types.insert(NewTypesKey::IteratorAdapter, Vec::new());
+        res.new_types.insert(NewTypesKey::Closure, Vec::new());
         res.types_wishlist.insert(goal);
         res
     }
 
+    /// Record that the tactic discarded `def` for `reason`, only kept with the
+    /// `term-search-dump` feature
+    ///
+    /// Always returns `None`, so that the tactics can discard the candidate with
+    /// `return lookup.reject(..)`.
+    fn reject<T>(&self, reason: RejectReason, def: impl Into<ModuleDef>) -> Option<T> {
+        #[cfg(feature = "term-search-dump")]
+        self.rejections.borrow_mut().insert((reason, def.into()));
+        #[cfg(not(feature = "term-search-dump"))]
+        let _ = (reason, def);
+        None
+    }
+
+    /// Record that the trees squashed to `Expr::Many` were discarded for having too many
+    /// alternatives, reported by the outermost items used to build them
+    fn reject_squashed(&self, exprs: &[Expr]) {
+        if !cfg!(feature = "term-search-dump") {
+            return;
+        }
+        for def in exprs.iter().filter_map(|it| used_items(it).into_iter().next()) {
+            self.reject::<()>(RejectReason::TooManyAlternatives, def);
+        }
+    }
+
+    /// Check if `ty` can be used in place of `other` with the unification predicate of the table
+    fn could_unify(&self, db: &dyn HirDatabase, ty: &Type, other: &Type) -> bool {
+        self.unify_calls.set(self.unify_calls.get() + 1);
+        (self.unify)(db, ty, other)
+    }
+
+    /// Cache of trait implementations, cloned by the tactics to check implementations while
+    /// searching the table
+    fn impls(&self) -> Rc<ImplsCache> {
+        self.impls.clone()
+    }
+
     /// Find all `Expr`s that unify with the `ty`
     fn find(&mut self, db: &dyn HirDatabase, ty: &Type) -> Option<Vec<Expr>> {
         let res = self
             .data
             .iter()
-            .find(|(t, _)| t.could_unify_with_deeply(db, ty))
+            .find(|(t, _)| self.could_unify(db, t, ty))
             .map(|(t, tts)| tts.exprs(t));
 
         if res.is_none() {
@@ -129,7 +274,8 @@ impl LookupTable {
 
         // Collapse suggestions if there are many
         if let Some(res) = &res {
-            if res.len() > self.many_threshold {
+            if res.len() > self.many_threshold(ty) {
+                self.reject_squashed(res);
                 return Some(vec![Expr::Many(ty.clone())]);
             }
         }
@@ -137,28 +283,33 @@ impl LookupTable {
         res
     }
 
-    /// Same as find but automatically creates shared reference of types in the lookup
+    /// Same as find but automatically creates shared references of types in the lookup
     ///
     /// For example if we have type `i32` in data and we query for `&i32` it map all the type
     /// trees we have for `i32` with `Expr::Reference` and returns them.
+    /// Up to `TermSearchConfig::max_autoref_depth` references are added (`&&i32` for `&&i32`),
+    /// inner references are only taken to places (such as locals) and never to temporaries.
     fn find_autoref(&mut self, db: &dyn HirDatabase, ty: &Type) -> Option<Vec<Expr>> {
         let res = self
             .data
             .iter()
-            .find(|(t, _)| t.could_unify_with_deeply(db, ty))
+            .find(|(t, _)| self.could_unify(db, t, ty))
             .map(|(t, it)| it.exprs(t))
             .or_else(|| {
-                self.data
-                    .iter()
-                    .find(|(t, _)| {
-                        Type::reference(t, Mutability::Shared).could_unify_with_deeply(db, ty)
-                    })
-                    .map(|(t, it)| {
-                        it.exprs(t)
-                            .into_iter()
-                            .map(|expr| Expr::Reference(Box::new(expr)))
-                            .collect()
-                    })
+                (1..=self.max_autoref_depth).find_map(|depth| {
+                    let (t, it) = self.data.iter().find(|(t, _)| {
+                        let referenced = (0..depth)
+                            .fold((*t).clone(), |it, _| Type::reference(&it, Mutability::Shared));
+                        self.could_unify(db, &referenced, ty)
+                    })?;
+                    let exprs: Vec<Expr> = it
+                        .exprs(t)
+                        .into_iter()
+                        .filter(|expr| depth == 1 || is_place(expr))
+                        .map(|expr| (0..depth).fold(expr, |it, _| Expr::Reference(Box::new(it))))
+                        .collect();
+                    (!exprs.is_empty()).then_some(exprs)
+                })
             });
 
         if res.is_none() {
@@ -167,7 +318,8 @@ impl LookupTable {
 
         // Collapse suggestions if there are many
         if let Some(res) = &res {
-            if res.len() > self.many_threshold {
+            if res.len() > self.many_threshold(ty) {
+                self.reject_squashed(res);
                 return Some(vec![Expr::Many(ty.clone())]);
             }
         }
@@ -180,26 +332,66 @@ impl LookupTable {
     /// Note that the types have to be the same, unification is not enough as unification is not
     /// transitive. For example Vec<i32> and FxHashSet<i32> both unify with Iterator<Item = i32>,
     /// but they clearly do not unify themselves.
-    fn insert(&mut self, ty: Type, exprs: impl Iterator<Item = Expr>) {
-        match self.data.get_mut(&ty) {
+    fn insert(&mut self, db: &dyn HirDatabase, ty: Type, exprs: impl Iterator<Item = Expr>) {
+        if self.is_forbidden(db, &ty) {
+            return;
+        }
+        let threshold = self.many_threshold(&ty);
+        if self.max_idle_type_rounds.is_some() {
+            self.round_types.insert(ty.clone());
+        }
+        let squashed = match self.data.get_mut(&ty) {
             Some(it) => {
-                it.extend_with_threshold(self.many_threshold, exprs);
+                let squashed = it.extend_with_threshold(threshold, exprs);
                 if it.is_many() {
-                    self.types_wishlist.remove(&ty);
+                    self.types_wishlist.shift_remove(&ty);
                 }
+                squashed
             }
             None => {
-                self.data.insert(ty.clone(), AlternativeExprs::new(self.many_threshold, exprs));
+                let mut alternatives = AlternativeExprs::Few(Default::default());
+                let squashed = alternatives.extend_with_threshold(threshold, exprs);
+                self.data.insert(ty.clone(), alternatives);
                 for it in self.new_types.values_mut() {
                     it.push(ty.clone());
                 }
+                squashed
             }
+        };
+        self.reject_squashed(&squashed);
+    }
+
+    /// Replace the type trees of `ty` already in the table with `exprs`
+    fn replace(&mut self, ty: &Type, exprs: Vec<Expr>) {
+        let threshold = self.many_threshold(ty);
+        if let Some(it) = self.data.get_mut(ty) {
+            *it = AlternativeExprs::new(threshold, exprs.into_iter());
+        }
+    }
+
+    /// Check if `ty` is one of `TermSearchConfig::forbidden_types` that are never constructed
+    ///
+    /// Unknown type arguments of the forbidden types (as in `Adt::ty`) match any type, deep
+    /// unification alone would treat them as errors that match nothing.
+    fn is_forbidden(&self, db: &dyn HirDatabase, ty: &Type) -> bool {
+        self.forbidden_types.iter().any(|it| match it.contains_unknown() {
+            true => ty.could_unify_with(db, it),
+            false => ty.could_unify_with_deeply(db, it),
+        })
+    }
+
+    /// Threshold to squash trees of type `ty` to `Many`
+    fn many_threshold(&self, ty: &Type) -> usize {
+        match *ty == self.goal {
+            true if self.exhaustive_goal => usize::MAX,
+            true => self.goal_many_threshold,
+            false => self.many_threshold,
         }
     }
 
-    /// Iterate all the reachable types
+    /// Iterate all the reachable types that are not exhausted
     fn iter_types(&self) -> impl Iterator<Item = Type> + '_ {
-        self.data.keys().cloned()
+        self.data.keys().filter(|it| !self.exhausted_types.contains(*it)).cloned()
     }
 
     /// Query new types reached since last query by key
@@ -238,22 +430,239 @@ impl LookupTable {
         self.round_scopedef_hits.clear();
     }
 
+    /// Mark types that have not helped to build types relevant for the goal for
+    /// `TermSearchConfig::max_idle_type_rounds` rounds as exhausted
+    ///
+    /// Types relevant for the goal are the goal itself and the types queried but not found.
+    /// The types used to build them (directly or through other types) are hits of the round.
+    /// New types start as idle as the types they are built from, so that chains of types built
+    /// from idle types (such as `Some(Some(Some(x)))`) get exhausted too.
+    /// Exhausted types are no longer tried as generic arguments, but can still be found as
+    /// arguments to functions the same way as other types.
+    fn exhaust_idle_types(&mut self, db: &dyn HirDatabase) {
+        let Some(max_idle_rounds) = self.max_idle_type_rounds else { return };
+        let round_types = std::mem::take(&mut self.round_types);
+        for ty in &round_types {
+            if self.rounds_since_type_hit.contains_key(ty) {
+                continue;
+            }
+            let idle = self
+                .source_types(db, ty)
+                .iter()
+                .filter_map(|it| self.rounds_since_type_hit.get(it))
+                .min()
+                .copied()
+                .unwrap_or(0);
+            self.rounds_since_type_hit.insert(ty.clone(), idle);
+        }
+
+        let mut queue: Vec<Type> = round_types
+            .into_iter()
+            .filter(|ty| self.types_wishlist.contains(ty) || self.could_unify(db, ty, &self.goal))
+            .collect();
+        let mut hits = FxHashSet::default();
+        while let Some(ty) = queue.pop() {
+            if hits.insert(ty.clone()) {
+                queue.extend(self.source_types(db, &ty));
+            }
+        }
+
+        for (ty, idle) in self.rounds_since_type_hit.iter_mut() {
+            match hits.contains(ty) {
+                true => {
+                    *idle = 0;
+                    self.exhausted_types.remove(ty);
+                }
+                false => {
+                    *idle += 1;
+                    if *idle > max_idle_rounds {
+                        self.exhausted_types.insert(ty.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Types of the values the trees of `ty` in the table are built from
+    fn source_types(&self, db: &dyn HirDatabase, ty: &Type) -> Vec<Type> {
+        let Some(exprs) = self.data.get(ty) else { return Vec::new() };
+        exprs
+            .exprs(ty)
+            .iter()
+            .flat_map(|expr| expr.children())
+            .map(|mut it| {
+                // References are taken on the fly, the referenced types are in the table
+                while let Expr::Reference(inner) | Expr::MutReference(inner) = it {
+                    it = inner;
+                }
+                it.ty(db)
+            })
+            .collect()
+    }
+
+    /// Human-readable state of the table: the types reached with the amount of trees of each and
+    /// the exhausted `ScopeDef`s
+    #[cfg(feature = "term-search-dump")]
+    fn dump(&self, db: &dyn HirDatabase) -> String {
+        use crate::HirDisplay;
+        use std::fmt::Write;
+
+        let mut out = String::from("types reached:\n");
+        for (ty, exprs) in &self.data {
+            let trees = match exprs {
+                AlternativeExprs::Few(exprs) => exprs.len().to_string(),
+                AlternativeExprs::Many => "many".to_owned(),
+            };
+            let exhausted = if self.exhausted_types.contains(ty) { " (exhausted)" } else { "" };
+            let _ = writeln!(out, "  {}: {trees}{exhausted}", ty.display(db));
+        }
+        let mut defs: Vec<_> = self
+            .exhausted_scopedefs
+            .iter()
+            .map(|def| {
+                let name = match def {
+                    ScopeDef::ModuleDef(it) => it.name(db),
+                    ScopeDef::GenericParam(it) => Some(it.name(db)),
+                    ScopeDef::Local(it) => Some(it.name(db)),
+                    _ => None,
+                };
+                match name {
+                    Some(name) => name.display(db.upcast()).to_string(),
+                    None => format!("{def:?}"),
+                }
+            })
+            .collect();
+        defs.sort();
+        let _ = writeln!(out, "exhausted scopedefs: {}", defs.join(", "));
+        out
+    }
+
     /// Get exhausted `ScopeDef`s
     fn exhausted_scopedefs(&self) -> &FxHashSet<ScopeDef> {
         &self.exhausted_scopedefs
     }
 
     /// Types queried but not found
-    fn types_wishlist(&mut self) -> &FxHashSet<Type> {
+    fn types_wishlist(&mut self) -> &FxIndexSet<Type> {
         &self.types_wishlist
     }
+
+    /// Merge other lookup table into this one, such as a table of values found separately to
+    /// seed the search with
+    ///
+    /// Expressions of types present in both tables are subject to the threshold of this table, so
+    /// the merged types may get squashed to `Many`.
+    /// Types not present in this table before the merge are new for all the `NewTypesKey`s.
+    pub(crate) fn merge(&mut self, db: &dyn HirDatabase, other: LookupTable) {
+        for (ty, exprs) in other.data {
+            match exprs {
+                AlternativeExprs::Few(exprs) => self.insert(db, ty, exprs.into_iter()),
+                AlternativeExprs::Many => {
+                    if !self.data.contains_key(&ty) {
+                        for it in self.new_types.values_mut() {
+                            it.push(ty.clone());
+                        }
+                    }
+                    self.types_wishlist.shift_remove(&ty);
+                    self.data.insert(ty, AlternativeExprs::Many);
+                }
+            }
+        }
+        self.types_wishlist.extend(
+            other
+                .types_wishlist
+                .into_iter()
+                .filter(|ty| !self.data.get(ty).is_some_and(|it| it.is_many())),
+        );
+        self.exhausted_scopedefs.extend(other.exhausted_scopedefs);
+        self.round_scopedef_hits.extend(other.round_scopedef_hits);
+        for (def, hits) in other.rounds_since_sopedef_hit {
+            let it = self.rounds_since_sopedef_hit.entry(def).or_insert(hits);
+            *it = (*it).max(hits);
+        }
+        self.round_types.extend(other.round_types);
+        for (ty, idle) in other.rounds_since_type_hit {
+            let it = self.rounds_since_type_hit.entry(ty).or_insert(idle);
+            *it = (*it).min(idle);
+        }
+        self.exhausted_types.extend(
+            other.exhausted_types.into_iter().filter(|ty| self.rounds_since_type_hit[ty] > 0),
+        );
+        self.unify_calls.set(self.unify_calls.get() + other.unify_calls.get());
+        if !Rc::ptr_eq(&self.impls, &other.impls) {
+            self.impls.cache.borrow_mut().extend(other.impls.cache.take());
+            self.impls.hits.set(self.impls.hits.get() + other.impls.hits.get());
+            self.impls.misses.set(self.impls.misses.get() + other.impls.misses.get());
+        }
+    }
+}
+
+/// Answers of the trait solver to "does the type implement the trait?" for a single search
+///
+/// The tactics keep asking about the same types and traits (such as `Default`, `ToString` or
+/// `Into`), so the answers are computed lazily and kept for the whole search.
+#[derive(Debug, Default)]
+struct ImplsCache {
+    /// Result of the trait solver by the type, trait and the trait arguments
+    cache: RefCell<FxHashMap<(Type, Trait, Vec<Type>), bool>>,
+    /// Amount of queries answered from `cache`
+    hits: Cell<usize>,
+    /// Amount of queries that had to run the trait solver
+    misses: Cell<usize>,
+}
+
+impl ImplsCache {
+    /// Check if `ty` implements `trait_` with the given trait arguments
+    fn impls_trait(&self, db: &dyn HirDatabase, ty: &Type, trait_: Trait, args: &[Type]) -> bool {
+        let key = (ty.clone(), trait_, args.to_vec());
+        if let Some(&it) = self.cache.borrow().get(&key) {
+            self.hits.set(self.hits.get() + 1);
+            return it;
+        }
+        self.misses.set(self.misses.get() + 1);
+        let it = ty.impls_trait(db, trait_, args);
+        self.cache.borrow_mut().insert(key, it);
+        it
+    }
+}
+
+/// Predicate used by term search to check if the type reached (first type argument) can be used
+/// where the other type is expected.
+///
+/// Default is [`Type::could_unify_with_deeply`], custom predicates can be used for example to only
+/// allow exact type matches, or to consult state kept by the caller.
+pub type UnifyFn = Arc<dyn Fn(&dyn HirDatabase, &Type, &Type) -> bool>;
+
+/// Cost of a solution used by term search to order the solutions, the ones with lower cost come
+/// first. See `TermSearchConfig::score_fn`.
+pub type ScoreFn = fn(&dyn HirDatabase, &Expr) -> f64;
+
+/// Default predicate for checking if types match in term search
+fn could_unify_deeply() -> UnifyFn {
+    Arc::new(|db, ty, other| ty.could_unify_with_deeply(db, other))
+}
+
+/// The part of [`Semantics`] term search depends on
+///
+/// Term search only needs the database from `Semantics`, everything else about the hole comes
+/// from its [`SemanticsScope`]. Hosts without a `Semantics` (such as tools or tests working on a
+/// database directly) can implement this and build the scope with
+/// [`SemanticsScope::for_module`].
+pub trait TermSearchSema: fmt::Debug {
+    fn db(&self) -> &dyn HirDatabase;
+}
+
+impl<DB: HirDatabase> TermSearchSema for Semantics<'_, DB> {
+    fn db(&self) -> &dyn HirDatabase {
+        self.db
+    }
 }
 
 /// Context for the `term_search` function
 #[derive(Debug)]
-pub struct TermSearchCtx<'a, DB: HirDatabase> {
-    /// Semantics for the program
-    pub sema: &'a Semantics<'a, DB>,
+pub struct TermSearchCtx<'a> {
+    /// Semantics for the program, usually [`Semantics`]
+    pub sema: &'a dyn TermSearchSema,
     /// Semantic scope, captures context for the term search
     pub scope: &'a SemanticsScope<'a>,
     /// Target / expected output type
@@ -262,20 +671,800 @@ pub struct TermSearchCtx<'a, DB: HirDatabase> {
     pub config: TermSearchConfig,
 }
 
+impl TermSearchCtx<'_> {
+    /// Check if item is excluded from the term search by `TermSearchConfig::blocklist`
+    ///
+    /// Items are matched by the path to their definition, so `crate::foo::bar` blocks function
+    /// `bar` in module `foo` of current crate and `crate::foo` blocks everything in that module.
+    /// Paths starting with crate name (such as `core::mem::take`) match items in other crates.
+    fn is_blocked(&self, def: ModuleDef) -> bool {
+        if self.config.blocklist.is_empty() {
+            return false;
+        }
+        let db = self.sema.db();
+        let Some((krate, segments)) = def_path(db, def) else { return false };
+        let crate_name = krate.display_name(db).map(|it| it.crate_name().to_string());
+
+        self.config.blocklist.iter().any(|path| {
+            match self.segments_in_crate(path, krate, crate_name.as_deref()) {
+                Some(blocked) => !blocked.is_empty() && segments.starts_with(blocked),
+                None => false,
+            }
+        })
+    }
+
+    /// Score of the expression by `TermSearchConfig::ranking_hints`
+    ///
+    /// Sum of the hints of all the items used in the expression, hints match the path to the
+    /// definition of the item exactly.
+    fn ranking_score(&self, expr: &Expr) -> f32 {
+        let db = self.sema.db();
+        used_items(expr)
+            .into_iter()
+            .filter_map(|def| {
+                let (krate, segments) = def_path(db, def)?;
+                let crate_name = krate.display_name(db).map(|it| it.crate_name().to_string());
+                let score: f32 = self
+                    .config
+                    .ranking_hints
+                    .iter()
+                    .filter(|(path, _)| {
+                        self.segments_in_crate(path, krate, crate_name.as_deref())
+                            == Some(segments.as_slice())
+                    })
+                    .map(|(_, score)| score)
+                    .sum();
+                Some(score)
+            })
+            .sum()
+    }
+
+    /// Segments of the path relative to the root of `krate`, `None` if the path points to other
+    /// crate
+    fn segments_in_crate<'p>(
+        &self,
+        path: &'p ModPath,
+        krate: crate::Crate,
+        crate_name: Option<&str>,
+    ) -> Option<&'p [Name]> {
+        match path.kind {
+            PathKind::Crate if krate == self.scope.krate() => Some(path.segments()),
+            PathKind::Plain | PathKind::Abs => match path.segments().split_first() {
+                Some((first, rest)) if first.as_str() == crate_name => Some(rest),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Check if item is excluded from the term search because it is only compiled for tests
+    /// (`#[cfg(test)]`) and the term search target is not in test code.
+    ///
+    /// Items count as test only if they or any of their parents (impl, trait or modules) have
+    /// `test` cfg. Test code is also code inside of `#[test]` functions.
+    fn is_test_only(&self, def: ModuleDef) -> bool {
+        let db = self.sema.db();
+        if !is_in_test_cfg(db, def) {
+            return false;
+        }
+        if !self.config.enable_test_items {
+            return true;
+        }
+        let in_test_fn = match self.scope.resolver().body_owner() {
+            Some(DefWithBodyId::FunctionId(it)) => {
+                let func = Function::from(it);
+                func.is_test(db) || is_in_test_cfg(db, ModuleDef::Function(func))
+            }
+            _ => false,
+        };
+        !in_test_fn && !is_in_test_cfg(db, ModuleDef::Module(self.scope.module()))
+    }
+
+    /// Check if function is excluded from the term search because it can panic and
+    /// `TermSearchConfig::enable_panicking` is not set.
+    ///
+    /// Only well known accessors of standard library such as `Option::unwrap` are considered.
+    fn is_panicking(&self, func: Function) -> bool {
+        if self.config.enable_panicking {
+            return false;
+        }
+        let db = self.sema.db();
+        let Some((krate, segments)) = def_path(db, ModuleDef::Function(func)) else {
+            return false;
+        };
+        if !krate.origin(db).is_lang() {
+            return false;
+        }
+        let [.., adt, func] = segments.as_slice() else { return false };
+        matches!(
+            (adt.as_str(), func.as_str()),
+            (Some("Option"), Some("unwrap" | "expect"))
+                | (Some("Result"), Some("unwrap" | "expect" | "unwrap_err" | "expect_err"))
+        )
+    }
+
+    /// Get `Default::default()` expression for the type if `TermSearchConfig::enable_default_fields`
+    /// is set and the type implements `Default`
+    fn default_value(&self, lookup: &LookupTable, ty: &Type) -> Option<Expr> {
+        if !self.config.enable_default_fields {
+            return None;
+        }
+        let default_trait = self.lang_trait("default", "Default")?;
+        lookup
+            .impls
+            .impls_trait(self.sema.db(), ty, default_trait, &[])
+            .then(|| Expr::FamousType { ty: ty.clone(), value: DEFAULT_VALUE })
+    }
+
+    /// Find trait from standard library that is in scope by its module and name
+    fn lang_trait(&self, module: &str, name: &str) -> Option<Trait> {
+        let db = self.sema.db();
+        let is_wanted = |it: &Trait| match def_path(db, ModuleDef::Trait(*it)) {
+            Some((krate, segments)) if krate.origin(db).is_lang() => {
+                let [.., m, n] = segments.as_slice() else { return false };
+                m.as_str() == Some(module) && n.as_str() == Some(name)
+            }
+            _ => false,
+        };
+        self.scope.visible_traits().iter().map(|&it| Trait::from(it)).find(is_wanted)
+    }
+}
+
+/// Source code of the value used for fields filled by `Default` trait
+const DEFAULT_VALUE: &str = "Default::default()";
+
+/// Check if the item or any of its parents is only compiled with `test` cfg
+fn is_in_test_cfg(db: &dyn HirDatabase, def: ModuleDef) -> bool {
+    let has_test_cfg = |attrs: Option<AttrsWithOwner>| {
+        attrs.and_then(|it| it.cfg()).map_or(false, |it| requires_test(&it))
+    };
+    if has_test_cfg(def.attrs(db)) {
+        return true;
+    }
+    let container = match def {
+        ModuleDef::Function(it) => it.as_assoc_item(db).map(|it| it.container(db)),
+        ModuleDef::Const(it) => it.as_assoc_item(db).map(|it| it.container(db)),
+        _ => None,
+    };
+    let container_attrs = match container {
+        Some(AssocItemContainer::Impl(it)) => Some(it.attrs(db)),
+        Some(AssocItemContainer::Trait(it)) => Some(it.attrs(db)),
+        None => None,
+    };
+    if has_test_cfg(container_attrs) {
+        return true;
+    }
+    let module = match def {
+        ModuleDef::Module(it) => Some(it),
+        _ => def.module(db),
+    };
+    module.map_or(false, |it| {
+        it.path_to_root(db).into_iter().any(|it| has_test_cfg(Some(it.attrs(db))))
+    })
+}
+
+/// Check if cfg expression can only be enabled together with `test`
+fn requires_test(cfg: &CfgExpr) -> bool {
+    match cfg {
+        CfgExpr::Atom(CfgAtom::Flag(it)) => it == "test",
+        CfgExpr::All(it) => it.iter().any(requires_test),
+        CfgExpr::Any(it) => !it.is_empty() && it.iter().all(requires_test),
+        CfgExpr::Invalid | CfgExpr::Atom(_) | CfgExpr::Not(_) => false,
+    }
+}
+
+/// Helper function to get path segments to definition of the item (starting from crate root)
+fn def_path(db: &dyn HirDatabase, def: ModuleDef) -> Option<(crate::Crate, Vec<Name>)> {
+    // Associated items are named by their parent type or trait, not by the module of the impl
+    let parent = match def {
+        ModuleDef::Function(it) => it.as_assoc_item(db).map(|it| it.container(db)),
+        ModuleDef::Const(it) => it.as_assoc_item(db).map(|it| it.container(db)),
+        _ => None,
+    };
+    let (krate, mut segments) = match parent {
+        Some(AssocItemContainer::Trait(it)) => def_path(db, ModuleDef::Trait(it))?,
+        Some(AssocItemContainer::Impl(it)) => def_path(db, it.self_ty(db).as_adt()?.into())?,
+        None => {
+            let module = def.module(db)?;
+            let mut segments: Vec<Name> =
+                module.path_to_root(db).into_iter().rev().filter_map(|it| it.name(db)).collect();
+            if let ModuleDef::Variant(it) = def {
+                segments.push(it.parent_enum(db).name(db));
+            }
+            (module.krate(), segments)
+        }
+    };
+    // Modules are named by `path_to_root`
+    if !matches!(def, ModuleDef::Module(_)) {
+        segments.push(def.name(db)?);
+    }
+    Some((krate, segments))
+}
+
 /// Configuration options for the term search
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct TermSearchConfig {
     /// Enable borrow checking, this guarantees the outputs of the `term_search` to borrow-check
     pub enable_borrowcheck: bool,
     /// Indicate when to squash multiple trees to `Many` as there are too many to keep track
     pub many_alternatives_threshold: usize,
+    /// Same as `many_alternatives_threshold`, but for the trees of the goal type, so that we can
+    /// keep more of them without exploding the amount of intermediate trees
+    pub goal_many_alternatives_threshold: usize,
     /// Fuel for term search in "units of work"
     pub fuel: u64,
+    /// Paths to items (or modules containing items) that should never be used in the output
+    pub blocklist: Vec<ModPath>,
+    /// Allow methods that can panic such as `Option::unwrap` or `Result::expect`, as well as
+    /// indexing containers (`v[i]`)
+    pub enable_panicking: bool,
+    /// Only return expressions that refer to items already in scope, so that using them does not
+    /// require adding any imports
+    pub no_new_imports: bool,
+    /// Maximum amount of arguments (not counting `self`) of function or method to call
+    pub max_fn_args: usize,
+    /// Maximum amount of references to take of a value to pass it as an argument (`&&x`)
+    pub max_autoref_depth: usize,
+    /// Seed to shuffle the order items in scope are tried in, the lookup table keeps the
+    /// insertion order so the whole search follows it.
+    /// Meant for testing only, to reproduce failures of randomized tests.
+    pub shuffle_seed: Option<u64>,
+    /// Double check that the solutions type check by verifying the arguments and trait bounds of
+    /// every call in them, this trades speed for the correctness of the output
+    pub validate: bool,
+    /// Construct structs that cannot be built from the values in scope by filling all of their
+    /// fields with `Default::default()`, requires all the fields to implement `Default`
+    pub enable_default_fields: bool,
+    /// Nudges for ranking of the solutions by paths to items they use, solutions with higher
+    /// total score come first. Solutions with equal score keep their order.
+    /// Paths are matched the same way as in `blocklist`, but only exactly.
+    pub ranking_hints: FxHashMap<ModPath, f32>,
+    /// Order of the solutions yielded by [`term_search_iter`]
+    pub stream_order: StreamOrder,
+    /// Allow items only compiled for tests (`#[cfg(test)]`) when the term search target is in
+    /// test code too, they are never used outside of tests
+    pub enable_test_items: bool,
+    /// Allow chains of two `Into::into` conversions (`a -> b -> goal`), single conversions to the
+    /// goal are always allowed
+    pub enable_conversion_chains: bool,
+    /// Allow filling raw pointer goals by casting references, off by default as raw pointers are
+    /// rarely wanted outside of unsafe code
+    pub enable_raw_pointers: bool,
+    /// Rank method calls (`x.foo()`) before the function calls taking the same arguments
+    /// (`foo(x)`), by default function calls come first as they are found first
+    pub prefer_method_syntax: bool,
+    /// Also look for functions in all the public modules of the current crate instead of only the
+    /// ones in scope, the found solutions use paths to them or require new imports
+    pub exhaustive_module_walk: bool,
+    /// Stop trying types as generic arguments once they have not helped to reach the goal or types
+    /// wanted for it for this many rounds, `None` to keep trying all the types
+    pub max_idle_type_rounds: Option<u32>,
+    /// Allow solutions doing unsafe operations such as calling `unsafe fn`s, they are rendered
+    /// inside of an `unsafe` block
+    pub allow_unsafe: bool,
+    /// Types never constructed or used in the solutions, matched with
+    /// `Type::could_unify_with_deeply`. Unknown type arguments match any type, so generic types
+    /// with unknown arguments (such as `Adt::ty`) forbid all their instances.
+    /// Unlike `blocklist` this also covers values reached without naming any item.
+    pub forbidden_types: Vec<Type>,
+    /// Wall-clock time after which no new rounds are started, the round running at the deadline is
+    /// still finished so the solutions are the same as with fewer rounds of the search.
+    /// Sets `TermSearchStats::timed_out` when hit. Unlike `fuel` this never stops mid-round.
+    pub soft_deadline: Option<Duration>,
+    /// Keep all the trees of the goal type instead of squashing them to `Many` past
+    /// `goal_many_alternatives_threshold`, so that every way to build the goal found is returned.
+    /// Trees of the other types are still squashed. Meant to be bounded by `max_solutions`.
+    pub exhaustive_goal: bool,
+    /// Maximum amount of solutions returned after ranking, `None` to return all of them
+    pub max_solutions: Option<usize>,
+    /// Maximum amount of distinct functions and constructors called in a solution, calling the
+    /// same one multiple times counts once. Unlike the depth this also limits wide flat trees.
+    /// `None` for no limit.
+    pub max_distinct_calls: Option<usize>,
+    /// Custom cost of the solutions replacing the built-in ranking of [`term_search`] and
+    /// [`Expr::complexity`] of `StreamOrder::ShortestFirst`. Called once per solution, the ones
+    /// with lower cost come first and solutions with equal cost keep their order.
+    pub score_fn: Option<ScoreFn>,
+    /// Maximum amount of distinct modules the items used in a solution come from, items of other
+    /// crates count with their own modules. `None` for no limit.
+    pub max_modules: Option<usize>,
+    /// Ranking penalty per module the items used in a solution come from beyond the first one,
+    /// so that solutions built from items of a single module come before the ones stitching
+    /// together items from many modules. Weighted against the scores of `ranking_hints`.
+    pub module_spread_weight: f32,
+    /// Rank the solutions allocating on the heap lower, by the amount of calls building new
+    /// `String`s, `Vec`s or `Box`es in them (such as `to_string` or `Box::new`), so that the
+    /// borrowing solutions come first. Weighted against the scores of `ranking_hints`.
+    pub allocation_penalty: f32,
+    /// Out of the solutions that differ only in the references taken to them (`x` and `&x`),
+    /// keep only the ones matching the reference level of the goal exactly. See
+    /// [`dedup_reference_levels`].
+    pub dedup_reference_levels: bool,
+    /// Enable building `String` values by concatenating reached strings with `+` (`a + &b`)
+    pub enable_string_concat: bool,
+    /// Enable building integers by adding two reached integers with `+` (`a + b`)
+    pub enable_integer_add: bool,
+    /// Always end the solutions of [`term_search`] with `todo!()` of the goal type, so that there
+    /// is something to suggest even if the goal was not reached
+    pub always_include_todo: bool,
+    /// Only transform the values already in scope (projections, method calls and conversions),
+    /// without building new values with constructors, factory functions or literals
+    pub transformations_only: bool,
+    /// Maximum amount of fluent builder methods returning `Self` chained on a builder
+    /// (`b.with_x(x).with_y(y)`), `0` to never call them
+    pub max_builder_chain: usize,
+    /// Maximum amount of closures nested in each other (`|x| move |y| ..` for closures returning
+    /// closures), at most 2. `0` never builds closures.
+    pub max_closure_depth: usize,
+    /// Domain specific well known values of the given types added to the ones of the famous types
+    /// tactic (such as `true` or `()`), rendered the way their trees are
+    pub extra_known_values: Vec<(Type, Expr)>,
+    /// Return type of the function the hole is in, if the `?` operator can be used at the hole
+    /// (it is not inside of a closure or an async block). Reached `Result`s are unwrapped with `?`
+    /// when the function returns a `Result` their error converts to (`Ok(foo()?)`).
+    pub enclosing_ret_ty: Option<Type>,
+}
+
+impl TermSearchConfig {
+    /// Record that the user accepted the suggested expression, so that the items it is built from
+    /// rank higher in the future searches using this config
+    pub fn record_accepted(&mut self, db: &dyn HirDatabase, expr: &Expr) {
+        for path in item_paths(db, expr) {
+            *self.ranking_hints.entry(path).or_default() += 1.0;
+        }
+    }
+
+    /// Whether `tactic` is run with this config, see `transformations_only`
+    fn runs(&self, tactic: TacticKind) -> bool {
+        !(self.transformations_only && tactic.is_construction())
+    }
 }
 
 impl Default for TermSearchConfig {
     fn default() -> Self {
-        Self { enable_borrowcheck: true, many_alternatives_threshold: 1, fuel: 400 }
+        Self {
+            enable_borrowcheck: true,
+            many_alternatives_threshold: 1,
+            goal_many_alternatives_threshold: 1,
+            fuel: 400,
+            blocklist: Vec::new(),
+            enable_panicking: false,
+            no_new_imports: false,
+            max_fn_args: 5,
+            max_autoref_depth: 2,
+            shuffle_seed: None,
+            validate: false,
+            enable_default_fields: false,
+            ranking_hints: FxHashMap::default(),
+            stream_order: StreamOrder::Tactic,
+            enable_test_items: true,
+            enable_conversion_chains: false,
+            enable_raw_pointers: false,
+            prefer_method_syntax: false,
+            exhaustive_module_walk: false,
+            max_idle_type_rounds: None,
+            allow_unsafe: false,
+            forbidden_types: Vec::new(),
+            soft_deadline: None,
+            exhaustive_goal: false,
+            max_solutions: None,
+            max_distinct_calls: None,
+            score_fn: None,
+            max_modules: None,
+            module_spread_weight: 0.0,
+            allocation_penalty: 0.0,
+            dedup_reference_levels: false,
+            enable_string_concat: false,
+            enable_integer_add: false,
+            always_include_todo: false,
+            transformations_only: false,
+            max_builder_chain: 3,
+            max_closure_depth: 1,
+            extra_known_values: Vec::new(),
+            enclosing_ret_ty: None,
+        }
+    }
+}
+
+impl TermSearchConfig {
+    /// Build the config from the portable `settings` and the parts that can only be provided by
+    /// the host, as they refer to the types and functions of the analyzed code
+    pub fn from_parts(
+        settings: TermSearchSettings,
+        forbidden_types: Vec<Type>,
+        score_fn: Option<ScoreFn>,
+        extra_known_values: Vec<(Type, Expr)>,
+    ) -> Self {
+        let TermSearchSettings {
+            enable_borrowcheck,
+            many_alternatives_threshold,
+            goal_many_alternatives_threshold,
+            fuel,
+            blocklist,
+            enable_panicking,
+            no_new_imports,
+            max_fn_args,
+            max_autoref_depth,
+            shuffle_seed,
+            validate,
+            enable_default_fields,
+            ranking_hints,
+            stream_order,
+            enable_test_items,
+            enable_conversion_chains,
+            enable_raw_pointers,
+            prefer_method_syntax,
+            exhaustive_module_walk,
+            max_idle_type_rounds,
+            allow_unsafe,
+            soft_deadline_ms,
+            exhaustive_goal,
+            max_solutions,
+            max_distinct_calls,
+            max_modules,
+            module_spread_weight,
+            allocation_penalty,
+            dedup_reference_levels,
+            enable_string_concat,
+            enable_integer_add,
+            always_include_todo,
+            transformations_only,
+            max_builder_chain,
+            max_closure_depth,
+        } = settings;
+        Self {
+            enable_borrowcheck,
+            many_alternatives_threshold,
+            goal_many_alternatives_threshold,
+            fuel,
+            blocklist: blocklist.iter().map(|it| parse_mod_path(it)).collect(),
+            enable_panicking,
+            no_new_imports,
+            max_fn_args,
+            max_autoref_depth,
+            shuffle_seed,
+            validate,
+            enable_default_fields,
+            ranking_hints: ranking_hints
+                .into_iter()
+                .map(|(path, score)| (parse_mod_path(&path), score))
+                .collect(),
+            stream_order,
+            enable_test_items,
+            enable_conversion_chains,
+            enable_raw_pointers,
+            prefer_method_syntax,
+            exhaustive_module_walk,
+            max_idle_type_rounds,
+            allow_unsafe,
+            forbidden_types,
+            soft_deadline: soft_deadline_ms.map(Duration::from_millis),
+            exhaustive_goal,
+            max_solutions,
+            max_distinct_calls,
+            score_fn,
+            max_modules,
+            module_spread_weight,
+            allocation_penalty,
+            dedup_reference_levels,
+            enable_string_concat,
+            enable_integer_add,
+            always_include_todo,
+            transformations_only,
+            max_builder_chain,
+            max_closure_depth,
+            extra_known_values,
+            enclosing_ret_ty: None,
+        }
+    }
+
+    /// Portable part of the config, inverse of [`TermSearchConfig::from_parts`]
+    pub fn settings(&self) -> TermSearchSettings {
+        TermSearchSettings {
+            enable_borrowcheck: self.enable_borrowcheck,
+            many_alternatives_threshold: self.many_alternatives_threshold,
+            goal_many_alternatives_threshold: self.goal_many_alternatives_threshold,
+            fuel: self.fuel,
+            blocklist: self.blocklist.iter().map(mod_path_to_string).collect(),
+            enable_panicking: self.enable_panicking,
+            no_new_imports: self.no_new_imports,
+            max_fn_args: self.max_fn_args,
+            max_autoref_depth: self.max_autoref_depth,
+            shuffle_seed: self.shuffle_seed,
+            validate: self.validate,
+            enable_default_fields: self.enable_default_fields,
+            ranking_hints: self
+                .ranking_hints
+                .iter()
+                .map(|(path, &score)| (mod_path_to_string(path), score))
+                .collect(),
+            stream_order: self.stream_order,
+            enable_test_items: self.enable_test_items,
+            enable_conversion_chains: self.enable_conversion_chains,
+            enable_raw_pointers: self.enable_raw_pointers,
+            prefer_method_syntax: self.prefer_method_syntax,
+            exhaustive_module_walk: self.exhaustive_module_walk,
+            max_idle_type_rounds: self.max_idle_type_rounds,
+            allow_unsafe: self.allow_unsafe,
+            soft_deadline_ms: self.soft_deadline.map(|it| it.as_millis() as u64),
+            exhaustive_goal: self.exhaustive_goal,
+            max_solutions: self.max_solutions,
+            max_distinct_calls: self.max_distinct_calls,
+            max_modules: self.max_modules,
+            module_spread_weight: self.module_spread_weight,
+            allocation_penalty: self.allocation_penalty,
+            dedup_reference_levels: self.dedup_reference_levels,
+            enable_string_concat: self.enable_string_concat,
+            enable_integer_add: self.enable_integer_add,
+            always_include_todo: self.always_include_todo,
+            transformations_only: self.transformations_only,
+            max_builder_chain: self.max_builder_chain,
+            max_closure_depth: self.max_closure_depth,
+        }
+    }
+}
+
+/// Portable part of [`TermSearchConfig`], meant for loading project-level settings
+///
+/// Holds plain data only, so that the host can deserialize it (with the `serde` feature) from its
+/// own configuration and build the config with [`TermSearchConfig::from_parts`]. Missing fields
+/// take their default values. The fields are documented on [`TermSearchConfig`], the differences
+/// are documented here.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct TermSearchSettings {
+    pub enable_borrowcheck: bool,
+    pub many_alternatives_threshold: usize,
+    pub goal_many_alternatives_threshold: usize,
+    pub fuel: u64,
+    /// Paths such as `core::mem::take` or `crate::foo`
+    pub blocklist: Vec<String>,
+    pub enable_panicking: bool,
+    pub no_new_imports: bool,
+    pub max_fn_args: usize,
+    pub max_autoref_depth: usize,
+    pub shuffle_seed: Option<u64>,
+    pub validate: bool,
+    pub enable_default_fields: bool,
+    /// Paths written the same way as in `blocklist`
+    pub ranking_hints: FxHashMap<String, f32>,
+    pub stream_order: StreamOrder,
+    pub enable_test_items: bool,
+    pub enable_conversion_chains: bool,
+    pub enable_raw_pointers: bool,
+    pub prefer_method_syntax: bool,
+    pub exhaustive_module_walk: bool,
+    pub max_idle_type_rounds: Option<u32>,
+    pub allow_unsafe: bool,
+    /// `TermSearchConfig::soft_deadline` in milliseconds
+    pub soft_deadline_ms: Option<u64>,
+    pub exhaustive_goal: bool,
+    pub max_solutions: Option<usize>,
+    pub max_distinct_calls: Option<usize>,
+    pub max_modules: Option<usize>,
+    pub module_spread_weight: f32,
+    pub allocation_penalty: f32,
+    pub dedup_reference_levels: bool,
+    pub enable_string_concat: bool,
+    pub enable_integer_add: bool,
+    pub always_include_todo: bool,
+    pub transformations_only: bool,
+    pub max_builder_chain: usize,
+    pub max_closure_depth: usize,
+}
+
+impl Default for TermSearchSettings {
+    fn default() -> Self {
+        TermSearchConfig::default().settings()
+    }
+}
+
+/// Parse path such as `crate::foo::bar` or `::core::mem`
+fn parse_mod_path(path: &str) -> ModPath {
+    let (kind, rest) = match path.strip_prefix("::") {
+        Some(rest) => (PathKind::Abs, rest),
+        None => match path.strip_prefix("crate::") {
+            Some(rest) => (PathKind::Crate, rest),
+            None if path == "crate" => (PathKind::Crate, ""),
+            None => (PathKind::Plain, path),
+        },
+    };
+    let segments = rest
+        .split("::")
+        .map(str::trim)
+        .filter(|it| !it.is_empty())
+        .map(|it| Name::new_text_dont_use(it.into()));
+    ModPath::from_segments(kind, segments)
+}
+
+/// Inverse of `parse_mod_path`
+fn mod_path_to_string(path: &ModPath) -> String {
+    let prefix = match path.kind {
+        PathKind::Abs => "::",
+        PathKind::Crate if path.segments().is_empty() => "crate",
+        PathKind::Crate => "crate::",
+        _ => "",
+    };
+    let segments = path.segments().iter().map(|it| it.to_smol_str());
+    format!("{prefix}{}", segments.format("::"))
+}
+
+/// Statistics collected during the term search
+///
+/// Meant for tuning the heuristics and reporting why the search did not produce the expected
+/// results.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TermSearchStats {
+    /// Amount of rounds the tactics were run for
+    pub rounds_run: usize,
+    /// Amount of distinct types in the lookup table at the end of the search
+    pub types_reached: usize,
+    /// Amount of unifications done when searching the lookup table for types
+    pub unify_calls: usize,
+    /// Amount of solutions found before removing duplicates
+    pub solutions_pre_dedup: usize,
+    /// Amount of solutions returned
+    pub solutions_post_dedup: usize,
+    /// Amount of solutions yielded by each of the tactics
+    pub per_tactic_counts: FxHashMap<TacticKind, usize>,
+    /// Biggest [`Expr::depth`] of the solutions returned
+    pub max_depth: usize,
+    /// Amount of types exhausted by `TermSearchConfig::max_idle_type_rounds` at the end of the
+    /// search
+    pub types_exhausted: usize,
+    /// Amount of checks if a type implements a trait done by the tactics
+    pub trait_queries: usize,
+    /// Amount of the trait implementation checks answered from the cache
+    pub trait_cache_hits: usize,
+    /// Amount of the trait implementation checks that were not answered from the cache and had to
+    /// run the trait solver
+    pub trait_solver_calls: usize,
+    /// The search stopped at a round boundary because `TermSearchConfig::soft_deadline` passed
+    pub timed_out: bool,
+    /// No solutions were found, but items of a collection reached are of the goal type or have a
+    /// field of it. Such goals usually need a loop over the collection (such as summing the
+    /// fields of the items), that term search cannot write.
+    pub likely_needs_iteration: bool,
+    /// State of the lookup table at the end of every round, see `LookupTable::dump`
+    #[cfg(feature = "term-search-dump")]
+    pub round_dumps: Vec<String>,
+    /// Candidates discarded by the tactics in the order they were first discarded, see
+    /// [`term_search_with_rejections`]
+    #[cfg(feature = "term-search-dump")]
+    pub rejections: Vec<(RejectReason, ModuleDef)>,
+}
+
+/// Reason the term search discarded a candidate item
+///
+/// Candidates are discarded every time a tactic tries them, so items that are used with other
+/// generic arguments or in later rounds can be reported as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectReason {
+    /// Item is not visible from the term search target
+    Visibility,
+    /// Function is unsafe to call and `TermSearchConfig::allow_unsafe` is not set
+    Unsafe,
+    /// Item or some of its generic parameters are unstable
+    Unstable,
+    /// Item is excluded by `TermSearchConfig::blocklist`
+    Blocked,
+    /// Item is only compiled for tests and the target is not in test code
+    TestOnly,
+    /// Function is known to panic and `TermSearchConfig::enable_panicking` is not set
+    Panicking,
+    /// Function takes more than `TermSearchConfig::max_fn_args` arguments
+    TooManyArgs,
+    /// Item has lifetime or const parameters, that are not supported
+    UnsupportedGenerics,
+    /// Generic arguments do not satisfy the trait bounds and where clauses of the item
+    BoundsNotSatisfied,
+    /// Function returns reference or raw pointer, whose lifetimes are not checked
+    ReturnsReference,
+    /// Some of the arguments could not be filled with the values reached so far
+    MissingArguments,
+    /// Function takes no arguments to transform and `TermSearchConfig::transformations_only` is
+    /// set
+    NoArguments,
+    /// Item was not exhausted yet when the search ran out of `TermSearchConfig::fuel`
+    OutOfFuel,
+    /// Tree built with the item was squashed to `Expr::Many` as more alternatives of its type were
+    /// reached than `TermSearchConfig::many_alternatives_threshold` (or
+    /// `goal_many_alternatives_threshold` for the goal) allows
+    TooManyAlternatives,
+}
+
+/// Tactic that produced the solution of term search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TacticKind {
+    /// Values in scope (locals, constants, statics, etc)
+    Trivial,
+    /// Well known values such as `true` or `()`
+    FamousTypes,
+    /// Casts of fieldless enums to integers
+    FieldlessEnumCast,
+    /// Casts of references to raw pointers
+    RawPointer,
+    /// `Cow::Borrowed` and `Cow::Owned` constructors
+    Cow,
+    /// Struct and enum variant constructors
+    TypeConstructor,
+    /// Calls to free functions
+    FreeFunction,
+    /// Method calls
+    ImplMethod,
+    /// Chains of fluent builder methods returning `Self` (`b.with_x(x).with_y(y)`)
+    BuilderChain,
+    /// Field accesses
+    StructProjection,
+    /// Dereferences of smart pointers and newtypes
+    Deref,
+    /// Coercions to trait objects
+    TraitObject,
+    /// Calls to associated functions without `self` parameter
+    ImplStaticMethod,
+    /// Tuple constructors
+    MakeTuple,
+    /// Arrays of repeated element
+    RepeatArray,
+    /// Arrays listing distinct elements
+    ArrayLiteral,
+    /// Conversions of `Display` values to `String`
+    ToString,
+    /// Pinning values with `Box::pin`
+    BoxPin,
+    /// Conversions with `Into::into`
+    Conversion,
+    /// Concatenations of strings with `+` (`a + &b`)
+    StringConcat,
+    /// Additions of integers with `+` (`a + b`)
+    IntegerAdd,
+    /// First elements of collections (`v.iter().next()`)
+    FirstElement,
+    /// `map`, `filter` and `cloned` adapters of iterators (`v.iter().map(parse)`)
+    IteratorAdapter,
+    /// Collections of a single element (`vec![x]` or `iter::once(x).collect()`)
+    SingleElementCollection,
+    /// Indexing of containers implementing `Index` (`v[i]`)
+    Index,
+    /// Unwrapping `Result`s with `?` in functions returning `Result` (`foo()?`)
+    TryOperator,
+    /// Closures for closure trait objects (`Box::new(|x| ..)`)
+    Closure,
+    /// Fallback `todo!()` from `TermSearchConfig::always_include_todo`
+    Todo,
+}
+
+impl TacticKind {
+    /// Whether the tactic builds new values instead of transforming the reached ones, such
+    /// tactics are not run with `TermSearchConfig::transformations_only`
+    pub fn is_construction(self) -> bool {
+        matches!(
+            self,
+            TacticKind::FamousTypes
+                | TacticKind::Cow
+                | TacticKind::TypeConstructor
+                | TacticKind::ImplStaticMethod
+                | TacticKind::MakeTuple
+                | TacticKind::RepeatArray
+                | TacticKind::ArrayLiteral
+                | TacticKind::SingleElementCollection
+                | TacticKind::BoxPin
+                | TacticKind::Closure
+        )
+    }
+}
+
+impl TermSearchStats {
+    /// Extend `solutions` with solutions found by `tactic` and count them
+    fn extend(
+        &mut self,
+        solutions: &mut Vec<(TacticKind, Expr)>,
+        tactic: TacticKind,
+        exprs: impl Iterator<Item = Expr>,
+    ) {
+        let len = solutions.len();
+        solutions.extend(exprs.map(|it| (tactic, it)));
+        *self.per_tactic_counts.entry(tactic).or_default() += solutions.len() - len;
     }
 }
 
@@ -300,47 +1489,1337 @@ impl Default for TermSearchConfig {
 /// Note that there are usually more ways we can get to the `goal` type but some are discarded to
 /// reduce the memory consumption. It is also unlikely anyone is willing ti browse through
 /// thousands of possible responses so we currently take first 10 from every tactic.
-pub fn term_search<DB: HirDatabase>(ctx: &TermSearchCtx<'_, DB>) -> Vec<Expr> {
+pub fn term_search(ctx: &TermSearchCtx<'_>) -> Vec<Expr> {
+    term_search_with_unify(ctx, could_unify_deeply())
+}
+
+/// # Term search with custom unification
+///
+/// Same as [`term_search`], but uses `unify` instead of [`Type::could_unify_with_deeply`] for
+/// checking if type reached can be used for `goal` or as a parameter in the lookup.
+pub fn term_search_with_unify(ctx: &TermSearchCtx<'_>, unify: UnifyFn) -> Vec<Expr> {
+    let (solutions, _) = term_search_impl(ctx, unify);
+    solutions.into_iter().map(|(_, it)| it).collect()
+}
+
+/// # Term search with statistics
+///
+/// Same as [`term_search`], but also returns [`TermSearchStats`] collected during the search.
+pub fn term_search_with_stats(ctx: &TermSearchCtx<'_>) -> (Vec<Expr>, TermSearchStats) {
+    let (solutions, stats) = term_search_impl(ctx, could_unify_deeply());
+    (solutions.into_iter().map(|(_, it)| it).collect(), stats)
+}
+
+/// # Term search with rejections
+///
+/// Same as [`term_search`], but also calls `reject_sink` for every candidate item the tactics
+/// discarded, with the reason it was discarded. Meant for finding out why an item was not
+/// suggested. Each pair of reason and item is reported once, in the order they were first seen.
+#[cfg(feature = "term-search-dump")]
+pub fn term_search_with_rejections(
+    ctx: &TermSearchCtx<'_>,
+    reject_sink: &mut dyn FnMut(RejectReason, ModuleDef),
+) -> Vec<Expr> {
+    let (solutions, stats) = term_search_impl(ctx, could_unify_deeply());
+    for (reason, def) in stats.rejections {
+        reject_sink(reason, def);
+    }
+    solutions.into_iter().map(|(_, it)| it).collect()
+}
+
+/// # Term search grouped by tactic
+///
+/// Same as [`term_search`], but solutions are grouped by the tactic that produced them.
+/// Solutions found by multiple tactics only appear under the tactic that found them first.
+/// Groups are ordered by their best ranked solution.
+pub fn term_search_grouped(ctx: &TermSearchCtx<'_>) -> Vec<(TacticKind, Vec<Expr>)> {
+    let (solutions, _) = term_search_impl(ctx, could_unify_deeply());
+    let mut groups: Vec<(TacticKind, Vec<Expr>)> = Vec::new();
+    for (tactic, expr) in solutions {
+        match groups.iter_mut().find(|(it, _)| *it == tactic) {
+            Some((_, exprs)) => exprs.push(expr),
+            None => groups.push((tactic, vec![expr])),
+        }
+    }
+    groups
+}
+
+fn term_search_impl(
+    ctx: &TermSearchCtx<'_>,
+    unify: UnifyFn,
+) -> (Vec<(TacticKind, Expr)>, TermSearchStats) {
+    let mut search = Search::new(ctx, unify);
+    if search.goal_unreachable() {
+        let mut solutions = Vec::new();
+        push_todo_fallback(&search.ctx, &mut solutions);
+        return (solutions, search.stats);
+    }
+
+    let mut solutions = search.first_round();
+    while let Some(round) = search.next_round() {
+        solutions.extend(round);
+    }
+
+    solutions.retain(|(_, it)| search.accepts(it));
+    search.stats.solutions_pre_dedup = solutions.len();
+    let mut solutions = search.rank(solutions);
+    if search.ctx.config.dedup_reference_levels {
+        solutions =
+            dedup_reference_levels_by(ctx.sema.db(), &search.ctx.goal, solutions, |it| &it.1);
+    }
+    if let Some(max_solutions) = search.ctx.config.max_solutions {
+        solutions.truncate(max_solutions);
+    }
+    push_todo_fallback(&search.ctx, &mut solutions);
+
+    let mut stats = search.stats;
+    stats.solutions_post_dedup = solutions.len();
+    stats.max_depth = solutions.iter().map(|(_, it)| it.depth()).max().unwrap_or(0);
+    stats.types_reached = search.lookup.data.len();
+    stats.types_exhausted = search.lookup.exhausted_types.len();
+    stats.unify_calls = search.lookup.unify_calls.get();
+    let impls = &search.lookup.impls;
+    stats.trait_queries = impls.hits.get() + impls.misses.get();
+    stats.trait_cache_hits = impls.hits.get();
+    stats.trait_solver_calls = impls.misses.get();
+    stats.likely_needs_iteration = solutions.iter().all(|(tactic, _)| *tactic == TacticKind::Todo)
+        && likely_needs_iteration(&search.ctx, &search.lookup);
+    #[cfg(feature = "term-search-dump")]
+    {
+        stats.rejections = search.lookup.rejections.take().into_iter().collect();
+    }
+    (solutions, stats)
+}
+
+/// Append `todo!()` of the goal type as the last solution if `TermSearchConfig::always_include_todo`
+/// is set, the one found by the search (if any) is moved to the end
+fn push_todo_fallback(ctx: &TermSearchCtx<'_>, solutions: &mut Vec<(TacticKind, Expr)>) {
+    if ctx.config.always_include_todo {
+        solutions.retain(|(_, it)| !it.is_many());
+        solutions.push((TacticKind::Todo, Expr::Many(ctx.goal.clone())));
+    }
+}
+
+/// State of the term search kept between the rounds
+struct Search<'a> {
+    /// Context with goal normalized
+    ctx: TermSearchCtx<'a>,
+    defs: FxIndexSet<ScopeDef>,
+    lookup: LookupTable,
+    fuel: Cell<u64>,
+    /// Fuel of the search this one is nested in (to find closure bodies), all the fuel used is
+    /// charged to it as well
+    parent_fuel: Option<&'a dyn Fn() -> bool>,
+    stats: TermSearchStats,
+    /// Items and traits in scope for `TermSearchConfig::no_new_imports`
+    in_scope: Option<(FxHashSet<ModuleDef>, FxHashSet<Trait>)>,
+    /// Canonical value of the goal if it has a single value (such as `()` or `PhantomData<T>`), it
+    /// is the only solution so no rounds have to be run
+    singleton: Option<Expr>,
+}
+
+impl<'a> Search<'a> {
+    fn new(ctx: &TermSearchCtx<'a>, unify: UnifyFn) -> Self {
+        // Normalize associated type projections (such as `<S as Trait>::Assoc`) in goal, so that it
+        // can be matched against the concrete types in lookup
+        let ctx = TermSearchCtx {
+            goal: ctx.goal.normalize(ctx.sema.db()),
+            config: ctx.config.clone(),
+            ..*ctx
+        };
+        let lookup = LookupTable::new(&ctx.config, ctx.goal.clone(), unify);
+        let defs = scope_defs(&ctx, &lookup);
+        let fuel = Cell::new(ctx.config.fuel);
+        let in_scope = ctx.config.no_new_imports.then(|| items_in_scope(&ctx));
+        let mut search = Search {
+            ctx,
+            defs,
+            lookup,
+            fuel,
+            parent_fuel: None,
+            stats: TermSearchStats::default(),
+            in_scope,
+            singleton: None,
+        };
+        search.singleton = tactics::singleton_value(&search.ctx, &search.defs)
+            .filter(|it| !search.ctx.config.transformations_only && search.accepts(it));
+        search
+    }
+
+    /// Whether the goal has no solutions to search for
+    ///
+    /// There are no values of unsized types, they can only be used behind references. None of the
+    /// solutions of forbidden types could be used either.
+    fn goal_unreachable(&self) -> bool {
+        let db = self.ctx.sema.db();
+        !self.ctx.goal.is_sized(db) || self.lookup.is_forbidden(db, &self.ctx.goal)
+    }
+
+    /// Run the tactics that do not depend on other tactics, also populates lookup table
+    fn first_round(&mut self) -> Vec<(TacticKind, Expr)> {
+        let Search { ctx, defs, lookup, stats, singleton, .. } = self;
+        let mut solutions = Vec::new();
+        // Goals with a single value are filled with it right away, see `tactics::singleton_value`
+        if let Some(expr) = singleton {
+            lookup.insert(ctx.sema.db(), ctx.goal.clone(), iter::once(expr.clone()));
+            stats.extend(&mut solutions, TacticKind::Trivial, iter::once(expr.clone()));
+            return solutions;
+        }
+        stats.extend(&mut solutions, TacticKind::Trivial, tactics::trivial(ctx, defs, lookup));
+        if ctx.config.runs(TacticKind::FamousTypes) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::FamousTypes,
+                tactics::famous_types(ctx, defs, lookup),
+            );
+        }
+        solutions
+    }
+
+    /// Run all the tactics once, `None` if we are out of fuel or time or the round found nothing
+    /// new
+    fn next_round(&mut self) -> Option<Vec<(TacticKind, Expr)>> {
+        let Search { ctx, defs, lookup, fuel, parent_fuel, stats, singleton, .. } = self;
+        if singleton.is_some() {
+            return None;
+        }
+        if lookup.deadline.is_some_and(|it| Instant::now() >= it) {
+            tracing::debug!("soft deadline passed");
+            stats.timed_out = true;
+            return None;
+        }
+        let should_continue = &|| {
+            let remaining = fuel.get();
+            fuel.set(remaining.saturating_sub(1));
+            if remaining == 0 {
+                tracing::debug!("fuel exhausted");
+            }
+            remaining > 0 && parent_fuel.map_or(true, |it| it())
+        };
+        if !should_continue() {
+            for def in defs.iter().filter(|it| !lookup.exhausted_scopedefs().contains(it)) {
+                if let ScopeDef::ModuleDef(it) = def {
+                    lookup.reject::<()>(RejectReason::OutOfFuel, *it);
+                }
+            }
+            return None;
+        }
+
+        lookup.new_round();
+        lookup.exhaust_idle_types(ctx.sema.db());
+        stats.rounds_run += 1;
+        let progress = (lookup.data.len(), lookup.types_wishlist.len());
+
+        let mut solutions = Vec::new();
+        stats.extend(
+            &mut solutions,
+            TacticKind::FieldlessEnumCast,
+            tactics::fieldless_enum_cast(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::RawPointer,
+            tactics::raw_pointer(ctx, defs, lookup, should_continue),
+        );
+        if ctx.config.runs(TacticKind::Cow) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::Cow,
+                tactics::cow(ctx, defs, lookup, should_continue),
+            );
+        }
+        if ctx.config.runs(TacticKind::TypeConstructor) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::TypeConstructor,
+                tactics::type_constructor(ctx, defs, lookup, should_continue),
+            );
+        }
+        stats.extend(
+            &mut solutions,
+            TacticKind::BuilderChain,
+            tactics::builder_chain(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::FreeFunction,
+            tactics::free_function(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::ImplMethod,
+            tactics::impl_method(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::StructProjection,
+            tactics::struct_projection(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::Deref,
+            tactics::deref(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::TraitObject,
+            tactics::trait_object(ctx, defs, lookup, should_continue),
+        );
+        if ctx.config.runs(TacticKind::ImplStaticMethod) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::ImplStaticMethod,
+                tactics::impl_static_method(ctx, defs, lookup, should_continue),
+            );
+        }
+        if ctx.config.runs(TacticKind::MakeTuple) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::MakeTuple,
+                tactics::make_tuple(ctx, defs, lookup, should_continue),
+            );
+        }
+        if ctx.config.runs(TacticKind::RepeatArray) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::RepeatArray,
+                tactics::repeat_array(ctx, defs, lookup, should_continue),
+            );
+        }
+        if ctx.config.runs(TacticKind::ArrayLiteral) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::ArrayLiteral,
+                tactics::array_literal(ctx, defs, lookup, should_continue),
+            );
+        }
+        if ctx.config.runs(TacticKind::SingleElementCollection) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::SingleElementCollection,
+                tactics::single_element_collection(ctx, defs, lookup, should_continue),
+            );
+        }
+        stats.extend(
+            &mut solutions,
+            TacticKind::ToString,
+            tactics::to_string(ctx, defs, lookup, should_continue),
+        );
+        if ctx.config.runs(TacticKind::BoxPin) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::BoxPin,
+                tactics::box_pin(ctx, defs, lookup, should_continue),
+            );
+        }
+        stats.extend(
+            &mut solutions,
+            TacticKind::Conversion,
+            tactics::conversion(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::StringConcat,
+            tactics::string_concat(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::IntegerAdd,
+            tactics::integer_add(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::TryOperator,
+            tactics::try_operator(ctx, defs, lookup, should_continue),
+        );
+        if ctx.config.runs(TacticKind::Closure) {
+            stats.extend(
+                &mut solutions,
+                TacticKind::Closure,
+                tactics::closure(ctx, defs, lookup, should_continue),
+            );
+        }
+        stats.extend(
+            &mut solutions,
+            TacticKind::FirstElement,
+            tactics::first_element(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::IteratorAdapter,
+            tactics::iterator_adapter(ctx, defs, lookup, should_continue),
+        );
+        stats.extend(
+            &mut solutions,
+            TacticKind::Index,
+            tactics::index(ctx, defs, lookup, should_continue),
+        );
+
+        // Discard not interesting `ScopeDef`s for speedup
+        for def in lookup.exhausted_scopedefs() {
+            defs.shift_remove(def);
+        }
+        #[cfg(feature = "term-search-dump")]
+        {
+            let dump = lookup.dump(ctx.sema.db());
+            tracing::trace!(round = stats.rounds_run, "lookup table state\n{dump}");
+            stats.round_dumps.push(dump);
+        }
+        // Nothing new was reached or wished for so the next rounds would not find anything either
+        if solutions.is_empty() && (lookup.data.len(), lookup.types_wishlist.len()) == progress {
+            tracing::debug!("search saturated");
+            return None;
+        }
+        Some(solutions)
+    }
+
+    /// Check if the solution should be returned to the user
+    fn accepts(&self, expr: &Expr) -> bool {
+        let db = self.ctx.sema.db();
+        // Calls to `#[must_use]` functions in unit position only trigger `unused_must_use` lint
+        if self.ctx.goal.is_unit() && is_must_use_call(db, expr) {
+            return false;
+        }
+        if !self.ctx.config.allow_unsafe && expr.is_unsafe(db) {
+            return false;
+        }
+        if uses_forbidden_type(db, expr, &self.lookup) {
+            return false;
+        }
+        if self.ctx.config.max_distinct_calls.is_some_and(|max| distinct_calls(expr) > max) {
+            return false;
+        }
+        if self.ctx.config.max_modules.is_some_and(|max| used_modules(db, expr) > max) {
+            return false;
+        }
+        if self.ctx.config.validate && !(type_checks(db, expr) && fits(db, expr, &self.ctx.goal)) {
+            return false;
+        }
+        if let Some((items, traits)) = &self.in_scope {
+            if !is_in_scope(db, expr, items, traits) {
+                return false;
+            }
+        }
+        !expr.is_many()
+    }
+
+    /// Deduplicate and order the solutions from the best to the worst
+    fn rank(&self, solutions: Vec<(TacticKind, Expr)>) -> Vec<(TacticKind, Expr)> {
+        let db = self.ctx.sema.db();
+        if let Some(score_fn) = self.ctx.config.score_fn {
+            // Stable sort keeps the order the solutions were found in for equal cost
+            let mut scored: Vec<_> = solutions
+                .into_iter()
+                .unique_by(|(_, it)| it.clone())
+                .map(|it| (score_fn(db, &it.1), it))
+                .collect();
+            scored.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+            return scored.into_iter().map(|(_, it)| it).collect();
+        }
+        // Constructors taking only sizes (such as `Vec::with_capacity(n)`) are speculative as we
+        // do not know if the value is meant as capacity, so rank them after everything else
+        let (sized_constructors, solutions): (Vec<_>, Vec<_>) = solutions
+            .into_iter()
+            .unique_by(|(_, it)| it.clone())
+            .partition(|(_, it)| is_sized_constructor(db, it));
+        // Conversions are less likely wanted than the values of goal type we already have
+        let (conversions, solutions): (Vec<_>, Vec<_>) =
+            solutions.into_iter().partition(|(tactic, _)| {
+                matches!(
+                    tactic,
+                    TacticKind::ToString | TacticKind::Conversion | TacticKind::StringConcat
+                )
+            });
+        // Structs with all the fields defaulted are only a fallback when nothing else fits
+        let (default_constructors, solutions): (Vec<_>, Vec<_>) =
+            solutions.into_iter().partition(|(_, it)| is_default_constructor(it));
+        let solutions: Vec<_> = solutions
+            .into_iter()
+            .chain(conversions)
+            .chain(sized_constructors)
+            .chain(default_constructors)
+            .collect();
+        let solutions = match self.ctx.config.prefer_method_syntax {
+            true => prefer_method_calls(solutions),
+            false => solutions,
+        };
+        let spread_weight = self.ctx.config.module_spread_weight;
+        let allocation_penalty = self.ctx.config.allocation_penalty;
+        if self.ctx.config.ranking_hints.is_empty()
+            && spread_weight == 0.0
+            && allocation_penalty == 0.0
+        {
+            return solutions;
+        }
+        let score = |expr: &Expr| {
+            let spread = used_modules(db, expr).saturating_sub(1) as f32;
+            let allocated = allocations(db, expr) as f32;
+            self.ctx.ranking_score(expr) - spread_weight * spread - allocation_penalty * allocated
+        };
+        // Stable sort keeps the order above for the solutions with equal score
+        let mut scored: Vec<_> = solutions.into_iter().map(|it| (score(&it.1), it)).collect();
+        scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        scored.into_iter().map(|(_, it)| it).collect()
+    }
+}
+
+/// # Lazy term search
+///
+/// Same as [`term_search`], but the search only runs as many rounds as needed to produce the
+/// next solution, so stopping the iteration early also stops the search.
+///
+/// Solutions are yielded in the order set by `TermSearchConfig::stream_order`. As the search does
+/// not know all the solutions upfront, the ranking of [`term_search`] is not applied.
+pub fn term_search_iter<'a>(ctx: &TermSearchCtx<'a>) -> TermSearchIter<'a> {
+    let search = Search::new(ctx, could_unify_deeply());
+    let done = search.goal_unreachable();
+    TermSearchIter {
+        search,
+        started: false,
+        done,
+        seen: FxHashSet::default(),
+        buffer: BinaryHeap::new(),
+        counter: 0,
+    }
+}
+
+/// Order of the solutions yielded by [`term_search_iter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StreamOrder {
+    /// Solutions of every round in the order the tactics found them
+    Tactic,
+    /// Solutions in non-decreasing order of [`Expr::complexity`], or of the cost from
+    /// `TermSearchConfig::score_fn` when it is set
+    ///
+    /// Later rounds can find simpler solutions than the earlier ones, so solutions are held back
+    /// until the search is done. Once more than `buffer` solutions are held back, the simplest of
+    /// them are yielded early and order is no longer guaranteed for the solutions found later.
+    ShortestFirst { buffer: usize },
+}
+
+/// Iterator returned by [`term_search_iter`]
+pub struct TermSearchIter<'a> {
+    search: Search<'a>,
+    /// Whether the first round was run
+    started: bool,
+    /// Whether we are out of fuel
+    done: bool,
+    seen: FxHashSet<Expr>,
+    buffer: BinaryHeap<Buffered>,
+    /// Amount of solutions buffered so far, keeps the order of solutions with same complexity
+    counter: usize,
+}
+
+/// Solution held back by [`TermSearchIter`], ordered so that the cheapest one is on top of the heap
+struct Buffered {
+    /// Complexity of the solution or its cost from `TermSearchConfig::score_fn`
+    score: f64,
+    index: usize,
+    expr: Expr,
+}
+
+impl PartialEq for Buffered {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Buffered {}
+
+impl PartialOrd for Buffered {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Buffered {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.score.total_cmp(&self.score).then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl TermSearchIter<'_> {
+    /// Run next round of the search and buffer the solutions it found
+    fn fill(&mut self) {
+        let solutions = match self.started {
+            false => {
+                self.started = true;
+                self.search.first_round()
+            }
+            true => match self.search.next_round() {
+                Some(it) => it,
+                None => {
+                    self.done = true;
+                    return;
+                }
+            },
+        };
+        for (_, expr) in solutions {
+            if !self.search.accepts(&expr) || !self.seen.insert(expr.clone()) {
+                continue;
+            }
+            let score = match (self.search.ctx.config.stream_order, self.search.ctx.config.score_fn)
+            {
+                (StreamOrder::Tactic, _) => 0.0,
+                (StreamOrder::ShortestFirst { .. }, Some(score_fn)) => {
+                    score_fn(self.search.ctx.sema.db(), &expr)
+                }
+                (StreamOrder::ShortestFirst { .. }, None) => expr.complexity() as f64,
+            };
+            self.buffer.push(Buffered { score, index: self.counter, expr });
+            self.counter += 1;
+        }
+    }
+}
+
+impl Iterator for TermSearchIter<'_> {
+    type Item = Expr;
+
+    fn next(&mut self) -> Option<Expr> {
+        loop {
+            let safe = match self.search.ctx.config.stream_order {
+                StreamOrder::Tactic => true,
+                // Nothing can be simpler than a single node, so these never have to wait
+                StreamOrder::ShortestFirst { buffer } => {
+                    self.buffer.len() > buffer
+                        || self.search.ctx.config.score_fn.is_none()
+                            && self.buffer.peek().map_or(false, |it| it.score <= 1.0)
+                }
+            };
+            if safe || self.done {
+                if let Some(it) = self.buffer.pop() {
+                    return Some(it.expr);
+                }
+            }
+            if self.done {
+                return None;
+            }
+            self.fill();
+        }
+    }
+}
+
+/// Bodies of closure taking `params` and returning `ret` for the closure tactic, best first
+///
+/// Found by a nested search with the parameters (and the ones of the enclosing closures) as the
+/// only locals, one closure deeper than the search of `parent`. The nested search gets at most a
+/// quarter of the fuel, charges all the fuel it uses to the parent through `should_continue` and
+/// stops at the deadline of the parent. With `capture_locals` the locals in scope are used as
+/// well. Bodies using all the parameters come first.
+fn closure_bodies(
+    ctx: &TermSearchCtx<'_>,
+    parent: &LookupTable,
+    should_continue: &dyn Fn() -> bool,
+    params: &[(&'static str, Type)],
+    ret: Type,
+    capture_locals: bool,
+) -> Vec<Expr> {
+    const MAX_BODIES: usize = 4;
+    let config = TermSearchConfig {
+        fuel: ctx.config.fuel / 4,
+        extra_known_values: params
+            .iter()
+            .map(|(name, ty)| (ty.clone(), Expr::ClosureParam { name, ty: ty.clone() }))
+            .chain(ctx.config.extra_known_values.iter().cloned())
+            .collect(),
+        // The values of the return type are the bodies, so none of them are squashed
+        exhaustive_goal: true,
+        always_include_todo: false,
+        enclosing_ret_ty: None,
+        ..ctx.config.clone()
+    };
+    let ctx = TermSearchCtx { sema: ctx.sema, scope: ctx.scope, goal: ret, config };
+    let mut search = Search::new(&ctx, parent.unify.clone());
+    if search.goal_unreachable() {
+        return Vec::new();
+    }
+    search.parent_fuel = Some(should_continue);
+    search.lookup.closure_depth = parent.closure_depth + 1;
+    search.lookup.deadline = parent.deadline;
+    if !capture_locals {
+        search.defs.retain(|it| !matches!(it, ScopeDef::Local(_)));
+    }
+
+    let mut solutions = search.first_round();
+    while let Some(round) = search.next_round() {
+        solutions.extend(round);
+    }
+    solutions.retain(|(_, it)| search.accepts(it));
+    let (using_all, rest): (Vec<_>, Vec<_>) =
+        search.rank(solutions).into_iter().map(|(_, it)| it).partition(|it| {
+            let used = it.free_closure_params();
+            params.iter().all(|(name, _)| used.contains(name))
+        });
+    using_all.into_iter().chain(rest).take(MAX_BODIES).collect()
+}
+
+/// # Term search from seed expression
+///
+/// Search for single step continuations (methods calls and field accesses) that take us from the
+/// `seed` expression to the `goal` type.
+/// This is useful for completing partially written expressions such as `foo.` as instead of
+/// searching the whole scope we only try to reach the goal from the known value.
+///
+/// Values in scope are only used as arguments for method calls.
+/// Continuations are ordered so that the ones with least arguments come first.
+pub fn term_search_from_seed(ctx: &TermSearchCtx<'_>, seed: Expr) -> Vec<Expr> {
+    let db = ctx.sema.db();
+    let mut lookup = LookupTable::new(&ctx.config, ctx.goal.clone(), could_unify_deeply());
+    let defs = scope_defs(ctx, &lookup);
+    let fuel = Cell::new(ctx.config.fuel);
+    let should_continue = &|| {
+        let remaining = fuel.get();
+        fuel.set(remaining.saturating_sub(1));
+        remaining > 0
+    };
+
+    // Populate lookup with values for arguments
+    tactics::trivial(ctx, &defs, &mut lookup).for_each(drop);
+    tactics::famous_types(ctx, &defs, &mut lookup).for_each(drop);
+
+    // Only the seed type should be expanded, seed also replaces all the other values of its type
+    // so that all the continuations start from it
+    for it in lookup.new_types.values_mut() {
+        it.clear();
+    }
+    let seed_ty = seed.ty(db);
+    lookup.data.shift_remove(&seed_ty);
+    let mut seeded = LookupTable::new(&ctx.config, ctx.goal.clone(), could_unify_deeply());
+    seeded.insert(db, seed_ty, iter::once(seed.clone()));
+    lookup.merge(db, seeded);
+
+    let mut solutions: Vec<Expr> =
+        tactics::impl_method(ctx, &defs, &mut lookup, should_continue).collect();
+    solutions.extend(tactics::struct_projection(ctx, &defs, &mut lookup, should_continue));
+
+    solutions
+        .into_iter()
+        .filter(|it| match it {
+            Expr::Method { target, .. } => **target == seed,
+            Expr::Field { expr, .. } => **expr == seed,
+            _ => false,
+        })
+        .unique()
+        .sorted_by_key(|it| match it {
+            Expr::Method { params, .. } => params.len() + 1,
+            _ => 0,
+        })
+        .collect()
+}
+
+/// # Continuations from receiver type
+///
+/// Search for method calls and field accesses that take a value of `receiver_ty` to the `goal`
+/// type, for example to complete the method chain in `vec.iter().`.
+///
+/// As the receiver value is not known, it is represented by `Expr::Many(receiver_ty)` in the
+/// returned expressions. Continuations are ranked the same way as in `term_search_from_seed`.
+pub fn continuations_from<'a>(
+    sema: &'a dyn TermSearchSema,
+    scope: &'a SemanticsScope<'a>,
+    receiver_ty: Type,
+    goal: Type,
+    config: TermSearchConfig,
+) -> Vec<Expr> {
+    let ctx = TermSearchCtx { sema, scope, goal, config };
+    term_search_from_seed(&ctx, Expr::Many(receiver_ty))
+}
+
+/// # Nearest reachable types
+///
+/// Explain why the `goal` could not be reached by listing the types reached by the search that are
+/// closest to it, along with their edit distance to the goal. Taking or removing a reference,
+/// wrapping or unwrapping a type (`T` for goal `Option<T>`) and replacing a type argument count as
+/// one edit each.
+///
+/// Types are ordered by their distance, the ones unrelated to the goal are left out, as are the
+/// ones unifying with it.
+pub fn nearest_reachable<'a>(
+    sema: &'a dyn TermSearchSema,
+    scope: &'a SemanticsScope<'a>,
+    goal: Type,
+    config: TermSearchConfig,
+) -> Vec<(Type, usize)> {
+    let db = sema.db();
+    let ctx = TermSearchCtx { sema, scope, goal, config };
+    let mut search = Search::new(&ctx, could_unify_deeply());
+    search.first_round();
+    while search.next_round().is_some() {}
+
+    let goal = &search.ctx.goal;
+    let mut nearest: Vec<(Type, usize)> = search
+        .lookup
+        .data
+        .keys()
+        .filter(|ty| !ty.contains_unknown())
+        .filter_map(|ty| Some((ty.clone(), type_distance(db, ty, goal)?)))
+        .filter(|(_, distance)| *distance > 0)
+        .collect();
+    nearest.sort_by_key(|(_, distance)| *distance);
+    nearest
+}
+
+/// Edit distance between `ty` and `goal` as described in [`nearest_reachable`], `None` if the
+/// types are not related
+fn type_distance(db: &dyn HirDatabase, ty: &Type, goal: &Type) -> Option<usize> {
+    if ty.could_unify_with_deeply(db, goal) {
+        return Some(0);
+    }
+    let mut distances = Vec::new();
+    // Differ by reference
+    if let Some(inner) = goal.remove_ref() {
+        distances.extend(type_distance(db, ty, &inner).map(|it| it + 1));
+    }
+    if let Some(inner) = ty.remove_ref() {
+        distances.extend(type_distance(db, &inner, goal).map(|it| it + 1));
+    }
+    // Same type with some of the type arguments replaced
+    if ty.as_adt().is_some() && ty.as_adt() == goal.as_adt() {
+        let replaced = ty
+            .type_arguments()
+            .zip(goal.type_arguments())
+            .filter(|(it, other)| !it.could_unify_with_deeply(db, other))
+            .count();
+        distances.push(replaced.max(1));
+    }
+    // Wrapped in the goal or wrapping it
+    distances
+        .extend(goal.type_arguments().filter_map(|it| type_distance(db, ty, &it)).map(|it| it + 1));
+    distances
+        .extend(ty.type_arguments().filter_map(|it| type_distance(db, &it, goal)).map(|it| it + 1));
+    distances.into_iter().min()
+}
+
+/// Collect the items in scope that term search can use
+///
+/// Items are in deterministic order, shuffled by `TermSearchConfig::shuffle_seed` if one is set.
+fn scope_defs(ctx: &TermSearchCtx<'_>, lookup: &LookupTable) -> FxIndexSet<ScopeDef> {
     let module = ctx.scope.module();
-    let mut defs = FxHashSet::default();
+    let mut defs = FxIndexSet::default();
     defs.insert(ScopeDef::ModuleDef(ModuleDef::Module(module)));
 
+    // Names in scope include the whole prelude, most of which (traits, macros, type aliases) no
+    // tactic looks at, so only keep the ones values can be built from
     ctx.scope.process_all_names(&mut |_, def| {
-        defs.insert(def);
+        if is_value_source(&def) {
+            defs.insert(def);
+        }
+    });
+    if ctx.config.exhaustive_module_walk {
+        let db = ctx.sema.db();
+        let mut modules = vec![module.krate().root_module()];
+        while let Some(it) = modules.pop() {
+            modules.extend(it.children(db));
+            for decl in it.declarations(db) {
+                if let ModuleDef::Function(func) = decl {
+                    if func.is_visible_from(db, module) {
+                        defs.insert(ScopeDef::ModuleDef(decl));
+                    }
+                }
+            }
+        }
+    }
+    defs.retain(|def| match def {
+        ScopeDef::ModuleDef(it) if ctx.is_blocked(*it) => {
+            lookup.reject::<()>(RejectReason::Blocked, *it);
+            false
+        }
+        ScopeDef::ModuleDef(it) if ctx.is_test_only(*it) => {
+            lookup.reject::<()>(RejectReason::TestOnly, *it);
+            false
+        }
+        _ => true,
     });
+    if let Some(seed) = ctx.config.shuffle_seed {
+        let mut shuffled: Vec<ScopeDef> = defs.into_iter().collect();
+        if !shuffled.is_empty() {
+            let mut rng = oorandom::Rand32::new(seed);
+            stdx::rand::shuffle(&mut shuffled, |i| rng.rand_range(0..i as u32) as usize);
+        }
+        defs = shuffled.into_iter().collect();
+    }
+    defs
+}
 
-    let mut lookup = LookupTable::new(ctx.config.many_alternatives_threshold, ctx.goal.clone());
-    let fuel = std::cell::Cell::new(ctx.config.fuel);
+/// Check if the tactics can build values out of the `ScopeDef`, these are the values themselves
+/// (locals, constants and statics) and the items that can be called or constructed
+fn is_value_source(def: &ScopeDef) -> bool {
+    matches!(
+        def,
+        ScopeDef::Local(_)
+            | ScopeDef::GenericParam(GenericParam::ConstParam(_))
+            | ScopeDef::ModuleDef(
+                ModuleDef::Const(_)
+                    | ModuleDef::Static(_)
+                    | ModuleDef::Function(_)
+                    | ModuleDef::Variant(_)
+                    | ModuleDef::Adt(Adt::Struct(_) | Adt::Enum(_))
+            )
+    )
+}
 
-    let should_continue = &|| {
-        let remaining = fuel.get();
-        fuel.set(remaining.saturating_sub(1));
-        if remaining == 0 {
-            tracing::debug!("fuel exhausted");
+/// Collect items and traits that can be referred to without imports at term search target
+fn items_in_scope(ctx: &TermSearchCtx<'_>) -> (FxHashSet<ModuleDef>, FxHashSet<Trait>) {
+    let mut items = FxHashSet::default();
+    ctx.scope.process_all_names(&mut |_, def| {
+        if let ScopeDef::ModuleDef(it) = def {
+            items.insert(it);
         }
-        remaining > 0
+    });
+    let traits = ctx.scope.visible_traits().0.into_iter().map(Trait::from).collect();
+    (items, traits)
+}
+
+/// Check if all the items expression refers to are in scope, so that using it requires no new
+/// imports
+fn is_in_scope(
+    db: &dyn HirDatabase,
+    expr: &Expr,
+    items: &FxHashSet<ModuleDef>,
+    traits: &FxHashSet<Trait>,
+) -> bool {
+    let all_in_scope = |exprs: &[Expr]| exprs.iter().all(|it| is_in_scope(db, it, items, traits));
+    match expr {
+        Expr::Const(it) => items.contains(&ModuleDef::Const(*it)),
+        Expr::Static(it) => items.contains(&ModuleDef::Static(*it)),
+        Expr::FnItem(it) => items.contains(&ModuleDef::Function(*it)),
+        Expr::Local(_)
+        | Expr::ConstParam(_)
+        | Expr::FamousType { .. }
+        | Expr::ClosureParam { .. }
+        | Expr::Many(_) => true,
+        Expr::Function { func, params, .. } => {
+            let named = match func.as_assoc_item(db).map(|it| it.container(db)) {
+                Some(AssocItemContainer::Trait(it)) => items.contains(&ModuleDef::Trait(it)),
+                Some(AssocItemContainer::Impl(it)) => {
+                    it.self_ty(db).as_adt().map_or(true, |it| items.contains(&ModuleDef::Adt(it)))
+                }
+                None => items.contains(&ModuleDef::Function(*func)),
+            };
+            named && all_in_scope(params)
+        }
+        Expr::Method { func, target, params, .. } => {
+            let trait_in_scope = func
+                .as_assoc_item(db)
+                .and_then(|it| it.container_or_implemented_trait(db))
+                .map_or(true, |it| traits.contains(&it));
+            trait_in_scope && is_in_scope(db, target, items, traits) && all_in_scope(params)
+        }
+        Expr::Variant { variant, params, .. } => {
+            let named = items.contains(&ModuleDef::Variant(*variant))
+                || items.contains(&ModuleDef::Adt(Adt::Enum(variant.parent_enum(db))));
+            named && all_in_scope(params)
+        }
+        Expr::Struct { strukt, params, .. } => {
+            items.contains(&ModuleDef::Adt(Adt::Struct(*strukt))) && all_in_scope(params)
+        }
+        Expr::Tuple { params, .. } | Expr::Array { elems: params, .. } => all_in_scope(params),
+        Expr::ArrayRepeat { elem, .. } => is_in_scope(db, elem, items, traits),
+        Expr::VecMacro { mac, elem, .. } => {
+            items.contains(&ModuleDef::Macro(*mac)) && is_in_scope(db, elem, items, traits)
+        }
+        Expr::Index { expr, index, .. } => {
+            is_in_scope(db, expr, items, traits) && is_in_scope(db, index, items, traits)
+        }
+        Expr::Add { lhs, rhs, .. } => {
+            is_in_scope(db, lhs, items, traits) && is_in_scope(db, rhs, items, traits)
+        }
+        Expr::Field { expr, .. }
+        | Expr::Reference(expr)
+        | Expr::MutReference(expr)
+        | Expr::Cast { expr, .. }
+        | Expr::Deref { expr, .. }
+        | Expr::Try { expr, .. }
+        | Expr::Closure { body: expr, .. } => is_in_scope(db, expr, items, traits),
+    }
+}
+
+/// Paths to the items used in the expression
+///
+/// Paths start with the name of the crate the item is defined in, so they can be used as
+/// `TermSearchConfig::ranking_hints` or `TermSearchConfig::blocklist` in any crate.
+pub fn item_paths(db: &dyn HirDatabase, expr: &Expr) -> Vec<ModPath> {
+    used_items(expr)
+        .into_iter()
+        .filter_map(|def| {
+            let (krate, segments) = def_path(db, def)?;
+            let crate_name = krate.display_name(db)?.crate_name().to_string();
+            let crate_name = Name::new_text_dont_use(crate_name.into());
+            Some(ModPath::from_segments(PathKind::Plain, iter::once(crate_name).chain(segments)))
+        })
+        .unique()
+        .collect()
+}
+
+/// Items the expression refers to by path
+fn used_items(expr: &Expr) -> Vec<ModuleDef> {
+    let mut items = Vec::new();
+    let mut stack = vec![expr];
+    while let Some(expr) = stack.pop() {
+        match expr {
+            Expr::Const(it) => items.push(ModuleDef::Const(*it)),
+            Expr::Static(it) => items.push(ModuleDef::Static(*it)),
+            Expr::FnItem(it) => items.push(ModuleDef::Function(*it)),
+            Expr::Local(_)
+            | Expr::ConstParam(_)
+            | Expr::FamousType { .. }
+            | Expr::ClosureParam { .. }
+            | Expr::Many(_) => (),
+            Expr::Function { func, params, .. } => {
+                items.push(ModuleDef::Function(*func));
+                stack.extend(params);
+            }
+            Expr::Method { func, target, params, .. } => {
+                items.push(ModuleDef::Function(*func));
+                stack.push(target);
+                stack.extend(params);
+            }
+            Expr::Variant { variant, params, .. } => {
+                items.push(ModuleDef::Variant(*variant));
+                stack.extend(params);
+            }
+            Expr::Struct { strukt, params, .. } => {
+                items.push(ModuleDef::Adt(Adt::Struct(*strukt)));
+                stack.extend(params);
+            }
+            Expr::Tuple { params, .. } | Expr::Array { elems: params, .. } => stack.extend(params),
+            Expr::ArrayRepeat { elem, .. } => stack.push(elem),
+            Expr::VecMacro { mac, elem, .. } => {
+                items.push(ModuleDef::Macro(*mac));
+                stack.push(elem);
+            }
+            Expr::Index { expr, index, .. } => {
+                stack.push(expr);
+                stack.push(index);
+            }
+            Expr::Add { lhs, rhs, .. } => {
+                stack.push(lhs);
+                stack.push(rhs);
+            }
+            Expr::Field { expr, .. }
+            | Expr::Reference(expr)
+            | Expr::MutReference(expr)
+            | Expr::Cast { expr, .. }
+            | Expr::Deref { expr, .. }
+            | Expr::Try { expr, .. }
+            | Expr::Closure { body: expr, .. } => stack.push(expr),
+        }
+    }
+    items
+}
+
+/// Check if items of any of the collections (types implementing `IntoIterator`) in lookup are of
+/// the goal type or have a field of it, so that the goal could be computed by a loop over them
+fn likely_needs_iteration(ctx: &TermSearchCtx<'_>, lookup: &LookupTable) -> bool {
+    let db = ctx.sema.db();
+    let Some(into_iter) = ctx.lang_trait("collect", "IntoIterator") else { return false };
+    let Some(item) = into_iter.items(db).into_iter().find_map(|it| match it {
+        AssocItem::TypeAlias(it) if it.name(db).as_str() == Some("Item") => Some(it),
+        _ => None,
+    }) else {
+        return false;
     };
+    let is_goal = |ty: &Type| lookup.could_unify(db, ty, &ctx.goal);
+    lookup
+        .data
+        .keys()
+        .filter(|ty| !ty.contains_unknown() && lookup.impls.impls_trait(db, ty, into_iter, &[]))
+        .filter_map(|ty| ty.normalize_trait_assoc_type(db, &[], item))
+        .map(|it| it.strip_references())
+        .any(|it| is_goal(&it) || it.fields(db).iter().any(|(_, ty)| is_goal(ty)))
+}
 
-    // Try trivial tactic first, also populates lookup table
-    let mut solutions: Vec<Expr> = tactics::trivial(ctx, &defs, &mut lookup).collect();
-    // Use well known types tactic before iterations as it does not depend on other tactics
-    solutions.extend(tactics::famous_types(ctx, &defs, &mut lookup));
+/// Amount of distinct functions and constructors called in the expression
+fn distinct_calls(expr: &Expr) -> usize {
+    used_items(expr)
+        .into_iter()
+        .filter(|it| {
+            matches!(it, ModuleDef::Function(_) | ModuleDef::Variant(_) | ModuleDef::Adt(_))
+        })
+        .unique()
+        .count()
+}
 
-    while should_continue() {
-        lookup.new_round();
+/// Amount of nodes of the expression allocating on the heap
+///
+/// These are the calls and `vec!`s building new `String`s, `Vec`s or `Box`es. Calls without
+/// arguments (such as `String::new()`) do not allocate.
+fn allocations(db: &dyn HirDatabase, expr: &Expr) -> usize {
+    let mut count = 0;
+    let mut stack = vec![expr];
+    while let Some(expr) = stack.pop() {
+        let allocates = match expr {
+            Expr::VecMacro { .. } => true,
+            Expr::Function { params, .. } if params.is_empty() => false,
+            Expr::Function { .. } | Expr::Method { .. } => {
+                let ty = expr.ty(db);
+                tactics::is_string(db, &ty)
+                    || tactics::is_vec(db, &ty)
+                    || tactics::is_lang_adt(db, &ty, LangItem::OwnedBox)
+            }
+            _ => false,
+        };
+        count += allocates as usize;
+        stack.extend(expr.children());
+    }
+    count
+}
 
-        solutions.extend(tactics::type_constructor(ctx, &defs, &mut lookup, should_continue));
-        solutions.extend(tactics::free_function(ctx, &defs, &mut lookup, should_continue));
-        solutions.extend(tactics::impl_method(ctx, &defs, &mut lookup, should_continue));
-        solutions.extend(tactics::struct_projection(ctx, &defs, &mut lookup, should_continue));
-        solutions.extend(tactics::impl_static_method(ctx, &defs, &mut lookup, should_continue));
-        solutions.extend(tactics::make_tuple(ctx, &defs, &mut lookup, should_continue));
+/// Amount of distinct modules the items used in the expression come from
+fn used_modules(db: &dyn HirDatabase, expr: &Expr) -> usize {
+    used_items(expr).into_iter().filter_map(|it| it.module(db)).unique().count()
+}
 
-        // Discard not interesting `ScopeDef`s for speedup
-        for def in lookup.exhausted_scopedefs() {
-            defs.remove(def);
+/// Check if expression type checks
+///
+/// Unlike the tactics that use unification heuristics this makes sure that all the arguments fit
+/// the parameters of the functions and constructors and that the trait bounds of the functions
+/// called hold.
+fn type_checks(db: &dyn HirDatabase, expr: &Expr) -> bool {
+    let args_fit = |params: &[Expr], tys: Vec<Type>| {
+        params.len() == tys.len()
+            && params.iter().zip(tys).all(|(it, ty)| type_checks(db, it) && fits(db, it, &ty))
+    };
+    match expr {
+        Expr::Const(_)
+        | Expr::Static(_)
+        | Expr::FnItem(_)
+        | Expr::Local(_)
+        | Expr::ConstParam(_)
+        | Expr::FamousType { .. }
+        | Expr::ClosureParam { .. }
+        | Expr::Many(_) => true,
+        Expr::Function { func, generics, params } => {
+            let tys = func
+                .params_without_self_with_args(db, generics.iter().cloned())
+                .into_iter()
+                .map(|it| it.ty().clone())
+                .collect();
+            // Closures are typed by the closure trait objects they are used as (`Box::new` of
+            // `dyn Fn()`), their own types are sized but cannot be named
+            let closure_args = params.iter().any(|it| matches!(it, Expr::Closure { .. }));
+            (closure_args || func.predicates_hold_with_args(db, generics.iter().cloned()))
+                && args_fit(params, tys)
+        }
+        Expr::Method { func, generics, target, params } => {
+            let generics = expr::method_args(db, *func, target, generics);
+            let tys = func
+                .params_without_self_with_args(db, generics.iter().cloned())
+                .into_iter()
+                .map(|it| it.ty().clone())
+                .collect();
+            let closure_args = params.iter().any(|it| matches!(it, Expr::Closure { .. }));
+            (closure_args || func.predicates_hold_with_args(db, generics.iter().cloned()))
+                && type_checks(db, target)
+                && args_fit(params, tys)
+        }
+        Expr::Variant { variant, generics, params } => {
+            let tys = variant
+                .fields(db)
+                .into_iter()
+                .map(|it| it.ty_with_args(db, generics.iter().cloned()))
+                .collect();
+            args_fit(params, tys)
+        }
+        Expr::Struct { strukt, generics, params } => {
+            let tys = strukt
+                .fields(db)
+                .into_iter()
+                .map(|it| it.ty_with_args(db, generics.iter().cloned()))
+                .collect();
+            args_fit(params, tys)
+        }
+        Expr::Tuple { ty, params } => args_fit(params, ty.tuple_fields(db)),
+        Expr::Array { ty, elems } => match ty.as_array(db) {
+            Some((elem_ty, len)) => len == elems.len() && args_fit(elems, vec![elem_ty; len]),
+            None => false,
+        },
+        Expr::ArrayRepeat { ty, elem, .. } => match ty
+            .as_array(db)
+            .map(|(it, _)| it)
+            .or_else(|| ty.as_array_with_const_param_len(db).map(|(it, _)| it))
+        {
+            Some(elem_ty) => {
+                elem_ty.is_copy(db) && args_fit(std::slice::from_ref(elem), vec![elem_ty])
+            }
+            None => false,
+        },
+        Expr::VecMacro { ty, elem, .. } => match ty.type_arguments().next() {
+            Some(elem_ty) => args_fit(std::slice::from_ref(elem), vec![elem_ty]),
+            None => false,
+        },
+        Expr::Index { expr, index, .. } => type_checks(db, expr) && type_checks(db, index),
+        Expr::Add { lhs, rhs, .. } => type_checks(db, lhs) && type_checks(db, rhs),
+        Expr::Field { expr, .. }
+        | Expr::Reference(expr)
+        | Expr::MutReference(expr)
+        | Expr::Cast { expr, .. }
+        | Expr::Deref { expr, .. }
+        | Expr::Try { expr, .. }
+        | Expr::Closure { body: expr, .. } => type_checks(db, expr),
+    }
+}
+
+/// # Deduplicate reference levels
+///
+/// Out of the solutions that differ only in the references taken to them (such as `x`, `&x` and
+/// `&&x`), keep only the ones with exactly the reference level of `goal`, the others only reach
+/// the goal through coercion. Solutions none of whose forms match the goal exactly are all kept.
+///
+/// Applied to the solutions of [`term_search`] with `TermSearchConfig::dedup_reference_levels`.
+pub fn dedup_reference_levels(
+    db: &dyn HirDatabase,
+    goal: &Type,
+    solutions: Vec<Expr>,
+) -> Vec<Expr> {
+    dedup_reference_levels_by(db, goal, solutions, |it| it)
+}
+
+fn dedup_reference_levels_by<T>(
+    db: &dyn HirDatabase,
+    goal: &Type,
+    solutions: Vec<T>,
+    expr: impl Fn(&T) -> &Expr,
+) -> Vec<T> {
+    fn strip_references(mut expr: &Expr) -> &Expr {
+        while let Expr::Reference(inner) | Expr::MutReference(inner) = expr {
+            expr = inner;
         }
+        expr
     }
 
-    solutions.into_iter().filter(|it| !it.is_many()).unique().collect()
+    let goal = goal.normalize(db);
+    let exact: Vec<bool> = solutions
+        .iter()
+        .map(|it| match expr(it) {
+            Expr::Many(_) => false,
+            it => reference_ty(db, it).normalize(db).could_unify_with_deeply(db, &goal),
+        })
+        .collect();
+    let keep: Vec<bool> = {
+        let matched: FxHashSet<&Expr> = solutions
+            .iter()
+            .zip(&exact)
+            .filter(|(_, &exact)| exact)
+            .map(|(it, _)| strip_references(expr(it)))
+            .collect();
+        solutions
+            .iter()
+            .zip(&exact)
+            .map(|(it, &exact)| exact || !matched.contains(strip_references(expr(it))))
+            .collect()
+    };
+    solutions.into_iter().zip(keep).filter_map(|(it, keep)| keep.then_some(it)).collect()
+}
+
+/// Check if value of expression can be used in place of type `ty`
+fn fits(db: &dyn HirDatabase, expr: &Expr, ty: &Type) -> bool {
+    let expr_ty = match expr {
+        Expr::Many(_) => return true,
+        _ => reference_ty(db, expr),
+    };
+    let expr_ty = expr_ty.normalize(db);
+    let ty = ty.normalize(db);
+    expr_ty.could_unify_with_deeply(db, &ty) || expr_ty.could_coerce_to(db, &ty)
+}
+
+/// Type of the expression where references are accounted for
+///
+/// `Expr::ty` gives type of the referenced value for reference expressions.
+fn reference_ty(db: &dyn HirDatabase, expr: &Expr) -> Type {
+    match expr {
+        Expr::Reference(it) => Type::reference(&reference_ty(db, it), Mutability::Shared),
+        Expr::MutReference(it) => Type::reference(&reference_ty(db, it), Mutability::Mut),
+        _ => expr.ty(db),
+    }
+}
+
+/// Check if expression is call to function marked with `#[must_use]`
+fn is_must_use_call(db: &dyn HirDatabase, expr: &Expr) -> bool {
+    match expr {
+        Expr::Function { func, .. } | Expr::Method { func, .. } => {
+            func.attrs(db).by_key("must_use").exists()
+        }
+        _ => false,
+    }
+}
+
+/// Check if the expression or any of its subexpressions is of a forbidden type
+fn uses_forbidden_type(db: &dyn HirDatabase, expr: &Expr, lookup: &LookupTable) -> bool {
+    if lookup.forbidden_types.is_empty() {
+        return false;
+    }
+    let mut stack = vec![expr];
+    while let Some(expr) = stack.pop() {
+        if lookup.is_forbidden(db, &expr.ty(db)) {
+            return true;
+        }
+        stack.extend(expr.children());
+    }
+    false
+}
+
+/// Check if expression is a place (such as local or its field) that can be referenced without
+/// creating a temporary
+fn is_place(expr: &Expr) -> bool {
+    match expr {
+        Expr::Local(_) | Expr::Static(_) | Expr::Const(_) => true,
+        Expr::Field { expr, .. } | Expr::Index { expr, .. } => is_place(expr),
+        _ => false,
+    }
+}
+
+/// Move method calls right before the function calls taking the same arguments (`x.foo()` before
+/// `foo(x)`), the order is kept otherwise
+fn prefer_method_calls(solutions: Vec<(TacticKind, Expr)>) -> Vec<(TacticKind, Expr)> {
+    fn call_args(expr: &Expr) -> Option<Vec<&Expr>> {
+        match expr {
+            Expr::Function { params, .. } => Some(params.iter().collect()),
+            Expr::Method { target, params, .. } => {
+                Some(iter::once(&**target).chain(params).collect())
+            }
+            _ => None,
+        }
+    }
+
+    let positions: Vec<(usize, bool)> = solutions
+        .iter()
+        .enumerate()
+        .map(|(idx, (_, expr))| match expr {
+            Expr::Method { .. } => {
+                let args = call_args(expr);
+                let equivalent = solutions[..idx].iter().position(|(_, it)| {
+                    matches!(it, Expr::Function { .. }) && call_args(it) == args
+                });
+                (equivalent.unwrap_or(idx), false)
+            }
+            _ => (idx, true),
+        })
+        .collect();
+    let mut solutions: Vec<_> = positions.into_iter().zip(solutions).collect();
+    solutions.sort_by_key(|(position, _)| *position);
+    solutions.into_iter().map(|(_, it)| it).collect()
+}
+
+/// Check if expression is struct literal with fields filled by `Default::default()`
+fn is_default_constructor(expr: &Expr) -> bool {
+    match expr {
+        Expr::Struct { params, .. } => params
+            .iter()
+            .any(|it| matches!(it, Expr::FamousType { value, .. } if *value == DEFAULT_VALUE)),
+        _ => false,
+    }
+}
+
+/// Check if expression is call to associated function that takes only `usize` arguments
+fn is_sized_constructor(db: &dyn HirDatabase, expr: &Expr) -> bool {
+    match expr {
+        Expr::Function { func, params, .. } => {
+            func.as_assoc_item(db).is_some()
+                && !params.is_empty()
+                && params.iter().all(|it| it.ty(db).is_usize())
+        }
+        _ => false,
+    }
 }
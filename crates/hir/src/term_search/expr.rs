@@ -1,4 +1,9 @@
 //! Type tree for term search
+//!
+//! Besides being produced by the term search, trees can be built by hand with the constructors on
+//! [`Expr`] (`Expr::local`, `Expr::call`, `Expr::method`, `Expr::field`, `Expr::reference`, ...),
+//! for example to combine found terms with hand written ones. The constructors taking arguments
+//! check the arity and that the arguments fit the parameters and return `None` otherwise.
 
 use hir_def::find_path::PrefixKind;
 use hir_expand::mod_path::ModPath;
@@ -7,19 +12,72 @@ use hir_ty::{
     display::{DisplaySourceCodeError, HirDisplay},
 };
 use itertools::Itertools;
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use std::{
+    cell::RefCell,
+    hash::{Hash, Hasher},
+    iter,
+};
 
 use crate::{
-    Adt, AsAssocItem, Const, ConstParam, Field, Function, GenericDef, Local, ModuleDef,
-    SemanticsScope, Static, Struct, StructKind, Trait, Type, Variant,
+    Adt, AsAssocItem, AssocItemContainer, Const, ConstParam, Field, Function, GenericDef, Local,
+    Macro, ModuleDef, SemanticsScope, Static, Struct, StructKind, Trait, Type, Variant, VariantDef,
 };
 
+use super::{def_path, FxIndexMap};
+
+/// Path to the item that does not depend on the in-memory ids, used by `Expr::stable_key`
+///
+/// Associated items in impls also include the implemented trait and the self type, as the items of
+/// different impls are named the same way.
+fn stable_path(db: &dyn HirDatabase, def: ModuleDef) -> String {
+    let path = match def_path(db, def) {
+        Some((krate, segments)) => {
+            let krate = krate.display_name(db).map(|it| it.crate_name().to_string());
+            let segments = segments.iter().map(|it| it.display(db.upcast()).to_string());
+            iter::once(krate.unwrap_or_default()).chain(segments).join("::")
+        }
+        None => def.canonical_path(db).unwrap_or_default(),
+    };
+    let assoc = match def {
+        ModuleDef::Function(it) => it.as_assoc_item(db),
+        ModuleDef::Const(it) => it.as_assoc_item(db),
+        _ => None,
+    };
+    match assoc {
+        Some(it) => {
+            let trait_ = it.implemented_trait(db).map(|it| stable_path(db, ModuleDef::Trait(it)));
+            let self_ty = it.implementing_ty(db).map(|it| it.display(db).to_string());
+            format!("{path} in {} for {}", trait_.unwrap_or_default(), self_ty.unwrap_or_default())
+        }
+        None => path,
+    }
+}
+
+/// Check if the arguments fit the parameters (self param excluded) of the function
+fn args_fit(db: &dyn HirDatabase, func: Function, generics: &[Type], params: &[Expr]) -> bool {
+    let tys = func.params_without_self_with_args(db, generics.iter().cloned());
+    tys.len() == params.len() && params.iter().zip(tys).all(|(it, ty)| super::fits(db, it, ty.ty()))
+}
+
+/// Paths to the items found while rendering type trees
+///
+/// Trees rendered together share the cache, so that the scope is only queried once for every item
+/// and the items used by multiple trees are referred to by the same path in all of them.
+#[derive(Default)]
+struct PathCache(RefCell<FxHashMap<ModuleDef, Option<ModPath>>>);
+
 /// Helper function to get path to `ModuleDef`
 fn mod_item_path(
     sema_scope: &SemanticsScope<'_>,
     def: &ModuleDef,
     prefer_no_std: bool,
     prefer_prelude: bool,
+    paths: &PathCache,
 ) -> Option<ModPath> {
+    if let Some(it) = paths.0.borrow().get(def) {
+        return it.clone();
+    }
     let db = sema_scope.db;
     // Account for locals shadowing items from module
     let name_hit_count = def.name(db).map(|def_name| {
@@ -33,7 +91,7 @@ fn mod_item_path(
     });
 
     let m = sema_scope.module();
-    match name_hit_count {
+    let res = match name_hit_count {
         Some(0..=1) | None => m.find_use_path(db.upcast(), *def, prefer_no_std, prefer_prelude),
         Some(_) => m.find_use_path_prefixed(
             db.upcast(),
@@ -42,7 +100,9 @@ fn mod_item_path(
             prefer_no_std,
             prefer_prelude,
         ),
-    }
+    };
+    paths.0.borrow_mut().insert(*def, res.clone());
+    res
 }
 
 /// Helper function to get path to `ModuleDef` as string
@@ -51,8 +111,9 @@ fn mod_item_path_str(
     def: &ModuleDef,
     prefer_no_std: bool,
     prefer_prelude: bool,
+    paths: &PathCache,
 ) -> Result<String, DisplaySourceCodeError> {
-    let path = mod_item_path(sema_scope, def, prefer_no_std, prefer_prelude);
+    let path = mod_item_path(sema_scope, def, prefer_no_std, prefer_prelude, paths);
     path.map(|it| it.display(sema_scope.db.upcast()).to_string())
         .ok_or(DisplaySourceCodeError::PathNotFound)
 }
@@ -63,6 +124,7 @@ fn type_path(
     ty: &Type,
     prefer_no_std: bool,
     prefer_prelude: bool,
+    paths: &PathCache,
 ) -> Result<String, DisplaySourceCodeError> {
     let db = sema_scope.db;
     let m = sema_scope.module();
@@ -70,10 +132,19 @@ fn type_path(
     match ty.as_adt() {
         Some(adt) => {
             let ty_name = ty.display_source_code(db, m.id, true)?;
+            // Types not in scope are already displayed with the path to them
+            if !ty_name.starts_with(&adt.name(db).display(db.upcast()).to_string()) {
+                return Ok(ty_name);
+            }
 
-            let mut path =
-                mod_item_path(sema_scope, &ModuleDef::Adt(adt), prefer_no_std, prefer_prelude)
-                    .unwrap();
+            let mut path = mod_item_path(
+                sema_scope,
+                &ModuleDef::Adt(adt),
+                prefer_no_std,
+                prefer_prelude,
+                paths,
+            )
+            .unwrap();
             path.pop_segment();
             let path = path.display(db.upcast()).to_string();
             let res = match path.is_empty() {
@@ -86,17 +157,66 @@ fn type_path(
     }
 }
 
-/// Helper function to filter out generic parameters that are default
+/// Helper function to filter out generic parameters that are default or cannot be given explicitly
+/// (`impl Trait` in argument position)
 fn non_default_generics(db: &dyn HirDatabase, def: GenericDef, generics: &[Type]) -> Vec<Type> {
     def.type_or_const_params(db)
         .into_iter()
         .filter_map(|it| it.as_type_param(db))
         .zip(generics)
-        .filter(|(tp, arg)| tp.default(db).as_ref() != Some(arg))
+        .filter(|(tp, arg)| tp.default(db).as_ref() != Some(arg) && !tp.is_implicit(db))
         .map(|(_, arg)| arg.clone())
         .collect()
 }
 
+/// Amount of generics of `Expr::Method` that belong to the trait the method is declared in
+///
+/// For trait methods (but not the methods of trait impls) `generics` start with the type
+/// parameters of the trait (not counting `Self`) followed by the generics of the method itself.
+fn trait_generics_len(db: &dyn HirDatabase, func: Function) -> usize {
+    match func.as_assoc_item(db).map(|it| it.container(db)) {
+        Some(AssocItemContainer::Trait(it)) => GenericDef::from(it)
+            .type_or_const_params(db)
+            .into_iter()
+            .filter(|it| it.as_type_param(db).map_or(true, |it| !it.is_implicit(db)))
+            .count(),
+        _ => 0,
+    }
+}
+
+/// All the generic arguments of method call (including the ones of parent trait or impl) in order
+/// expected by `Function::ret_type_with_args` and similar
+pub(super) fn method_args(
+    db: &dyn HirDatabase,
+    func: Function,
+    target: &Expr,
+    generics: &[Type],
+) -> Vec<Type> {
+    let target_ty = target.ty(db);
+    match func.as_assoc_item(db).map(|it| it.container(db)) {
+        // `Self` is the first parameter of a trait
+        Some(AssocItemContainer::Trait(_)) => {
+            std::iter::once(target_ty).chain(generics.iter().cloned()).collect()
+        }
+        _ => target_ty.type_arguments().chain(generics.iter().cloned()).collect(),
+    }
+}
+
+/// Check if calling method with method syntax on value of type `ty` would be ambiguous (or resolve
+/// to another method) as other methods with the same name apply to it, such as methods of two
+/// different traits in scope
+fn is_ambiguous_method(sema_scope: &SemanticsScope<'_>, func: Function, ty: &Type) -> bool {
+    let db = sema_scope.db;
+    let mut candidates = Vec::new();
+    ty.iterate_method_candidates(db, sema_scope, None, Some(&func.name(db)), |it| {
+        if !candidates.contains(&it) {
+            candidates.push(it);
+        }
+        None::<()>
+    });
+    candidates.len() > 1
+}
+
 /// Type tree shows how can we get from set of types to some type.
 ///
 /// Consider the following code as an example
@@ -132,6 +252,8 @@ pub enum Expr {
     FamousType { ty: Type, value: &'static str },
     /// Function call (does not take self param)
     Function { func: Function, generics: Vec<Type>, params: Vec<Expr> },
+    /// Free function used as a value without calling it (`parse` in `it.map(parse)`)
+    FnItem(Function),
     /// Method call (has self param)
     Method { func: Function, generics: Vec<Type>, target: Box<Expr>, params: Vec<Expr> },
     /// Enum variant construction
@@ -140,105 +262,295 @@ pub enum Expr {
     Struct { strukt: Struct, generics: Vec<Type>, params: Vec<Expr> },
     /// Tuple construction
     Tuple { ty: Type, params: Vec<Expr> },
+    /// Array construction by listing the elements (`[a, b]`), the elements are coerced to the
+    /// element type of `ty`
+    Array { ty: Type, elems: Vec<Expr> },
+    /// Array construction by repeating `Copy` element (`[elem; N]`)
+    ArrayRepeat { ty: Type, elem: Box<Expr>, len: ArrayLen },
+    /// `Vec` with a single element built by the `vec!` macro of standard library (`vec![elem]`)
+    VecMacro { mac: Macro, ty: Type, elem: Box<Expr> },
     /// Struct field access
     Field { expr: Box<Expr>, field: Field },
+    /// Indexing value of type implementing `Index` (`expr[index]`) with `Output` type `ty`
+    Index { expr: Box<Expr>, index: Box<Expr>, ty: Type },
+    /// Addition of values with `+` operator (`lhs + rhs`) where `lhs` implements `Add`, `ty` is
+    /// the `Output` type of the impl
+    Add { lhs: Box<Expr>, rhs: Box<Expr>, ty: Type },
     /// Passing type as reference (with `&`)
     Reference(Box<Expr>),
+    /// Passing type as mutable reference (with `&mut`)
+    MutReference(Box<Expr>),
+    /// Primitive type cast (with `as`)
+    Cast { expr: Box<Expr>, ty: Type },
+    /// Dereferencing value (with `*`) of type implementing `Deref`
+    Deref { expr: Box<Expr>, ty: Type },
+    /// Unwrapping `Result` with the `?` operator (`expr?`), `ty` is the type of the `Ok` value
+    Try { expr: Box<Expr>, ty: Type },
+    /// Closure (`|x| body`) used as the closure trait type `ty` (such as `dyn Fn(i32) -> i32`),
+    /// or of unknown type `ty` when passed to generic method (`o.unwrap_or_else(|| x)`). Only the
+    /// latter capture locals, otherwise the body captures nothing but the parameters of the
+    /// closures it is nested in. Parameters the body does not use are rendered as `_`.
+    Closure { params: Vec<(&'static str, Type)>, body: Box<Expr>, ty: Type },
+    /// Parameter of closure, only found in the body of the closure
+    ClosureParam { name: &'static str, ty: Type },
     /// Indicates possibility of many different options that all evaluate to `ty`
     Many(Type),
 }
 
+/// Length of array built by repeating element
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+pub enum ArrayLen {
+    /// Length known from the array type (`[elem; 4]`)
+    Known(usize),
+    /// Length given by constant generic parameter in scope (`[elem; N]`)
+    Param(ConstParam),
+}
+
 impl Expr {
+    /// Local variable
+    pub fn local(local: Local) -> Expr {
+        Expr::Local(local)
+    }
+
+    /// Call to function that does not take self param, `None` if the arguments do not fit
+    pub fn call(
+        db: &dyn HirDatabase,
+        func: Function,
+        generics: Vec<Type>,
+        params: Vec<Expr>,
+    ) -> Option<Expr> {
+        if func.has_self_param(db) || !args_fit(db, func, &generics, &params) {
+            return None;
+        }
+        Some(Expr::Function { func, generics, params })
+    }
+
+    /// Method call on `target`, `None` if the function does not take self param or the arguments
+    /// do not fit
+    pub fn method(
+        db: &dyn HirDatabase,
+        func: Function,
+        generics: Vec<Type>,
+        target: Expr,
+        params: Vec<Expr>,
+    ) -> Option<Expr> {
+        if !func.has_self_param(db) {
+            return None;
+        }
+        let args = method_args(db, func, &target, &generics);
+        if !args_fit(db, func, &args, &params) {
+            return None;
+        }
+        Some(Expr::Method { func, generics, target: Box::new(target), params })
+    }
+
+    /// Access of struct field, `None` if `base` is not a value of the struct with the field
+    pub fn field(db: &dyn HirDatabase, base: Expr, field: Field) -> Option<Expr> {
+        match base.ty(db).as_adt() {
+            Some(Adt::Struct(it)) if it.fields(db).contains(&field) => {
+                Some(Expr::Field { expr: Box::new(base), field })
+            }
+            _ => None,
+        }
+    }
+
+    /// Shared reference to the value (`&inner`)
+    pub fn reference(inner: Expr) -> Expr {
+        Expr::Reference(Box::new(inner))
+    }
+
+    /// Mutable reference to the value (`&mut inner`)
+    pub fn mut_reference(inner: Expr) -> Expr {
+        Expr::MutReference(Box::new(inner))
+    }
+
     /// Generate source code for type tree.
     ///
     /// Note that trait imports are not added to generated code.
     /// To make sure that the code is valid, callee has to also ensure that all the traits listed
     /// by `traits_used` method are also imported.
+    /// Trees doing unsafe operations (see [`Expr::is_unsafe`]) are wrapped in an `unsafe` block.
     pub fn gen_source_code(
         &self,
         sema_scope: &SemanticsScope<'_>,
         many_formatter: &mut dyn FnMut(&Type) -> String,
         prefer_no_std: bool,
         prefer_prelude: bool,
+    ) -> Result<String, DisplaySourceCodeError> {
+        let paths = &PathCache::default();
+        self.gen_source_code_cached(
+            sema_scope,
+            many_formatter,
+            prefer_no_std,
+            prefer_prelude,
+            paths,
+        )
+    }
+
+    /// Generate source code for a batch of type trees along with the items each of them refers to
+    ///
+    /// Same as [`Expr::gen_source_code`] for every tree, but the paths to the items are only looked
+    /// up once for the whole batch, so the items used by multiple trees are referred to by the same
+    /// path in all of them. The items include the traits of the trait methods called.
+    pub fn render_all(
+        exprs: &[Expr],
+        sema_scope: &SemanticsScope<'_>,
+        many_formatter: &mut dyn FnMut(&Type) -> String,
+        prefer_no_std: bool,
+        prefer_prelude: bool,
+    ) -> Vec<Result<(String, FxHashSet<ModuleDef>), DisplaySourceCodeError>> {
+        let db = sema_scope.db;
+        let paths = &PathCache::default();
+        exprs
+            .iter()
+            .map(|expr| {
+                let code = expr.gen_source_code_cached(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                let items = super::used_items(expr)
+                    .into_iter()
+                    .chain(expr.traits_used(db).into_iter().map(ModuleDef::Trait))
+                    .collect();
+                Ok((code, items))
+            })
+            .collect()
+    }
+
+    /// Same as [`Expr::gen_source_code`], but looks the paths up in `paths` first
+    fn gen_source_code_cached(
+        &self,
+        sema_scope: &SemanticsScope<'_>,
+        many_formatter: &mut dyn FnMut(&Type) -> String,
+        prefer_no_std: bool,
+        prefer_prelude: bool,
+        paths: &PathCache,
+    ) -> Result<String, DisplaySourceCodeError> {
+        let code = self.gen_source_code_inner(
+            sema_scope,
+            many_formatter,
+            prefer_no_std,
+            prefer_prelude,
+            paths,
+        )?;
+        match self.is_unsafe(sema_scope.db) {
+            true => Ok(format!("unsafe {{ {code} }}")),
+            false => Ok(code),
+        }
+    }
+
+    fn gen_source_code_inner(
+        &self,
+        sema_scope: &SemanticsScope<'_>,
+        many_formatter: &mut dyn FnMut(&Type) -> String,
+        prefer_no_std: bool,
+        prefer_prelude: bool,
+        paths: &PathCache,
     ) -> Result<String, DisplaySourceCodeError> {
         let db = sema_scope.db;
-        let mod_item_path_str = |s, def| mod_item_path_str(s, def, prefer_no_std, prefer_prelude);
+        let mod_item_path_str =
+            |s, def| mod_item_path_str(s, def, prefer_no_std, prefer_prelude, paths);
         match self {
             Expr::Const(it) => mod_item_path_str(sema_scope, &ModuleDef::Const(*it)),
             Expr::Static(it) => mod_item_path_str(sema_scope, &ModuleDef::Static(*it)),
+            Expr::FnItem(it) => mod_item_path_str(sema_scope, &ModuleDef::Function(*it)),
             Expr::Local(it) => Ok(it.name(db).display(db.upcast()).to_string()),
             Expr::ConstParam(it) => Ok(it.name(db).display(db.upcast()).to_string()),
             Expr::FamousType { value, .. } => Ok(value.to_string()),
-            Expr::Function { func, params, .. } => {
-                let args = params
-                    .iter()
-                    .map(|f| {
-                        f.gen_source_code(sema_scope, many_formatter, prefer_no_std, prefer_prelude)
-                    })
-                    .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
-                    .into_iter()
-                    .join(", ");
-
-                match func.as_assoc_item(db).map(|it| it.container(db)) {
-                    Some(container) => {
-                        let container_name = match container {
-                            crate::AssocItemContainer::Trait(trait_) => {
-                                mod_item_path_str(sema_scope, &ModuleDef::Trait(trait_))?
-                            }
-                            crate::AssocItemContainer::Impl(imp) => {
-                                let self_ty = imp.self_ty(db);
-                                // Should it be guaranteed that `mod_item_path` always exists?
-                                match self_ty.as_adt().and_then(|adt| {
-                                    mod_item_path(
-                                        sema_scope,
-                                        &adt.into(),
-                                        prefer_no_std,
-                                        prefer_prelude,
-                                    )
-                                }) {
-                                    Some(path) => path.display(sema_scope.db.upcast()).to_string(),
-                                    None => self_ty.display(db).to_string(),
-                                }
-                            }
-                        };
-                        let fn_name = func.name(db).display(db.upcast()).to_string();
-                        Ok(format!("{container_name}::{fn_name}({args})"))
-                    }
-                    None => {
-                        let fn_name = mod_item_path_str(sema_scope, &ModuleDef::Function(*func))?;
-                        Ok(format!("{fn_name}({args})"))
-                    }
-                }
-            }
-            Expr::Method { func, target, params, .. } => {
+            Expr::Function { .. } => self.gen_function_call(
+                sema_scope,
+                many_formatter,
+                prefer_no_std,
+                prefer_prelude,
+                paths,
+                false,
+            ),
+            Expr::Method { func, generics, target, params } => {
                 if self.contains_many_in_illegal_pos(db) {
                     return Ok(many_formatter(&target.ty(db)));
                 }
 
-                let func_name = func.name(db).display(db.upcast()).to_string();
+                // Generics of the method (or its trait) do not have to be inferable from the
+                // arguments, so spell them out unless the method ones are (`it.map(parse)`)
+                let (trait_generics, generics) = generics.split_at(trait_generics_len(db, *func));
+                let turbofish = |generics: &[Type]| -> Result<String, DisplaySourceCodeError> {
+                    if generics.is_empty() {
+                        return Ok(String::new());
+                    }
+                    let generics = generics
+                        .iter()
+                        .map(|it| type_path(sema_scope, it, prefer_no_std, prefer_prelude, paths))
+                        .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
+                        .into_iter()
+                        .join(", ");
+                    Ok(format!("::<{generics}>"))
+                };
+                let generics = match func.generics_inferred_from_args(db) {
+                    true => Vec::new(),
+                    false => non_default_generics(db, (*func).into(), generics),
+                };
+                let func_name =
+                    format!("{}{}", func.name(db).display(db.upcast()), turbofish(&generics)?);
                 let self_param = func.self_param(db).unwrap();
-                let target_str = target.gen_source_code(
+                let target_str = target.gen_receiver_source_code(
                     sema_scope,
                     many_formatter,
                     prefer_no_std,
                     prefer_prelude,
+                    paths,
                 )?;
+                // Taking reference of dereference (`&*x`) does not need parenthesis, unlike the
+                // method calls on it
+                let borrowed_str = match **target {
+                    Expr::Deref { .. } => target_str.clone(),
+                    _ => target.parenthesize(target_str.clone()),
+                };
+                let target_str = target.parenthesize(target_str);
                 let args = params
                     .iter()
                     .map(|f| {
-                        f.gen_source_code(sema_scope, many_formatter, prefer_no_std, prefer_prelude)
+                        f.gen_source_code_inner(
+                            sema_scope,
+                            many_formatter,
+                            prefer_no_std,
+                            prefer_prelude,
+                            paths,
+                        )
                     })
                     .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
                     .into_iter()
                     .join(", ");
 
-                match func.as_assoc_item(db).and_then(|it| it.container_or_implemented_trait(db)) {
+                // Chains of calls on temporaries such as `v.iter().next()` read better with method
+                // syntax, and so do calls borrowing locals such as `n.to_string()`. Locals of
+                // reference types are left out, as autoderef could pick the method of the
+                // referenced type. Method syntax only resolves if the trait is in scope and no
+                // other method in scope shares the name, and it cannot name trait generics
+                let borrows_local = matches!(**target, Expr::Local(_))
+                    && !target.ty(db).is_reference()
+                    && matches!(self_param.access(db), crate::Access::Shared);
+                let trait_ = func
+                    .as_assoc_item(db)
+                    .and_then(|it| it.container_or_implemented_trait(db))
+                    .filter(|it| {
+                        !(matches!(**target, Expr::Method { .. }) || borrows_local)
+                            || !trait_generics.is_empty()
+                            || !sema_scope.visible_traits().0.contains(&it.id)
+                            || is_ambiguous_method(sema_scope, *func, &target.ty(db))
+                    });
+                match trait_ {
                     Some(trait_) => {
                         let trait_name = mod_item_path_str(sema_scope, &ModuleDef::Trait(trait_))?;
+                        let trait_name = format!("{trait_name}{}", turbofish(trait_generics)?);
                         let target = match self_param.access(db) {
-                            crate::Access::Shared if !target.is_many() => format!("&{target_str}"),
+                            crate::Access::Shared if !target.is_many() => {
+                                format!("&{borrowed_str}")
+                            }
                             crate::Access::Exclusive if !target.is_many() => {
-                                format!("&mut {target_str}")
+                                format!("&mut {borrowed_str}")
                             }
                             crate::Access::Owned => target_str,
                             _ => many_formatter(&target.ty(db)),
@@ -259,7 +571,9 @@ impl Expr {
                     false => {
                         let generics = generics
                             .iter()
-                            .map(|it| type_path(sema_scope, it, prefer_no_std, prefer_prelude))
+                            .map(|it| {
+                                type_path(sema_scope, it, prefer_no_std, prefer_prelude, paths)
+                            })
                             .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
                             .into_iter()
                             .join(", ");
@@ -271,11 +585,12 @@ impl Expr {
                         let args = params
                             .iter()
                             .map(|f| {
-                                f.gen_source_code(
+                                f.gen_source_code_inner(
                                     sema_scope,
                                     many_formatter,
                                     prefer_no_std,
                                     prefer_prelude,
+                                    paths,
                                 )
                             })
                             .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
@@ -292,11 +607,12 @@ impl Expr {
                                 let tmp = format!(
                                     "{}: {}",
                                     f.name(db).display(db.upcast()),
-                                    a.gen_source_code(
+                                    a.gen_source_code_inner(
                                         sema_scope,
                                         many_formatter,
                                         prefer_no_std,
-                                        prefer_prelude
+                                        prefer_prelude,
+                                        paths
                                     )?
                                 );
                                 Ok(tmp)
@@ -319,11 +635,12 @@ impl Expr {
                         let args = params
                             .iter()
                             .map(|a| {
-                                a.gen_source_code(
+                                a.gen_source_code_inner(
                                     sema_scope,
                                     many_formatter,
                                     prefer_no_std,
                                     prefer_prelude,
+                                    paths,
                                 )
                             })
                             .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
@@ -340,11 +657,12 @@ impl Expr {
                                 let tmp = format!(
                                     "{}: {}",
                                     f.name(db).display(db.upcast()),
-                                    a.gen_source_code(
+                                    a.gen_source_code_inner(
                                         sema_scope,
                                         many_formatter,
                                         prefer_no_std,
-                                        prefer_prelude
+                                        prefer_prelude,
+                                        paths
                                     )?
                                 );
                                 Ok(tmp)
@@ -359,7 +677,9 @@ impl Expr {
                         false => {
                             let generics = generics
                                 .iter()
-                                .map(|it| type_path(sema_scope, it, prefer_no_std, prefer_prelude))
+                                .map(|it| {
+                                    type_path(sema_scope, it, prefer_no_std, prefer_prelude, paths)
+                                })
                                 .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
                                 .into_iter()
                                 .join(", ");
@@ -375,7 +695,13 @@ impl Expr {
                 let args = params
                     .iter()
                     .map(|a| {
-                        a.gen_source_code(sema_scope, many_formatter, prefer_no_std, prefer_prelude)
+                        a.gen_source_code_inner(
+                            sema_scope,
+                            many_formatter,
+                            prefer_no_std,
+                            prefer_prelude,
+                            paths,
+                        )
                     })
                     .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
                     .into_iter()
@@ -383,37 +709,335 @@ impl Expr {
                 let res = format!("({args})");
                 Ok(res)
             }
+            Expr::Array { elems, .. } => {
+                let elems = elems
+                    .iter()
+                    .map(|a| {
+                        a.gen_source_code_inner(
+                            sema_scope,
+                            many_formatter,
+                            prefer_no_std,
+                            prefer_prelude,
+                            paths,
+                        )
+                    })
+                    .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
+                    .into_iter()
+                    .join(", ");
+                Ok(format!("[{elems}]"))
+            }
+            Expr::ArrayRepeat { elem, len, .. } => {
+                let elem = elem.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                let len = match len {
+                    ArrayLen::Known(it) => it.to_string(),
+                    ArrayLen::Param(it) => it.name(db).display(db.upcast()).to_string(),
+                };
+                Ok(format!("[{elem}; {len}]"))
+            }
+            Expr::VecMacro { mac, elem, .. } => {
+                let elem = elem.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                Ok(format!("{}![{elem}]", mac.name(db).display(db.upcast())))
+            }
             Expr::Field { expr, field } => {
                 if expr.contains_many_in_illegal_pos(db) {
                     return Ok(many_formatter(&expr.ty(db)));
                 }
 
-                let strukt = expr.gen_source_code(
+                let strukt = expr.gen_receiver_source_code(
                     sema_scope,
                     many_formatter,
                     prefer_no_std,
                     prefer_prelude,
+                    paths,
                 )?;
+                let strukt = expr.parenthesize(strukt);
                 let field = field.name(db).display(db.upcast()).to_string();
                 Ok(format!("{strukt}.{field}"))
             }
-            Expr::Reference(expr) => {
+            Expr::Index { expr, index, ty } => {
+                if expr.contains_many_in_illegal_pos(db) {
+                    return Ok(many_formatter(ty));
+                }
+
+                let container = expr.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                let container = expr.parenthesize(container);
+                let index = index.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                Ok(format!("{container}[{index}]"))
+            }
+            Expr::Add { lhs, rhs, .. } => {
+                let lhs = lhs.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                let rhs_code = rhs.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                // Addition is left associative, only the right hand side needs parenthesis
+                let rhs = match **rhs {
+                    Expr::Add { .. } => format!("({rhs_code})"),
+                    _ => rhs_code,
+                };
+                Ok(format!("{lhs} + {rhs}"))
+            }
+            Expr::Reference(expr) | Expr::MutReference(expr) => {
                 if expr.contains_many_in_illegal_pos(db) {
                     return Ok(many_formatter(&expr.ty(db)));
                 }
 
-                let inner = expr.gen_source_code(
+                let inner = expr.gen_source_code_inner(
                     sema_scope,
                     many_formatter,
                     prefer_no_std,
                     prefer_prelude,
+                    paths,
                 )?;
-                Ok(format!("&{inner}"))
+                let inner = match **expr {
+                    Expr::Deref { .. } | Expr::Closure { .. } => inner,
+                    _ => expr.parenthesize(inner),
+                };
+                match self {
+                    Expr::MutReference(_) => Ok(format!("&mut {inner}")),
+                    _ => Ok(format!("&{inner}")),
+                }
             }
+            Expr::Cast { expr, ty } => {
+                if expr.contains_many_in_illegal_pos(db) {
+                    return Ok(many_formatter(ty));
+                }
+
+                let inner = expr.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                let ty = type_path(sema_scope, ty, prefer_no_std, prefer_prelude, paths)?;
+                Ok(format!("{inner} as {ty}"))
+            }
+            Expr::Deref { expr, ty } => {
+                if expr.contains_many_in_illegal_pos(db) {
+                    return Ok(many_formatter(ty));
+                }
+
+                let inner = expr.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                // Dereferences are prefix operators like the dereference itself, so `**x` is fine
+                let inner = match **expr {
+                    Expr::Deref { .. } => inner,
+                    _ => expr.parenthesize(inner),
+                };
+                Ok(format!("*{inner}"))
+            }
+            Expr::Try { expr, ty } => {
+                if expr.contains_many_in_illegal_pos(db) {
+                    return Ok(many_formatter(ty));
+                }
+
+                let inner = expr.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                let inner = expr.parenthesize(inner);
+                Ok(format!("{inner}?"))
+            }
+            Expr::Closure { params, body, .. } => {
+                let used = body.free_closure_params();
+                let params = params
+                    .iter()
+                    .map(|(name, _)| if used.contains(name) { *name } else { "_" })
+                    .join(", ");
+                // Parameters of the enclosing closures are captured by value, the closure can
+                // outlive them
+                let capture = if self.free_closure_params().is_empty() { "" } else { "move " };
+                let body = body.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )?;
+                Ok(format!("{capture}|{params}| {body}"))
+            }
+            Expr::ClosureParam { name, .. } => Ok(name.to_string()),
             Expr::Many(ty) => Ok(many_formatter(ty)),
         }
     }
 
+    /// Generate source code for call of free or associated function
+    ///
+    /// With `self_turbofish` the generics of the `Self` type of associated functions are spelled
+    /// out (`Vec::<i32>::new()`), for the calls in positions where they cannot be inferred.
+    fn gen_function_call(
+        &self,
+        sema_scope: &SemanticsScope<'_>,
+        many_formatter: &mut dyn FnMut(&Type) -> String,
+        prefer_no_std: bool,
+        prefer_prelude: bool,
+        paths: &PathCache,
+        self_turbofish: bool,
+    ) -> Result<String, DisplaySourceCodeError> {
+        let db = sema_scope.db;
+        let mod_item_path_str =
+            |s, def| mod_item_path_str(s, def, prefer_no_std, prefer_prelude, paths);
+        let Expr::Function { func, generics, params } = self else {
+            return self.gen_source_code_inner(
+                sema_scope,
+                many_formatter,
+                prefer_no_std,
+                prefer_prelude,
+                paths,
+            );
+        };
+        let args = params
+            .iter()
+            .map(|f| {
+                f.gen_source_code_inner(
+                    sema_scope,
+                    many_formatter,
+                    prefer_no_std,
+                    prefer_prelude,
+                    paths,
+                )
+            })
+            .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?
+            .into_iter()
+            .join(", ");
+
+        match func.as_assoc_item(db).map(|it| it.container(db)) {
+            Some(container) => {
+                let container_name = match container {
+                    crate::AssocItemContainer::Trait(trait_) => {
+                        let trait_path = mod_item_path_str(sema_scope, &ModuleDef::Trait(trait_))?;
+                        // `generics` start with the `Self` type, which can not be inferred from
+                        // the arguments of functions without self parameter
+                        match generics.first() {
+                            Some(self_ty) if !func.has_self_param(db) => {
+                                let ty_path = self_ty.display_source_code(
+                                    db,
+                                    sema_scope.module().id,
+                                    true,
+                                )?;
+                                let in_scope = sema_scope.visible_traits().0.contains(&trait_.id);
+                                match in_scope && self_ty.type_arguments().next().is_none() {
+                                    true => ty_path,
+                                    false => format!("<{ty_path} as {trait_path}>"),
+                                }
+                            }
+                            _ => trait_path,
+                        }
+                    }
+                    crate::AssocItemContainer::Impl(imp) => {
+                        let self_ty = imp.self_ty(db);
+                        // Should it be guaranteed that `mod_item_path` always exists?
+                        let path = match self_ty.as_adt().and_then(|adt| {
+                            mod_item_path(
+                                sema_scope,
+                                &adt.into(),
+                                prefer_no_std,
+                                prefer_prelude,
+                                paths,
+                            )
+                        }) {
+                            Some(path) => path.display(sema_scope.db.upcast()).to_string(),
+                            None => self_ty.display(db).to_string(),
+                        };
+                        // `generics` start with the arguments of the `Self` type
+                        let adt_generics = match (self_turbofish, self_ty.as_adt()) {
+                            (true, Some(adt)) => non_default_generics(db, adt.into(), generics),
+                            _ => Vec::new(),
+                        };
+                        match adt_generics.is_empty() {
+                            true => path,
+                            false => {
+                                let generics = adt_generics
+                                    .iter()
+                                    .map(|it| {
+                                        type_path(
+                                            sema_scope,
+                                            it,
+                                            prefer_no_std,
+                                            prefer_prelude,
+                                            paths,
+                                        )
+                                    })
+                                    .collect::<Result<Vec<String>, DisplaySourceCodeError>>()?;
+                                format!("{path}::<{}>", generics.join(", "))
+                            }
+                        }
+                    }
+                };
+                let fn_name = func.name(db).display(db.upcast()).to_string();
+                Ok(format!("{container_name}::{fn_name}({args})"))
+            }
+            None => {
+                let fn_name = mod_item_path_str(sema_scope, &ModuleDef::Function(*func))?;
+                Ok(format!("{fn_name}({args})"))
+            }
+        }
+    }
+
+    /// Generate source code for the receiver of method call or field access
+    ///
+    /// The type of the receiver is not known from the context, so the generics of the types of
+    /// associated function calls are spelled out.
+    fn gen_receiver_source_code(
+        &self,
+        sema_scope: &SemanticsScope<'_>,
+        many_formatter: &mut dyn FnMut(&Type) -> String,
+        prefer_no_std: bool,
+        prefer_prelude: bool,
+        paths: &PathCache,
+    ) -> Result<String, DisplaySourceCodeError> {
+        self.gen_function_call(
+            sema_scope,
+            many_formatter,
+            prefer_no_std,
+            prefer_prelude,
+            paths,
+            true,
+        )
+    }
+
     /// Get type of the type tree.
     ///
     /// Same as getting the type of root node
@@ -421,25 +1045,35 @@ impl Expr {
         match self {
             Expr::Const(it) => it.ty(db),
             Expr::Static(it) => it.ty(db),
+            Expr::FnItem(it) => it.ty(db),
             Expr::Local(it) => it.ty(db),
             Expr::ConstParam(it) => it.ty(db),
             Expr::FamousType { ty, .. } => ty.clone(),
             Expr::Function { func, generics, .. } => {
-                func.ret_type_with_args(db, generics.iter().cloned())
+                func.ret_type_with_args(db, generics.iter().cloned()).normalize(db)
             }
-            Expr::Method { func, generics, target, .. } => func.ret_type_with_args(
-                db,
-                target.ty(db).type_arguments().chain(generics.iter().cloned()),
-            ),
+            Expr::Method { func, generics, target, .. } => func
+                .ret_type_with_args(db, method_args(db, *func, target, generics).into_iter())
+                .normalize(db),
             Expr::Variant { variant, generics, .. } => {
                 Adt::from(variant.parent_enum(db)).ty_with_args(db, generics.iter().cloned())
             }
             Expr::Struct { strukt, generics, .. } => {
                 Adt::from(*strukt).ty_with_args(db, generics.iter().cloned())
             }
-            Expr::Tuple { ty, .. } => ty.clone(),
+            Expr::Tuple { ty, .. }
+            | Expr::Array { ty, .. }
+            | Expr::ArrayRepeat { ty, .. }
+            | Expr::VecMacro { ty, .. } => ty.clone(),
             Expr::Field { expr, field } => field.ty_with_args(db, expr.ty(db).type_arguments()),
-            Expr::Reference(it) => it.ty(db),
+            Expr::Reference(it) | Expr::MutReference(it) => it.ty(db),
+            Expr::Index { ty, .. }
+            | Expr::Add { ty, .. }
+            | Expr::Cast { ty, .. }
+            | Expr::Deref { ty, .. }
+            | Expr::Try { ty, .. }
+            | Expr::Closure { ty, .. }
+            | Expr::ClosureParam { ty, .. } => ty.clone(),
             Expr::Many(ty) => ty.clone(),
         }
     }
@@ -478,14 +1112,423 @@ impl Expr {
                 }
             }
             Expr::Field { expr, .. } => expr.contains_many_in_illegal_pos(db),
-            Expr::Reference(target) => target.is_many(),
+            Expr::Index { expr, .. } => expr.is_many() || expr.contains_many_in_illegal_pos(db),
+            Expr::Reference(target) | Expr::MutReference(target) => target.is_many(),
+            Expr::Cast { expr, .. } | Expr::Deref { expr, .. } | Expr::Try { expr, .. } => {
+                expr.is_many()
+            }
             Expr::Many(_) => true,
             _ => false,
         }
     }
 
+    /// Wrap generated source code of the expression in parenthesis if it cannot be used as
+    /// operand for method call, field access or taking a reference as is (for example `a as i32`)
+    fn parenthesize(&self, code: String) -> String {
+        match self {
+            Expr::Cast { .. } | Expr::Deref { .. } | Expr::Add { .. } | Expr::Closure { .. } => {
+                format!("({code})")
+            }
+            _ => code,
+        }
+    }
+
     /// Helper function to check if outermost type tree is `Expr::Many` variant
     pub fn is_many(&self) -> bool {
         matches!(self, Expr::Many(_))
     }
+
+    /// Complexity of the type tree measured as the amount of nodes in it
+    ///
+    /// Leaves such as locals, constants and calls without arguments have complexity of 1.
+    pub fn complexity(&self) -> usize {
+        let children: usize = match self {
+            Expr::Const(_)
+            | Expr::Static(_)
+            | Expr::FnItem(_)
+            | Expr::Local(_)
+            | Expr::ConstParam(_)
+            | Expr::FamousType { .. }
+            | Expr::ClosureParam { .. }
+            | Expr::Many(_) => 0,
+            Expr::Function { params, .. }
+            | Expr::Variant { params, .. }
+            | Expr::Struct { params, .. }
+            | Expr::Tuple { params, .. }
+            | Expr::Array { elems: params, .. } => params.iter().map(Expr::complexity).sum(),
+            Expr::Method { target, params, .. } => {
+                target.complexity() + params.iter().map(Expr::complexity).sum::<usize>()
+            }
+            Expr::Index { expr, index, .. } => expr.complexity() + index.complexity(),
+            Expr::Add { lhs, rhs, .. } => lhs.complexity() + rhs.complexity(),
+            Expr::ArrayRepeat { elem, .. } | Expr::VecMacro { elem, .. } => elem.complexity(),
+            Expr::Field { expr, .. }
+            | Expr::Reference(expr)
+            | Expr::MutReference(expr)
+            | Expr::Cast { expr, .. }
+            | Expr::Deref { expr, .. }
+            | Expr::Try { expr, .. }
+            | Expr::Closure { body: expr, .. } => expr.complexity(),
+        };
+        children + 1
+    }
+
+    /// Depth of the type tree measured as the amount of nodes on its longest path from the root
+    ///
+    /// Leaves have depth of 1, `f(&a)` has depth of 3. Computed without recursion so that it works
+    /// on arbitrarily deep trees.
+    pub fn depth(&self) -> usize {
+        let mut max = 0;
+        let mut stack = vec![(self, 1)];
+        while let Some((expr, depth)) = stack.pop() {
+            max = max.max(depth);
+            stack.extend(expr.children().into_iter().map(|it| (it, depth + 1)));
+        }
+        max
+    }
+
+    /// Key of the type tree that is stable across runs, for example for persistent caches
+    ///
+    /// Hash of the semantic form of the tree built from the paths to the items it uses, the
+    /// rendered types and the structure of the tree, never from the in-memory ids. Method calls
+    /// hash the same as the equivalent function calls (`x.foo()` and `S::foo(&x)`), so trees only
+    /// differing in the call syntax get the same key.
+    pub fn stable_key(&self, db: &dyn HirDatabase) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.hash_stable(db, &mut hasher);
+        hasher.finish()
+    }
+
+    fn hash_stable(&self, db: &dyn HirDatabase, state: &mut FxHasher) {
+        let hash_all = |exprs: &[Expr], state: &mut FxHasher| {
+            exprs.len().hash(state);
+            exprs.iter().for_each(|it| it.hash_stable(db, state));
+        };
+        let hash_types = |tys: &[Type], state: &mut FxHasher| {
+            tys.len().hash(state);
+            tys.iter().for_each(|it| it.display(db).to_string().hash(state));
+        };
+        match self {
+            Expr::Const(it) => ("const", stable_path(db, ModuleDef::Const(*it))).hash(state),
+            Expr::Static(it) => ("static", stable_path(db, ModuleDef::Static(*it))).hash(state),
+            Expr::FnItem(it) => ("fn item", stable_path(db, ModuleDef::Function(*it))).hash(state),
+            Expr::Local(it) => {
+                let name = it.name(db).display(db.upcast()).to_string();
+                ("local", name, it.ty(db).display(db).to_string()).hash(state)
+            }
+            Expr::ConstParam(it) => {
+                ("const param", it.name(db).display(db.upcast()).to_string()).hash(state)
+            }
+            Expr::FamousType { ty, value } => {
+                ("famous", ty.display(db).to_string(), value).hash(state)
+            }
+            Expr::Function { func, generics, params } => {
+                ("call", stable_path(db, ModuleDef::Function(*func))).hash(state);
+                hash_types(generics, state);
+                hash_all(params, state);
+            }
+            Expr::Method { func, generics, target, params } => {
+                ("call", stable_path(db, ModuleDef::Function(*func))).hash(state);
+                hash_types(generics, state);
+                // Hashed as the receiver passed to the function explicitly
+                let target = match func.self_param(db).map(|it| it.access(db)) {
+                    Some(crate::Access::Shared) => Expr::Reference(target.clone()),
+                    Some(crate::Access::Exclusive) => Expr::MutReference(target.clone()),
+                    _ => (**target).clone(),
+                };
+                hash_all(&iter::once(target).chain(params.iter().cloned()).collect_vec(), state);
+            }
+            Expr::Variant { variant, generics, params } => {
+                ("variant", stable_path(db, ModuleDef::Variant(*variant))).hash(state);
+                hash_types(generics, state);
+                hash_all(params, state);
+            }
+            Expr::Struct { strukt, generics, params } => {
+                ("struct", stable_path(db, ModuleDef::Adt(Adt::Struct(*strukt)))).hash(state);
+                hash_types(generics, state);
+                hash_all(params, state);
+            }
+            Expr::Tuple { params, .. } => {
+                "tuple".hash(state);
+                hash_all(params, state);
+            }
+            Expr::Array { ty, elems } => {
+                ("array", ty.display(db).to_string()).hash(state);
+                hash_all(elems, state);
+            }
+            Expr::ArrayRepeat { elem, len, .. } => {
+                match len {
+                    ArrayLen::Known(it) => ("array repeat", it).hash(state),
+                    ArrayLen::Param(it) => {
+                        ("array repeat", it.name(db).display(db.upcast()).to_string()).hash(state)
+                    }
+                }
+                elem.hash_stable(db, state);
+            }
+            Expr::VecMacro { mac, elem, .. } => {
+                ("vec", stable_path(db, ModuleDef::Macro(*mac))).hash(state);
+                elem.hash_stable(db, state);
+            }
+            Expr::Field { expr, field } => {
+                let parent = match field.parent_def(db) {
+                    VariantDef::Struct(it) => ModuleDef::Adt(Adt::Struct(it)),
+                    VariantDef::Union(it) => ModuleDef::Adt(Adt::Union(it)),
+                    VariantDef::Variant(it) => ModuleDef::Variant(it),
+                };
+                let name = field.name(db).display(db.upcast()).to_string();
+                ("field", stable_path(db, parent), name).hash(state);
+                expr.hash_stable(db, state);
+            }
+            Expr::Index { expr, index, .. } => {
+                "index".hash(state);
+                expr.hash_stable(db, state);
+                index.hash_stable(db, state);
+            }
+            Expr::Add { lhs, rhs, .. } => {
+                "add".hash(state);
+                lhs.hash_stable(db, state);
+                rhs.hash_stable(db, state);
+            }
+            Expr::Reference(expr) => {
+                "ref".hash(state);
+                expr.hash_stable(db, state);
+            }
+            Expr::MutReference(expr) => {
+                "mut ref".hash(state);
+                expr.hash_stable(db, state);
+            }
+            Expr::Cast { expr, ty } => {
+                ("cast", ty.display(db).to_string()).hash(state);
+                expr.hash_stable(db, state);
+            }
+            Expr::Deref { expr, .. } => {
+                "deref".hash(state);
+                expr.hash_stable(db, state);
+            }
+            Expr::Try { expr, .. } => {
+                "try".hash(state);
+                expr.hash_stable(db, state);
+            }
+            Expr::Closure { params, body, .. } => {
+                "closure".hash(state);
+                hash_types(&params.iter().map(|(_, ty)| ty.clone()).collect_vec(), state);
+                body.hash_stable(db, state);
+            }
+            Expr::ClosureParam { name, ty } => {
+                ("closure param", name, ty.display(db).to_string()).hash(state)
+            }
+            Expr::Many(ty) => ("many", ty.display(db).to_string()).hash(state),
+        }
+    }
+
+    /// Check if evaluating the type tree requires an `unsafe` block
+    ///
+    /// This is the case for calls to `unsafe fn`s, reads of `static mut`s, reads of union fields
+    /// and dereferences of raw pointers anywhere in the tree.
+    pub fn is_unsafe(&self, db: &dyn HirDatabase) -> bool {
+        let mut stack = vec![self];
+        while let Some(expr) = stack.pop() {
+            let unsafe_op = match expr {
+                Expr::Function { func, .. } | Expr::Method { func, .. } => {
+                    func.is_unsafe_to_call(db)
+                }
+                Expr::Static(it) => it.is_mut(db),
+                Expr::Field { field, .. } => matches!(field.parent_def(db), VariantDef::Union(_)),
+                Expr::Deref { expr, .. } => expr.ty(db).is_raw_ptr(),
+                _ => false,
+            };
+            if unsafe_op {
+                return true;
+            }
+            stack.extend(expr.children());
+        }
+        false
+    }
+
+    /// Generate source code for type tree with shared subexpressions hoisted into `let`s.
+    ///
+    /// Non trivial subexpressions used more than once (such as `f(a)` in `g(f(a), f(a))`) are
+    /// evaluated once in a block instead (`{ let tmp = f(a); g(tmp, tmp) }`). Only subexpressions
+    /// of `Copy` types or ones only used behind shared references are hoisted so that no value is
+    /// moved twice. Without shared subexpressions this is the same as `gen_source_code`.
+    pub fn gen_source_code_hoisted(
+        &self,
+        sema_scope: &SemanticsScope<'_>,
+        many_formatter: &mut dyn FnMut(&Type) -> String,
+        prefer_no_std: bool,
+        prefer_prelude: bool,
+    ) -> Result<String, DisplaySourceCodeError> {
+        const NAMES: [&str; 6] = ["tmp", "tmp1", "tmp2", "tmp3", "tmp4", "tmp5"];
+        let db = sema_scope.db;
+        let paths = &PathCache::default();
+        let mut taken = Vec::new();
+        sema_scope.process_all_names(&mut |name, _| taken.push(name));
+        let mut names =
+            NAMES.into_iter().filter(|it| !taken.iter().any(|n| n.as_str() == Some(*it)));
+
+        let mut rest = self.clone();
+        let mut bindings = Vec::new();
+        while let Some(shared) = rest.largest_shared_subexpr(db) {
+            let Some(name) = names.next() else { break };
+            let placeholder = Expr::FamousType { ty: shared.ty(db), value: name };
+            rest = rest.replaced(&shared, &placeholder);
+            bindings.push((name, shared));
+        }
+
+        let mut gen = |expr: &Expr| {
+            expr.gen_source_code_inner(
+                sema_scope,
+                many_formatter,
+                prefer_no_std,
+                prefer_prelude,
+                paths,
+            )
+        };
+        let rest = gen(&rest)?;
+        let block = match self.is_unsafe(db) {
+            true => "unsafe ",
+            false if bindings.is_empty() => return Ok(rest),
+            false => "",
+        };
+        let lets = bindings
+            .iter()
+            .map(|(name, expr)| Ok(format!("let {name} = {}; ", gen(expr)?)))
+            .collect::<Result<String, DisplaySourceCodeError>>()?;
+        Ok(format!("{block}{{ {lets}{rest} }}"))
+    }
+
+    /// Biggest non trivial subexpression that is used more than once and can be hoisted
+    fn largest_shared_subexpr(&self, db: &dyn HirDatabase) -> Option<Expr> {
+        fn contains_many(expr: &Expr) -> bool {
+            expr.is_many() || expr.children().into_iter().any(contains_many)
+        }
+
+        // Amount of the uses and whether all of them are behind shared references
+        let mut uses: FxIndexMap<&Expr, (usize, bool)> = FxIndexMap::default();
+        let mut stack = vec![(self, false)];
+        while let Some((expr, referenced)) = stack.pop() {
+            if expr.complexity() > 1 && !matches!(expr, Expr::Reference(_) | Expr::MutReference(_))
+            {
+                let (count, all_referenced) = uses.entry(expr).or_insert((0, true));
+                *count += 1;
+                *all_referenced &= referenced;
+            }
+            // Subexpressions of closure bodies are evaluated on every call, possibly using the
+            // parameters, so they are never hoisted out of the closure
+            if matches!(expr, Expr::Closure { .. }) {
+                continue;
+            }
+            let is_reference = matches!(expr, Expr::Reference(_));
+            stack.extend(expr.children().into_iter().map(|it| (it, is_reference)));
+        }
+
+        uses.into_iter()
+            .filter(|(expr, (count, all_referenced))| {
+                *count > 1 && (*all_referenced || expr.ty(db).is_copy(db)) && !contains_many(expr)
+            })
+            .max_by_key(|(expr, _)| expr.complexity())
+            .map(|(expr, _)| expr.clone())
+    }
+
+    /// Direct subexpressions of the type tree
+    pub(super) fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Const(_)
+            | Expr::Static(_)
+            | Expr::FnItem(_)
+            | Expr::Local(_)
+            | Expr::ConstParam(_)
+            | Expr::FamousType { .. }
+            | Expr::ClosureParam { .. }
+            | Expr::Many(_) => Vec::new(),
+            Expr::Function { params, .. }
+            | Expr::Variant { params, .. }
+            | Expr::Struct { params, .. }
+            | Expr::Tuple { params, .. }
+            | Expr::Array { elems: params, .. } => params.iter().collect(),
+            Expr::Method { target, params, .. } => iter::once(&**target).chain(params).collect(),
+            Expr::Index { expr, index, .. } => vec![&**expr, &**index],
+            Expr::Add { lhs, rhs, .. } => vec![&**lhs, &**rhs],
+            Expr::ArrayRepeat { elem: expr, .. }
+            | Expr::VecMacro { elem: expr, .. }
+            | Expr::Field { expr, .. }
+            | Expr::Reference(expr)
+            | Expr::MutReference(expr)
+            | Expr::Cast { expr, .. }
+            | Expr::Deref { expr, .. }
+            | Expr::Try { expr, .. }
+            | Expr::Closure { body: expr, .. } => vec![&**expr],
+        }
+    }
+
+    /// Copy of the type tree with all the occurrences of `from` replaced with `to`
+    fn replaced(&self, from: &Expr, to: &Expr) -> Expr {
+        if self == from {
+            return to.clone();
+        }
+        let params = |params: &[Expr]| params.iter().map(|it| it.replaced(from, to)).collect();
+        let boxed = |expr: &Expr| Box::new(expr.replaced(from, to));
+        match self {
+            Expr::Const(_)
+            | Expr::Static(_)
+            | Expr::FnItem(_)
+            | Expr::Local(_)
+            | Expr::ConstParam(_)
+            | Expr::FamousType { .. }
+            | Expr::ClosureParam { .. }
+            | Expr::Many(_) => self.clone(),
+            Expr::Function { func, generics, params: p } => {
+                Expr::Function { func: *func, generics: generics.clone(), params: params(p) }
+            }
+            Expr::Method { func, generics, target, params: p } => Expr::Method {
+                func: *func,
+                generics: generics.clone(),
+                target: boxed(target),
+                params: params(p),
+            },
+            Expr::Variant { variant, generics, params: p } => {
+                Expr::Variant { variant: *variant, generics: generics.clone(), params: params(p) }
+            }
+            Expr::Struct { strukt, generics, params: p } => {
+                Expr::Struct { strukt: *strukt, generics: generics.clone(), params: params(p) }
+            }
+            Expr::Tuple { ty, params: p } => Expr::Tuple { ty: ty.clone(), params: params(p) },
+            Expr::Array { ty, elems } => Expr::Array { ty: ty.clone(), elems: params(elems) },
+            Expr::ArrayRepeat { ty, elem, len } => {
+                Expr::ArrayRepeat { ty: ty.clone(), elem: boxed(elem), len: *len }
+            }
+            Expr::VecMacro { mac, ty, elem } => {
+                Expr::VecMacro { mac: *mac, ty: ty.clone(), elem: boxed(elem) }
+            }
+            Expr::Field { expr, field } => Expr::Field { expr: boxed(expr), field: *field },
+            Expr::Index { expr, index, ty } => {
+                Expr::Index { expr: boxed(expr), index: boxed(index), ty: ty.clone() }
+            }
+            Expr::Add { lhs, rhs, ty } => {
+                Expr::Add { lhs: boxed(lhs), rhs: boxed(rhs), ty: ty.clone() }
+            }
+            Expr::Reference(expr) => Expr::Reference(boxed(expr)),
+            Expr::MutReference(expr) => Expr::MutReference(boxed(expr)),
+            Expr::Cast { expr, ty } => Expr::Cast { expr: boxed(expr), ty: ty.clone() },
+            Expr::Deref { expr, ty } => Expr::Deref { expr: boxed(expr), ty: ty.clone() },
+            Expr::Try { expr, ty } => Expr::Try { expr: boxed(expr), ty: ty.clone() },
+            Expr::Closure { params, body, ty } => {
+                Expr::Closure { params: params.clone(), body: boxed(body), ty: ty.clone() }
+            }
+        }
+    }
+
+    /// Names of the closure parameters used in the tree that are not parameters of closures in it,
+    /// these are the parameters of the enclosing closures
+    pub(super) fn free_closure_params(&self) -> Vec<&'static str> {
+        match self {
+            Expr::ClosureParam { name, .. } => vec![name],
+            Expr::Closure { params, body, .. } => body
+                .free_closure_params()
+                .into_iter()
+                .filter(|it| !params.iter().any(|(name, _)| name == it))
+                .collect(),
+            _ => self.children().into_iter().flat_map(Expr::free_closure_params).unique().collect(),
+        }
+    }
 }
@@ -9,20 +9,24 @@
 
 use std::iter;
 
+use hir_def::{lang_item::LangItem, type_ref::Mutability, AttrDefId};
 use hir_ty::db::HirDatabase;
 use hir_ty::mir::BorrowKind;
 use hir_ty::TyBuilder;
 use itertools::Itertools;
-use rustc_hash::FxHashSet;
 
 use crate::{
-    Adt, AssocItem, Enum, GenericDef, GenericParam, HasVisibility, Impl, ModuleDef, ScopeDef, Type,
-    TypeParam, Variant,
+    Adt, AsAssocItem, AssocItem, Enum, Function, GenericDef, GenericParam, HasVisibility, Impl,
+    Local, Macro, Module, ModuleDef, Name, ScopeDef, StructKind, Trait, Type, TypeAlias, TypeParam,
+    Variant,
 };
 
-use crate::term_search::{Expr, TermSearchConfig};
+use crate::term_search::{ArrayLen, Expr, RejectReason};
 
-use super::{LookupTable, NewTypesKey, TermSearchCtx};
+use super::{
+    def_path, expr, is_place, FxIndexMap, FxIndexSet, ImplsCache, LookupTable, NewTypesKey,
+    TermSearchCtx,
+};
 
 /// # Trivial tactic
 ///
@@ -38,13 +42,14 @@ use super::{LookupTable, NewTypesKey, TermSearchCtx};
 ///
 /// _Note that there is no use of calling this tactic in every iteration as the output does not
 /// depend on the current state of `lookup`_
-pub(super) fn trivial<'a, DB: HirDatabase>(
-    ctx: &'a TermSearchCtx<'a, DB>,
-    defs: &'a FxHashSet<ScopeDef>,
+pub(super) fn trivial<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    defs: &'a FxIndexSet<ScopeDef>,
     lookup: &'a mut LookupTable,
 ) -> impl Iterator<Item = Expr> + 'a {
-    let db = ctx.sema.db;
-    defs.iter().filter_map(|def| {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+    defs.iter().filter_map(move |def| {
         let expr = match def {
             ScopeDef::ModuleDef(ModuleDef::Const(it)) => Some(Expr::Const(*it)),
             ScopeDef::ModuleDef(ModuleDef::Static(it)) => Some(Expr::Static(*it)),
@@ -76,14 +81,80 @@ pub(super) fn trivial<'a, DB: HirDatabase>(
         lookup.mark_exhausted(*def);
 
         let ty = expr.ty(db);
-        lookup.insert(ty.clone(), std::iter::once(expr.clone()));
+        lookup.insert(db, ty.clone(), std::iter::once(expr.clone()));
 
         // Don't suggest local references as they are not valid for return
         if matches!(expr, Expr::Local(_)) && ty.contains_reference(db) {
             return None;
         }
 
-        ty.could_unify_with_deeply(db, &ctx.goal).then_some(expr)
+        unify(db, &ty, &ctx.goal).then_some(expr)
+    })
+}
+
+/// Get the canonical value of the goal if its type has exactly one value, that is `()` for unit
+/// and the unit struct itself for unit structs such as `PhantomData<T>`
+///
+/// `None` if other values of the goal may be reachable, as locals and calls of the goal type are
+/// suggestions too (such as `effect()` for `()` goal).
+pub(super) fn singleton_value(
+    ctx: &TermSearchCtx<'_>,
+    defs: &FxIndexSet<ScopeDef>,
+) -> Option<Expr> {
+    let db = ctx.sema.db();
+    if reaches_goal_otherwise(ctx, defs) {
+        return None;
+    }
+    if ctx.goal.is_unit() {
+        return Some(Expr::FamousType { ty: ctx.goal.clone(), value: "()" });
+    }
+    let Some(Adt::Struct(strukt)) = ctx.goal.as_adt() else { return None };
+    if strukt.kind(db) != StructKind::Unit
+        || strukt.is_unstable(db)
+        || !strukt.is_visible_from(db, ctx.scope.module())
+        || ctx.is_blocked(ModuleDef::Adt(Adt::Struct(strukt)))
+        || ctx.goal.contains_unknown()
+    {
+        return None;
+    }
+    Some(Expr::Struct { strukt, generics: ctx.goal.type_arguments().collect(), params: Vec::new() })
+}
+
+/// Check if other values of the goal than the canonical one may be reachable
+///
+/// That is if there are locals, constants or statics of the goal type in scope, or functions
+/// returning it. Only the functions the tactics start from are checked: free functions in scope,
+/// the visible traits and the associated functions of the goal type and the types of the locals.
+fn reaches_goal_otherwise(ctx: &TermSearchCtx<'_>, defs: &FxIndexSet<ScopeDef>) -> bool {
+    let db = ctx.sema.db();
+    let is_goal = |ty: &Type| ty.could_unify_with_deeply(db, &ctx.goal);
+    let returns_goal = |it: AssocItem| match it {
+        AssocItem::Function(f) => is_goal(&f.ret_type(db)),
+        _ => false,
+    };
+
+    let mut impl_tys = vec![ctx.goal.clone()];
+    for def in defs {
+        let reaches = match def {
+            ScopeDef::Local(it) => {
+                impl_tys.push(it.ty(db));
+                is_goal(&it.ty(db))
+            }
+            ScopeDef::ModuleDef(ModuleDef::Const(it)) => is_goal(&it.ty(db)),
+            ScopeDef::ModuleDef(ModuleDef::Static(it)) => is_goal(&it.ty(db)),
+            ScopeDef::ModuleDef(ModuleDef::Function(it)) => is_goal(&it.ret_type(db)),
+            _ => false,
+        };
+        if reaches {
+            return true;
+        }
+    }
+    let traits = ctx.scope.visible_traits().0;
+    if traits.into_iter().any(|it| Trait::from(it).items(db).into_iter().any(returns_goal)) {
+        return true;
+    }
+    impl_tys.into_iter().any(|ty| {
+        Impl::all_for_type(db, ty).into_iter().flat_map(|it| it.items(db)).any(returns_goal)
     })
 }
 
@@ -99,26 +170,33 @@ pub(super) fn trivial<'a, DB: HirDatabase>(
 /// * `defs` - Set of items in scope at term search target location
 /// * `lookup` - Lookup table for types
 /// * `should_continue` - Function that indicates when to stop iterating
-pub(super) fn type_constructor<'a, DB: HirDatabase>(
-    ctx: &'a TermSearchCtx<'a, DB>,
-    defs: &'a FxHashSet<ScopeDef>,
+pub(super) fn type_constructor<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    defs: &'a FxIndexSet<ScopeDef>,
     lookup: &'a mut LookupTable,
     should_continue: &'a dyn std::ops::Fn() -> bool,
 ) -> impl Iterator<Item = Expr> + 'a {
-    let db = ctx.sema.db;
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
     let module = ctx.scope.module();
     fn variant_helper(
-        db: &dyn HirDatabase,
+        ctx: &TermSearchCtx<'_>,
         lookup: &mut LookupTable,
         should_continue: &dyn std::ops::Fn() -> bool,
         parent_enum: Enum,
         variant: Variant,
-        config: &TermSearchConfig,
     ) -> Vec<(Type, Vec<Expr>)> {
+        let db = ctx.sema.db();
+        let config = &ctx.config;
         // Ignore unstable
         if variant.is_unstable(db) {
             return Vec::new();
         }
+        // Variants of the enums in scope are not in `defs`, so the blocklist is checked here
+        if ctx.is_blocked(ModuleDef::Variant(variant)) {
+            lookup.reject::<()>(RejectReason::Blocked, variant);
+            return Vec::new();
+        }
 
         let generics = GenericDef::from(variant.parent_enum(db));
         let Some(type_params) = generics
@@ -179,18 +257,10 @@ pub(super) fn type_constructor<'a, DB: HirDatabase>(
                     .map(|field| lookup.find(db, &field.ty_with_args(db, generics.iter().cloned())))
                     .collect::<Option<_>>()?;
 
-                // Note that we need special case for 0 param constructors because of multi cartesian
-                // product
-                let variant_exprs: Vec<Expr> = if param_exprs.is_empty() {
-                    vec![Expr::Variant { variant, generics, params: Vec::new() }]
-                } else {
-                    param_exprs
-                        .into_iter()
-                        .multi_cartesian_product()
-                        .map(|params| Expr::Variant { variant, generics: generics.clone(), params })
-                        .collect()
-                };
-                lookup.insert(enum_ty.clone(), variant_exprs.iter().cloned());
+                let variant_exprs: Vec<Expr> = bounded_cartesian_product(param_exprs)
+                    .map(|params| Expr::Variant { variant, generics: generics.clone(), params })
+                    .collect();
+                lookup.insert(db, enum_ty.clone(), variant_exprs.iter().cloned());
 
                 Some((enum_ty, variant_exprs))
             })
@@ -199,14 +269,8 @@ pub(super) fn type_constructor<'a, DB: HirDatabase>(
     defs.iter()
         .filter_map(move |def| match def {
             ScopeDef::ModuleDef(ModuleDef::Variant(it)) => {
-                let variant_exprs = variant_helper(
-                    db,
-                    lookup,
-                    should_continue,
-                    it.parent_enum(db),
-                    *it,
-                    &ctx.config,
-                );
+                let variant_exprs =
+                    variant_helper(ctx, lookup, should_continue, it.parent_enum(db), *it);
                 if variant_exprs.is_empty() {
                     return None;
                 }
@@ -224,9 +288,7 @@ pub(super) fn type_constructor<'a, DB: HirDatabase>(
                 let exprs: Vec<(Type, Vec<Expr>)> = enum_
                     .variants(db)
                     .into_iter()
-                    .flat_map(|it| {
-                        variant_helper(db, lookup, should_continue, *enum_, it, &ctx.config)
-                    })
+                    .flat_map(|it| variant_helper(ctx, lookup, should_continue, *enum_, it))
                     .collect();
 
                 if exprs.is_empty() {
@@ -246,8 +308,11 @@ pub(super) fn type_constructor<'a, DB: HirDatabase>(
             }
             ScopeDef::ModuleDef(ModuleDef::Adt(Adt::Struct(it))) => {
                 // Ignore unstable and not visible
-                if it.is_unstable(db) || !it.is_visible_from(db, module) {
-                    return None;
+                if it.is_unstable(db) {
+                    return lookup.reject(RejectReason::Unstable, Adt::Struct(*it));
+                }
+                if !it.is_visible_from(db, module) {
+                    return lookup.reject(RejectReason::Visibility, Adt::Struct(*it));
                 }
 
                 let generics = GenericDef::from(*it);
@@ -305,35 +370,41 @@ pub(super) fn type_constructor<'a, DB: HirDatabase>(
                             return None;
                         }
 
-                        // Early exit if some param cannot be filled from lookup
-                        let param_exprs: Vec<Vec<Expr>> = fields
-                            .into_iter()
-                            .map(|field| lookup.find(db, &field.ty(db)))
-                            .collect::<Option<_>>()?;
-
-                        // Note that we need special case for 0 param constructors because of multi cartesian
-                        // product
-                        let struct_exprs: Vec<Expr> = if param_exprs.is_empty() {
-                            vec![Expr::Struct { strukt: *it, generics, params: Vec::new() }]
-                        } else {
-                            param_exprs
-                                .into_iter()
-                                .multi_cartesian_product()
-                                .map(|params| Expr::Struct {
-                                    strukt: *it,
-                                    generics: generics.clone(),
-                                    params,
+                        // Early exit if some param cannot be filled from lookup, unless all of
+                        // them can be filled by `Default::default()`
+                        let found: Option<Vec<Vec<Expr>>> =
+                            fields.iter().map(|field| lookup.find(db, &field.ty(db))).collect();
+                        let defaulted = found.is_none();
+                        let param_exprs: Vec<Vec<Expr>> = match found {
+                            Some(it) => it,
+                            None => fields
+                                .iter()
+                                .map(|field| {
+                                    ctx.default_value(lookup, &field.ty(db)).map(|it| vec![it])
                                 })
-                                .collect()
+                                .collect::<Option<_>>()?,
                         };
 
-                        if non_default_type_params_len == 0 {
-                            // Fulfilled only if there are no generic parameters
+                        let struct_exprs: Vec<Expr> = bounded_cartesian_product(param_exprs)
+                            .map(|params| Expr::Struct {
+                                strukt: *it,
+                                generics: generics.clone(),
+                                params,
+                            })
+                            .collect();
+
+                        // Fulfilled only if there are no generic parameters, defaulted fields
+                        // might still be reached later
+                        if non_default_type_params_len == 0 && !defaulted {
                             lookup.mark_fulfilled(ScopeDef::ModuleDef(ModuleDef::Adt(
                                 Adt::Struct(*it),
                             )));
                         }
-                        lookup.insert(struct_ty.clone(), struct_exprs.iter().cloned());
+                        // Defaulted structs are only offered as solutions, not used to build
+                        // other values
+                        if !defaulted {
+                            lookup.insert(db, struct_ty.clone(), struct_exprs.iter().cloned());
+                        }
 
                         Some((struct_ty, struct_exprs))
                     })
@@ -343,14 +414,15 @@ pub(super) fn type_constructor<'a, DB: HirDatabase>(
             _ => None,
         })
         .flatten()
-        .filter_map(|(ty, exprs)| ty.could_unify_with_deeply(db, &ctx.goal).then_some(exprs))
+        .filter_map(move |(ty, exprs)| unify(db, &ty, &ctx.goal).then_some(exprs))
         .flatten()
 }
 
 /// # Free function tactic
 ///
 /// Attempts to call different functions in scope with parameters from lookup table.
-/// Functions that include generics are not used for performance reasons.
+/// Functions that have more than one generic parameter are only used if all the parameters can be
+/// inferred from the return type, trying all the combinations is too slow.
 ///
 /// Updates lookup by new types reached and returns iterator that yields
 /// elements that unify with `goal`.
@@ -360,13 +432,14 @@ pub(super) fn type_constructor<'a, DB: HirDatabase>(
 /// * `defs` - Set of items in scope at term search target location
 /// * `lookup` - Lookup table for types
 /// * `should_continue` - Function that indicates when to stop iterating
-pub(super) fn free_function<'a, DB: HirDatabase>(
-    ctx: &'a TermSearchCtx<'a, DB>,
-    defs: &'a FxHashSet<ScopeDef>,
+pub(super) fn free_function<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    defs: &'a FxIndexSet<ScopeDef>,
     lookup: &'a mut LookupTable,
     should_continue: &'a dyn std::ops::Fn() -> bool,
 ) -> impl Iterator<Item = Expr> + 'a {
-    let db = ctx.sema.db;
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
     let module = ctx.scope.module();
     defs.iter()
         .filter_map(move |def| match def {
@@ -374,67 +447,98 @@ pub(super) fn free_function<'a, DB: HirDatabase>(
                 let generics = GenericDef::from(*it);
 
                 // Ignore const params for now
-                let type_params = generics
+                let Some(type_params) = generics
                     .type_or_const_params(db)
                     .into_iter()
                     .map(|it| it.as_type_param(db))
-                    .collect::<Option<Vec<TypeParam>>>()?;
+                    .collect::<Option<Vec<TypeParam>>>()
+                else {
+                    return lookup.reject(RejectReason::UnsupportedGenerics, *it);
+                };
 
                 // Ignore lifetimes as we do not check them
                 if !generics.lifetime_params(db).is_empty() {
-                    return None;
+                    return lookup.reject(RejectReason::UnsupportedGenerics, *it);
+                }
+
+                // Ignore functions with many params as they explode the amount of combinations
+                if it.num_params(db) > ctx.config.max_fn_args {
+                    return lookup.reject(RejectReason::TooManyArgs, *it);
+                }
+
+                // Functions without arguments are factories, not transformations
+                if ctx.config.transformations_only && it.num_params(db) == 0 {
+                    return lookup.reject(RejectReason::NoArguments, *it);
                 }
 
                 // Only account for stable type parameters for now, unstable params can be default
                 // tho, for example in `Box<T, #[unstable] A: Allocator>`
                 if type_params.iter().any(|it| it.is_unstable(db) && it.default(db).is_none()) {
-                    return None;
+                    return lookup.reject(RejectReason::Unstable, *it);
                 }
 
                 let non_default_type_params_len =
                     type_params.iter().filter(|it| it.default(db).is_none()).count();
-
-                // Ignore bigger number of generics for now as they kill the performance
-                if non_default_type_params_len > 0 {
-                    return None;
-                }
-
-                let generic_params = lookup
-                    .iter_types()
-                    .collect::<Vec<_>>() // Force take ownership
-                    .into_iter()
-                    .permutations(non_default_type_params_len);
+                let explicit_type_params_len =
+                    type_params.iter().filter(|it| is_explicit(db, **it)).count();
+
+                // Trying all the combinations of bigger number of generics kills the performance,
+                // so we infer all of them at once from the types we are looking for instead
+                let generic_params: Vec<Vec<Type>> = match explicit_type_params_len {
+                    0 | 1 => lookup
+                        .iter_types()
+                        .collect::<Vec<_>>() // Force take ownership
+                        .into_iter()
+                        .permutations(explicit_type_params_len)
+                        .collect(),
+                    _ => lookup
+                        .types_wishlist()
+                        .clone()
+                        .into_iter()
+                        .chain(iter::once(ctx.goal.clone()))
+                        .filter_map(|ty| generics_from_ret_type(db, *it, &type_params, &ty))
+                        .unique()
+                        .collect(),
+                };
 
                 let exprs: Vec<_> = generic_params
+                    .into_iter()
+                    .flat_map(|generics| {
+                        with_impl_trait_args(db, &type_params, generics, lookup, should_continue)
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
                     .filter(|_| should_continue())
                     .filter_map(|generics| {
-                        // Insert default type params
-                        let mut g = generics.into_iter();
-                        let generics: Vec<_> = type_params
-                            .iter()
-                            .map(|it| match it.default(db) {
-                                Some(ty) => Some(ty),
-                                None => {
-                                    let generic = g.next().expect("Missing type param");
-                                    // Filter out generics that do not unify due to trait bounds
-                                    it.ty(db).could_unify_with(db, &generic).then_some(generic)
-                                }
-                            })
-                            .collect::<Option<_>>()?;
+                        // Filter out generics that do not satisfy trait bounds and where clauses
+                        if non_default_type_params_len > 0
+                            && !it.predicates_hold_with_args(db, generics.iter().cloned())
+                        {
+                            return lookup.reject(RejectReason::BoundsNotSatisfied, *it);
+                        }
 
-                        let ret_ty = it.ret_type_with_args(db, generics.iter().cloned());
+                        // Projections such as `<T as Add>::Output` are normalized, so that the
+                        // type matches the concrete types in lookup
+                        let ret_ty =
+                            it.ret_type_with_args(db, generics.iter().cloned()).normalize(db);
                         // Filter out private and unsafe functions
-                        if !it.is_visible_from(db, module)
-                            || it.is_unsafe_to_call(db)
-                            || it.is_unstable(db)
-                            || ctx.config.enable_borrowcheck && ret_ty.contains_reference(db)
+                        if !it.is_visible_from(db, module) {
+                            return lookup.reject(RejectReason::Visibility, *it);
+                        }
+                        if !ctx.config.allow_unsafe && it.is_unsafe_to_call(db) {
+                            return lookup.reject(RejectReason::Unsafe, *it);
+                        }
+                        if it.is_unstable(db) {
+                            return lookup.reject(RejectReason::Unstable, *it);
+                        }
+                        if ctx.config.enable_borrowcheck && ret_ty.contains_reference(db)
                             || ret_ty.is_raw_ptr()
                         {
-                            return None;
+                            return lookup.reject(RejectReason::ReturnsReference, *it);
                         }
 
                         // Early exit if some param cannot be filled from lookup
-                        let param_exprs: Vec<Vec<Expr>> = it
+                        let Some(param_exprs) = it
                             .params_without_self_with_args(db, generics.iter().cloned())
                             .into_iter()
                             .map(|field| {
@@ -444,27 +548,21 @@ pub(super) fn free_function<'a, DB: HirDatabase>(
                                     false => lookup.find_autoref(db, ty),
                                 }
                             })
-                            .collect::<Option<_>>()?;
-
-                        // Note that we need special case for 0 param constructors because of multi cartesian
-                        // product
-                        let fn_exprs: Vec<Expr> = if param_exprs.is_empty() {
-                            vec![Expr::Function { func: *it, generics, params: Vec::new() }]
-                        } else {
-                            param_exprs
-                                .into_iter()
-                                .multi_cartesian_product()
-                                .map(|params| Expr::Function {
-                                    func: *it,
-                                    generics: generics.clone(),
-
-                                    params,
-                                })
-                                .collect()
+                            .collect::<Option<Vec<Vec<Expr>>>>()
+                        else {
+                            return lookup.reject(RejectReason::MissingArguments, *it);
                         };
 
+                        let fn_exprs: Vec<Expr> = bounded_cartesian_product(param_exprs)
+                            .map(|params| Expr::Function {
+                                func: *it,
+                                generics: generics.clone(),
+                                params,
+                            })
+                            .collect();
+
                         lookup.mark_fulfilled(ScopeDef::ModuleDef(ModuleDef::Function(*it)));
-                        lookup.insert(ret_ty.clone(), fn_exprs.iter().cloned());
+                        lookup.insert(db, ret_ty.clone(), fn_exprs.iter().cloned());
                         Some((ret_ty, fn_exprs))
                     })
                     .collect();
@@ -473,16 +571,292 @@ pub(super) fn free_function<'a, DB: HirDatabase>(
             _ => None,
         })
         .flatten()
-        .filter_map(|(ty, exprs)| ty.could_unify_with_deeply(db, &ctx.goal).then_some(exprs))
+        .filter_map(move |(ty, exprs)| unify(db, &ty, &ctx.goal).then_some(exprs))
+        .flatten()
+}
+
+/// Infer non default generic parameters of the function by matching its return type against `ty`
+///
+/// All the parameters get solved at once, so that we never pick a parameter in a way that makes
+/// the rest of them unsatisfiable.
+/// Only parameters that appear in the return type as itself (`-> T`) or among the type arguments
+/// of ADTs (`-> Foo<T>`) are supported.
+fn generics_from_ret_type(
+    db: &dyn HirDatabase,
+    func: Function,
+    type_params: &[TypeParam],
+    ty: &Type,
+) -> Option<Vec<Type>> {
+    let mut solved: Vec<(TypeParam, Type)> = Vec::new();
+    if !solve_type_params(db, &func.ret_type(db), ty, &mut solved) {
+        return None;
+    }
+    type_params
+        .iter()
+        .filter(|it| is_explicit(db, **it))
+        .map(|param| solved.iter().find(|(it, _)| it == param).map(|(_, ty)| ty.clone()))
+        .collect()
+}
+
+/// Check if function in impl block can be called from `module`
+///
+/// Items of trait impls have no visibility of their own, they are visible wherever the trait is.
+fn is_callable_from(db: &dyn HirDatabase, func: Function, module: Module) -> bool {
+    match func.as_assoc_item(db).and_then(|it| it.implemented_trait(db)) {
+        Some(trait_) => trait_.is_visible_from(db, module),
+        None => func.is_visible_from(db, module),
+    }
+}
+
+/// Reason the associated function cannot be used from `module` regardless of its generic
+/// arguments, `None` if it can be
+fn assoc_fn_reject_reason(
+    ctx: &TermSearchCtx<'_>,
+    func: Function,
+    module: Module,
+) -> Option<RejectReason> {
+    let db = ctx.sema.db();
+    let reason = if !is_callable_from(db, func, module) {
+        RejectReason::Visibility
+    } else if !ctx.config.allow_unsafe && func.is_unsafe_to_call(db) {
+        RejectReason::Unsafe
+    } else if func.is_unstable(db) {
+        RejectReason::Unstable
+    } else if ctx.is_blocked(ModuleDef::Function(func)) {
+        RejectReason::Blocked
+    } else if ctx.is_test_only(ModuleDef::Function(func)) {
+        RejectReason::TestOnly
+    } else if ctx.is_panicking(func) {
+        RejectReason::Panicking
+    } else {
+        return None;
+    };
+    Some(reason)
+}
+
+/// Functions callable on `ty` through `imp`
+///
+/// For trait impls this includes the functions provided by the trait and not overridden in the
+/// impl, as long as `ty` implements the trait.
+/// Returns the functions along with the generic arguments of their parent, the arguments of `ty`
+/// for the impl functions and `ty` itself as the `Self` of the trait provided functions.
+fn impl_fns(
+    db: &dyn HirDatabase,
+    impls: &ImplsCache,
+    ty: &Type,
+    imp: Impl,
+) -> Vec<(Function, Vec<Type>)> {
+    let items = imp.items(db);
+    let mut fns: Vec<_> = items
+        .iter()
+        .filter_map(|it| match it {
+            AssocItem::Function(f) => Some((*f, ty.type_arguments().collect())),
+            _ => None,
+        })
+        .collect();
+    let Some(trait_) = imp.trait_(db) else { return fns };
+    // Generic arguments of the trait are not known from `ty` alone
+    if trait_.type_or_const_param_count(db, false) > 0 || !impls.impls_trait(db, ty, trait_, &[]) {
+        return fns;
+    }
+    let overridden: Vec<Name> = items.iter().filter_map(|it| it.name(db)).collect();
+    fns.extend(trait_.items(db).into_iter().filter_map(|it| match it {
+        AssocItem::Function(f) if !overridden.contains(&f.name(db)) => Some((f, vec![ty.clone()])),
+        _ => None,
+    }));
+    fns
+}
+
+/// Get the functions of the trait bounds of the type parameter with `Self` type as the generics
+///
+/// Type parameters have no impls, so the functions of their bounds are the only ones that can be
+/// called on them, for example `T::default()` for `T: Default`. Bounds with generic arguments are
+/// skipped as the arguments are not known from the bound alone.
+fn bound_fns(db: &dyn HirDatabase, ty: &Type) -> Vec<(Function, Vec<Type>)> {
+    let Some(param) = ty.as_type_param(db) else { return Vec::new() };
+    param
+        .trait_bounds(db)
+        .into_iter()
+        .filter(|it| it.type_or_const_param_count(db, false) == 0)
+        .flat_map(|it| it.items(db))
+        .filter_map(|it| match it {
+            AssocItem::Function(f) => Some((f, vec![ty.clone()])),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Get the functions of `IntoIterator` for arrays with the array as the `Self` type
+///
+/// The impl for arrays is generic over the length (`impl<T, const N: usize> IntoIterator for
+/// [T; N]`) and is not found by [`Impl::all_for_type`], so the functions are called through the
+/// trait instead (`IntoIterator::into_iter(arr)`).
+fn array_fns(
+    db: &dyn HirDatabase,
+    impls: &ImplsCache,
+    into_iterator: Option<Trait>,
+    ty: &Type,
+) -> Vec<(Function, Vec<Type>)> {
+    let Some(into_iterator) = into_iterator else { return Vec::new() };
+    if ty.as_array(db).is_none() || !impls.impls_trait(db, ty, into_iterator, &[]) {
+        return Vec::new();
+    }
+    into_iterator
+        .items(db)
+        .into_iter()
+        .filter_map(|it| match it {
+            AssocItem::Function(f) => Some((f, vec![ty.clone()])),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Get the methods of the trait object behind shared reference or `Box` with the trait object as
+/// the `Self` type
+///
+/// Methods are dispatched dynamically on the dereferenced pointer (`Shape::area(&*s)`), so only the
+/// object safe methods taking `&self` without type parameters of their own are returned. Methods
+/// gated behind `where Self: Sized` are not callable on trait objects and are skipped as well.
+fn dyn_fns(db: &dyn HirDatabase, ty: &Type) -> Vec<(Function, Vec<Type>)> {
+    let Some(dyn_ty) = dyn_pointee(db, ty) else { return Vec::new() };
+    dyn_ty
+        .as_dyn_traits()
+        .into_iter()
         .flatten()
+        .filter(|it| it.type_or_const_param_count(db, false) == 0)
+        .flat_map(|it| it.items(db))
+        .filter_map(|it| match it {
+            AssocItem::Function(f)
+                if f.self_param(db).map_or(false, |it| it.access(db) == crate::Access::Shared)
+                    && GenericDef::from(f).type_or_const_params(db).is_empty()
+                    && !f.requires_sized_self(db) =>
+            {
+                Some((f, vec![dyn_ty.clone()]))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Get the trait object behind shared reference (`&dyn Trait`) or `Box` (`Box<dyn Trait>`)
+fn dyn_pointee(db: &dyn HirDatabase, ty: &Type) -> Option<Type> {
+    let pointee = match ty.remove_ref() {
+        Some(it) if !ty.is_mutable_reference() => it,
+        Some(_) => return None,
+        None if is_lang_adt(db, ty, LangItem::OwnedBox) => ty.type_arguments().next()?,
+        None => return None,
+    };
+    pointee.as_dyn_traits().is_some().then_some(pointee)
+}
+
+/// Check if type parameter has to be given explicitly, that is it is declared in the generic
+/// parameter list and has no default
+fn is_explicit(db: &dyn HirDatabase, param: TypeParam) -> bool {
+    param.default(db).is_none() && !param.is_implicit(db)
+}
+
+/// Build all the generics of the function from the types of its explicit type parameters
+///
+/// Defaults are inserted for the parameters that have them. Parameters of `impl Trait` in
+/// argument position can stand for any type implementing the trait, so they are tried with the
+/// types in `lookup` that satisfy their bounds, at most `MAX_ARG_COMBINATIONS` combinations of
+/// them.
+fn with_impl_trait_args(
+    db: &dyn HirDatabase,
+    type_params: &[TypeParam],
+    explicit: Vec<Type>,
+    lookup: &LookupTable,
+    should_continue: &dyn std::ops::Fn() -> bool,
+) -> Vec<Vec<Type>> {
+    let impl_trait_args: Vec<Vec<Type>> = type_params
+        .iter()
+        .filter(|it| it.default(db).is_none() && it.is_implicit(db))
+        .map(|param| {
+            lookup
+                .iter_types()
+                .filter(|ty| satisfies_bounds(db, &lookup.impls, *param, ty))
+                .collect()
+        })
+        .collect();
+
+    bounded_cartesian_product(impl_trait_args)
+        .take_while(|_| should_continue())
+        .filter_map(|impl_trait_args| {
+            let mut explicit = explicit.iter().cloned();
+            let mut impl_trait_args = impl_trait_args.into_iter();
+            type_params
+                .iter()
+                .map(|it| match it.default(db) {
+                    Some(ty) => Some(ty),
+                    None if it.is_implicit(db) => impl_trait_args.next(),
+                    None => explicit.next(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Quick check of the bounds of type parameter that filters out most of the types which cannot be
+/// used for it
+///
+/// Only bounds on traits without generic parameters are checked, the where clauses should still
+/// be checked with `Function::predicates_hold_with_args`.
+fn satisfies_bounds(db: &dyn HirDatabase, impls: &ImplsCache, param: TypeParam, ty: &Type) -> bool {
+    param.trait_bounds(db).into_iter().all(|trait_| {
+        // Generics of traits include `Self`
+        GenericDef::from(trait_).type_or_const_params(db).len() > 1
+            || impls.impls_trait(db, ty, trait_, &[])
+    })
+}
+
+/// Match `pattern` against `ty` and record the types the parameters in `pattern` stand for
+///
+/// Returns `false` if the types cannot match.
+fn solve_type_params(
+    db: &dyn HirDatabase,
+    pattern: &Type,
+    ty: &Type,
+    solved: &mut Vec<(TypeParam, Type)>,
+) -> bool {
+    if let Some(param) = pattern.as_type_param(db) {
+        return match solved.iter().find(|(it, _)| *it == param) {
+            // Same parameter has to be the same type everywhere
+            Some((_, it)) => it == ty,
+            None => {
+                solved.push((param, ty.clone()));
+                true
+            }
+        };
+    }
+    match (pattern.as_adt(), ty.as_adt()) {
+        (Some(a), Some(b)) if a == b => pattern
+            .type_arguments()
+            .zip(ty.type_arguments())
+            .all(|(pattern, ty)| solve_type_params(db, &pattern, &ty, solved)),
+        (Some(_), Some(_)) => false,
+        // Parameters inside of other types are not solved
+        _ => true,
+    }
 }
 
 /// # Impl method tactic
 ///
 /// Attempts to call methods on types from lookup table.
-/// This includes both functions from direct impl blocks as well as functions from traits.
-/// Methods defined in impl blocks that are generic and methods that are themselves have
-/// generics are ignored for performance reasons.
+/// This includes both functions from direct impl blocks as well as functions from traits, the
+/// methods provided by traits (such as `Iterator::collect`) included. Methods of type parameters
+/// come from their trait bounds, and the object safe methods of trait objects behind references
+/// or boxes are called through dynamic dispatch.
+/// Generic parameters of the methods themselves (such as `T` in `fn parse<T>(&self) -> T`) are
+/// inferred from the types we are looking for by matching them against the return type.
+///
+/// Return types are normalized the same way as the goal, so that the methods returning associated
+/// types (such as `Self::IntoIter`) reach the goals written as projections
+/// (`<Bag as IntoIterator>::IntoIter`), and the projections of type parameters (`C::IntoIter`)
+/// are left as they are on both sides.
+///
+/// Methods returning references are only called when borrow checking is disabled, except for the
+/// accessors of `Option` and `Result` borrowing their payload (such as `Option::as_deref`) that
+/// are called on places.
 ///
 /// Updates lookup by new types reached and returns iterator that yields
 /// elements that unify with `goal`.
@@ -492,46 +866,68 @@ pub(super) fn free_function<'a, DB: HirDatabase>(
 /// * `defs` - Set of items in scope at term search target location
 /// * `lookup` - Lookup table for types
 /// * `should_continue` - Function that indicates when to stop iterating
-pub(super) fn impl_method<'a, DB: HirDatabase>(
-    ctx: &'a TermSearchCtx<'a, DB>,
-    _defs: &'a FxHashSet<ScopeDef>,
+pub(super) fn impl_method<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
     lookup: &'a mut LookupTable,
     should_continue: &'a dyn std::ops::Fn() -> bool,
 ) -> impl Iterator<Item = Expr> + 'a {
-    let db = ctx.sema.db;
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
     let module = ctx.scope.module();
+    let impls = lookup.impls();
+    let into_iterator = ctx.lang_trait("collect", "IntoIterator");
     lookup
         .new_types(NewTypesKey::ImplMethod)
         .into_iter()
-        .flat_map(|ty| {
-            Impl::all_for_type(db, ty.clone()).into_iter().map(move |imp| (ty.clone(), imp))
-        })
-        .flat_map(|(ty, imp)| imp.items(db).into_iter().map(move |item| (imp, ty.clone(), item)))
-        .filter_map(|(imp, ty, it)| match it {
-            AssocItem::Function(f) => Some((imp, ty, f)),
-            _ => None,
+        .flat_map(move |ty| {
+            let mut fns: Vec<(Option<Impl>, Function, Vec<Type>)> =
+                Impl::all_for_type(db, ty.clone())
+                    .into_iter()
+                    .flat_map(|imp| {
+                        impl_fns(db, &impls, &ty, imp)
+                            .into_iter()
+                            .map(move |(f, self_generics)| (Some(imp), f, self_generics))
+                    })
+                    .collect();
+            fns.extend(bound_fns(db, &ty).into_iter().map(|(f, generics)| (None, f, generics)));
+            fns.extend(dyn_fns(db, &ty).into_iter().map(|(f, generics)| (None, f, generics)));
+            fns.extend(
+                array_fns(db, &impls, into_iterator, &ty)
+                    .into_iter()
+                    .map(|(f, generics)| (None, f, generics)),
+            );
+            fns.into_iter().map(move |(imp, f, self_generics)| (imp, ty.clone(), f, self_generics))
         })
-        .filter_map(move |(imp, ty, it)| {
+        .filter_map(move |(imp, ty, it, self_generics)| {
             let fn_generics = GenericDef::from(it);
-            let imp_generics = GenericDef::from(imp);
+            let imp_generics = imp.map(GenericDef::from);
+            // Trait object the method is dynamically dispatched on
+            let dyn_self = imp.is_none().then(|| dyn_pointee(db, &ty)).flatten();
 
             // Ignore const params for now
-            let imp_type_params = imp_generics
-                .type_or_const_params(db)
+            let Some(imp_type_params) = imp_generics
+                .map_or_else(Vec::new, |it| it.type_or_const_params(db))
                 .into_iter()
                 .map(|it| it.as_type_param(db))
-                .collect::<Option<Vec<TypeParam>>>()?;
+                .collect::<Option<Vec<TypeParam>>>()
+            else {
+                return lookup.reject(RejectReason::UnsupportedGenerics, it);
+            };
 
             // Ignore const params for now
-            let fn_type_params = fn_generics
+            let Some(fn_type_params) = fn_generics
                 .type_or_const_params(db)
                 .into_iter()
                 .map(|it| it.as_type_param(db))
-                .collect::<Option<Vec<TypeParam>>>()?;
+                .collect::<Option<Vec<TypeParam>>>()
+            else {
+                return lookup.reject(RejectReason::UnsupportedGenerics, it);
+            };
 
             // Ignore all functions that have something to do with lifetimes as we don't check them
             if !fn_generics.lifetime_params(db).is_empty() {
-                return None;
+                return lookup.reject(RejectReason::UnsupportedGenerics, it);
             }
 
             // Ignore functions without self param
@@ -539,9 +935,14 @@ pub(super) fn impl_method<'a, DB: HirDatabase>(
                 return None;
             }
 
-            // Filter out private and unsafe functions
-            if !it.is_visible_from(db, module) || it.is_unsafe_to_call(db) || it.is_unstable(db) {
-                return None;
+            // Ignore functions with many params as they explode the amount of combinations
+            if it.num_params(db) - 1 > ctx.config.max_fn_args {
+                return lookup.reject(RejectReason::TooManyArgs, it);
+            }
+
+            // Filter out private, unsafe, blocked and panicking functions
+            if let Some(reason) = assoc_fn_reject_reason(ctx, it, module) {
+                return lookup.reject(reason, it);
             }
 
             // Only account for stable type parameters for now, unstable params can be default
@@ -549,7 +950,7 @@ pub(super) fn impl_method<'a, DB: HirDatabase>(
             if imp_type_params.iter().any(|it| it.is_unstable(db) && it.default(db).is_none())
                 || fn_type_params.iter().any(|it| it.is_unstable(db) && it.default(db).is_none())
             {
-                return None;
+                return lookup.reject(RejectReason::Unstable, it);
             }
 
             // Double check that we have fully known type
@@ -559,46 +960,59 @@ pub(super) fn impl_method<'a, DB: HirDatabase>(
 
             let non_default_fn_type_params_len =
                 fn_type_params.iter().filter(|it| it.default(db).is_none()).count();
-
-            // Ignore functions with generics for now as they kill the performance
-            // Also checking bounds for generics is problematic
-            if non_default_fn_type_params_len > 0 {
-                return None;
-            }
-
-            let generic_params = lookup
-                .iter_types()
-                .collect::<Vec<_>>() // Force take ownership
-                .into_iter()
-                .permutations(non_default_fn_type_params_len);
+            let explicit_fn_type_params_len =
+                fn_type_params.iter().filter(|it| is_explicit(db, **it)).count();
+            let is_payload_accessor = is_option_or_result(db, &ty)
+                && it.self_param(db).map_or(false, |it| it.access(db) == crate::Access::Shared);
+
+            // Trying all the types in lookup for generics kills the performance, so we infer them
+            // from the types we are looking for instead
+            let generic_params: Vec<Vec<Type>> = match explicit_fn_type_params_len {
+                0 => vec![Vec::new()],
+                _ => lookup
+                    .types_wishlist()
+                    .iter()
+                    .chain(iter::once(&ctx.goal))
+                    .filter_map(|ty| generics_from_ret_type(db, it, &fn_type_params, ty))
+                    .unique()
+                    .collect(),
+            };
 
             let exprs: Vec<_> = generic_params
+                .into_iter()
+                .flat_map(|generics| {
+                    with_impl_trait_args(db, &fn_type_params, generics, lookup, should_continue)
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
                 .filter(|_| should_continue())
                 .filter_map(|generics| {
-                    // Insert default type params
-                    let mut g = generics.into_iter();
-                    let generics: Vec<_> = ty
-                        .type_arguments()
-                        .map(Some)
-                        .chain(fn_type_params.iter().map(|it| match it.default(db) {
-                            Some(ty) => Some(ty),
-                            None => {
-                                let generic = g.next().expect("Missing type param");
-                                // Filter out generics that do not unify due to trait bounds
-                                it.ty(db).could_unify_with(db, &generic).then_some(generic)
-                            }
-                        }))
-                        .collect::<Option<_>>()?;
+                    let ret_ty = it
+                        .ret_type_with_args(
+                            db,
+                            self_generics.iter().cloned().chain(generics.iter().cloned()),
+                        )
+                        .normalize(db);
+                    let borrows_payload = ret_ty.contains_reference(db) && is_payload_accessor;
+
+                    // Filter out generics that do not satisfy trait bounds and where clauses
+                    if (non_default_fn_type_params_len > 0 || borrows_payload)
+                        && !it.predicates_hold_with_args(
+                            db,
+                            self_generics.iter().cloned().chain(generics.iter().cloned()),
+                        )
+                    {
+                        return lookup.reject(RejectReason::BoundsNotSatisfied, it);
+                    }
 
-                    let ret_ty = it.ret_type_with_args(
-                        db,
-                        ty.type_arguments().chain(generics.iter().cloned()),
-                    );
                     // Filter out functions that return references
-                    if ctx.config.enable_borrowcheck && ret_ty.contains_reference(db)
+                    if ctx.config.enable_borrowcheck
+                        && ret_ty.contains_reference(db)
+                        && !borrows_payload
                         || ret_ty.is_raw_ptr()
+                        || borrows_payload && ret_ty.contains_unknown()
                     {
-                        return None;
+                        return lookup.reject(RejectReason::ReturnsReference, it);
                     }
 
                     // Ignore functions that do not change the type
@@ -606,57 +1020,91 @@ pub(super) fn impl_method<'a, DB: HirDatabase>(
                         return None;
                     }
 
-                    let self_ty = it
-                        .self_param(db)
-                        .expect("No self param")
-                        .ty_with_args(db, ty.type_arguments().chain(generics.iter().cloned()));
+                    let self_ty = it.self_param(db).expect("No self param").ty_with_args(
+                        db,
+                        self_generics.iter().cloned().chain(generics.iter().cloned()),
+                    );
 
                     // Ignore functions that have different self type
-                    if !self_ty.autoderef(db).any(|s_ty| ty == s_ty) {
+                    let receiver_ty = dyn_self.as_ref().unwrap_or(&ty);
+                    if !self_ty
+                        .autoderef(db)
+                        .any(|s_ty| s_ty.could_unify_with_deeply(db, receiver_ty))
+                    {
                         return None;
                     }
 
-                    let target_type_exprs = lookup.find(db, &ty).expect("Type not in lookup");
+                    // Methods returning `Self` cannot be called on trait objects
+                    if dyn_self.is_some() && !ret_ty.is_sized(db) {
+                        return None;
+                    }
+
+                    // Custom unification predicates may not match the type with itself
+                    let mut target_type_exprs = lookup.find(db, &ty)?;
+                    // Methods of trait objects are called on the dereferenced pointer
+                    if let Some(dyn_ty) = &dyn_self {
+                        target_type_exprs = target_type_exprs
+                            .into_iter()
+                            .map(|expr| Expr::Deref { expr: Box::new(expr), ty: dyn_ty.clone() })
+                            .collect();
+                    }
+                    // Payload can only be borrowed from places, not from temporaries
+                    if borrows_payload {
+                        target_type_exprs.retain(is_place);
+                        if target_type_exprs.is_empty() {
+                            return None;
+                        }
+                    }
 
                     // Early exit if some param cannot be filled from lookup
-                    let param_exprs: Vec<Vec<Expr>> = it
+                    let Some(param_exprs) = it
                         .params_without_self_with_args(
                             db,
-                            ty.type_arguments().chain(generics.iter().cloned()),
+                            self_generics.iter().cloned().chain(generics.iter().cloned()),
                         )
                         .into_iter()
                         .map(|field| lookup.find_autoref(db, field.ty()))
-                        .collect::<Option<_>>()?;
+                        .collect::<Option<Vec<Vec<Expr>>>>()
+                    else {
+                        return lookup.reject(RejectReason::MissingArguments, it);
+                    };
 
-                    let fn_exprs: Vec<Expr> = std::iter::once(target_type_exprs)
-                        .chain(param_exprs)
-                        .multi_cartesian_product()
-                        .map(|params| {
-                            let mut params = params.into_iter();
-                            let target = Box::new(params.next().unwrap());
-                            Expr::Method {
-                                func: it,
-                                generics: generics.clone(),
-                                target,
-                                params: params.collect(),
-                            }
-                        })
-                        .collect();
+                    let fn_exprs: Vec<Expr> = bounded_cartesian_product(
+                        std::iter::once(target_type_exprs).chain(param_exprs).collect(),
+                    )
+                    .map(|params| {
+                        let mut params = params.into_iter();
+                        let target = Box::new(params.next().unwrap());
+                        Expr::Method {
+                            func: it,
+                            generics: generics.clone(),
+                            target,
+                            params: params.collect(),
+                        }
+                    })
+                    .collect();
 
-                    lookup.insert(ret_ty.clone(), fn_exprs.iter().cloned());
+                    lookup.insert(db, ret_ty.clone(), fn_exprs.iter().cloned());
                     Some((ret_ty, fn_exprs))
                 })
                 .collect();
             Some(exprs)
         })
         .flatten()
-        .filter_map(|(ty, exprs)| ty.could_unify_with_deeply(db, &ctx.goal).then_some(exprs))
+        .filter_map(move |(ty, exprs)| unify(db, &ty, &ctx.goal).then_some(exprs))
         .flatten()
 }
 
-/// # Struct projection tactic
+/// # Builder chain tactic
 ///
-/// Attempts different struct fields (`foo.bar.baz`)
+/// Attempts chaining the methods of fluent builders (`b.with_x(x).with_y(y)`), that is the
+/// inherent methods of structs taking `self` by value and returning `Self`.
+///
+/// Such methods do not change the type, so storing every chain length in lookup would only
+/// re-insert the same type each round with ever deeper chains. Instead the trees of the builder
+/// are replaced with the deepest chain calling distinct methods, up to
+/// `TermSearchConfig::max_builder_chain` of them, so that the methods consuming the builder (such
+/// as `.build()`) are called on the configured value.
 ///
 /// Updates lookup by new types reached and returns iterator that yields
 /// elements that unify with `goal`.
@@ -666,71 +1114,168 @@ pub(super) fn impl_method<'a, DB: HirDatabase>(
 /// * `defs` - Set of items in scope at term search target location
 /// * `lookup` - Lookup table for types
 /// * `should_continue` - Function that indicates when to stop iterating
-pub(super) fn struct_projection<'a, DB: HirDatabase>(
-    ctx: &'a TermSearchCtx<'a, DB>,
-    _defs: &'a FxHashSet<ScopeDef>,
+pub(super) fn builder_chain<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
     lookup: &'a mut LookupTable,
     should_continue: &'a dyn std::ops::Fn() -> bool,
 ) -> impl Iterator<Item = Expr> + 'a {
-    let db = ctx.sema.db;
+    let db = ctx.sema.db();
     let module = ctx.scope.module();
+    let max_chain = ctx.config.max_builder_chain;
     lookup
-        .new_types(NewTypesKey::StructProjection)
+        .new_types(NewTypesKey::BuilderChain)
         .into_iter()
-        .map(|ty| (ty.clone(), lookup.find(db, &ty).expect("Expr not in lookup")))
+        .filter(move |ty| max_chain > 0 && matches!(ty.as_adt(), Some(Adt::Struct(_))))
         .filter(|_| should_continue())
-        .flat_map(move |(ty, targets)| {
-            ty.fields(db).into_iter().filter_map(move |(field, filed_ty)| {
-                if !field.is_visible_from(db, module) {
-                    return None;
-                }
-                let exprs = targets
-                    .clone()
-                    .into_iter()
-                    .map(move |target| Expr::Field { field, expr: Box::new(target) });
-                Some((filed_ty, exprs))
-            })
+        .filter_map(move |ty| {
+            let methods: Vec<Function> = Impl::all_for_type(db, ty.clone())
+                .into_iter()
+                .filter(|imp| imp.trait_(db).is_none())
+                .flat_map(|imp| imp.items(db))
+                .filter_map(|it| match it {
+                    AssocItem::Function(f) => Some(f),
+                    _ => None,
+                })
+                .filter(|f| {
+                    f.self_param(db).map_or(false, |it| it.access(db) == crate::Access::Owned)
+                        && GenericDef::from(*f).params(db).is_empty()
+                        && f.num_params(db) - 1 <= ctx.config.max_fn_args
+                        && assoc_fn_reject_reason(ctx, *f, module).is_none()
+                        && ty.could_unify_with_deeply(
+                            db,
+                            &f.ret_type_with_args(db, ty.type_arguments()).normalize(db),
+                        )
+                })
+                .collect();
+            if methods.is_empty() {
+                return None;
+            }
+
+            // Fill the arguments of each method with the first tree reached for them, trying all
+            // of them would explode the amount of chains again
+            let steps: Vec<(Function, Vec<Expr>)> = methods
+                .into_iter()
+                .filter_map(|f| {
+                    let params = f
+                        .params_without_self_with_args(db, ty.type_arguments())
+                        .into_iter()
+                        .map(|param| {
+                            let expr = lookup.find_autoref(db, param.ty())?.into_iter().next()?;
+                            (!expr.is_many()).then_some(expr)
+                        })
+                        .collect::<Option<Vec<Expr>>>()?;
+                    Some((f, params))
+                })
+                .take(max_chain)
+                .collect();
+            if steps.is_empty() {
+                return None;
+            }
+
+            // Struct literals already set all the fields, so there is nothing to configure on them
+            let (targets, literals): (Vec<Expr>, Vec<Expr>) = lookup
+                .find(db, &ty)?
+                .into_iter()
+                .filter(|it| !it.is_many())
+                .partition(|it| !matches!(it, Expr::Struct { .. }));
+            if targets.is_empty() {
+                return None;
+            }
+            let chains: Vec<Expr> = targets
+                .into_iter()
+                .map(|target| {
+                    steps.iter().fold(target, |target, (func, params)| Expr::Method {
+                        func: *func,
+                        generics: Vec::new(),
+                        target: Box::new(target),
+                        params: params.clone(),
+                    })
+                })
+                .collect();
+            lookup.replace(&ty, chains.iter().cloned().chain(literals).collect());
+            lookup.could_unify(db, &ty, &ctx.goal).then_some(chains)
         })
-        .filter_map(|(ty, exprs)| ty.could_unify_with_deeply(db, &ctx.goal).then_some(exprs))
         .flatten()
 }
 
-/// # Famous types tactic
+/// # Struct projection tactic
 ///
-/// Attempts different values of well known types such as `true` or `false`.
+/// Attempts different struct fields (`foo.bar.baz`)
+///
+/// Fields of enum variants are never projected, not even of single-variant enums used as typed
+/// wrappers, as they can only be reached by destructuring the enum in a `match`. Their payload is
+/// reached through the `Deref` impls or accessor methods of the enum instead.
 ///
 /// Updates lookup by new types reached and returns iterator that yields
 /// elements that unify with `goal`.
 ///
-/// _Note that there is no point of calling it iteratively as the output is always the same_
-///
 /// # Arguments
 /// * `ctx` - Context for the term search
 /// * `defs` - Set of items in scope at term search target location
 /// * `lookup` - Lookup table for types
-pub(super) fn famous_types<'a, DB: HirDatabase>(
-    ctx: &'a TermSearchCtx<'a, DB>,
-    _defs: &'a FxHashSet<ScopeDef>,
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn struct_projection<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
     lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
 ) -> impl Iterator<Item = Expr> + 'a {
-    let db = ctx.sema.db;
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
     let module = ctx.scope.module();
-    [
-        Expr::FamousType { ty: Type::new(db, module.id, TyBuilder::bool()), value: "true" },
-        Expr::FamousType { ty: Type::new(db, module.id, TyBuilder::bool()), value: "false" },
-        Expr::FamousType { ty: Type::new(db, module.id, TyBuilder::unit()), value: "()" },
-    ]
-    .into_iter()
-    .map(|exprs| {
-        lookup.insert(exprs.ty(db), std::iter::once(exprs.clone()));
-        exprs
-    })
-    .filter(|expr| expr.ty(db).could_unify_with_deeply(db, &ctx.goal))
+    lookup
+        .new_types(NewTypesKey::StructProjection)
+        .into_iter()
+        .filter_map(|ty| Some((ty.clone(), lookup.find(db, &ty)?)))
+        .filter(|_| should_continue())
+        .flat_map(move |(ty, targets)| {
+            // Fields of borrowed structs can only be borrowed as well (`&foo.bar`)
+            let (strukt_ty, mutability) = match ty.remove_ref() {
+                Some(inner) if ty.is_mutable_reference() => (inner, Some(Mutability::Mut)),
+                Some(inner) => (inner, Some(Mutability::Shared)),
+                None => (ty, None),
+            };
+            strukt_ty.fields(db).into_iter().filter_map(move |(field, filed_ty)| {
+                if !field.is_visible_from(db, module) {
+                    return None;
+                }
+                // Unsized fields cannot be moved out, only borrowed
+                if mutability.is_none() && !filed_ty.is_sized(db) {
+                    return None;
+                }
+                let exprs = targets.clone().into_iter().map(move |target| {
+                    let expr = Expr::Field { field, expr: Box::new(target) };
+                    match mutability {
+                        Some(Mutability::Shared) => Expr::Reference(Box::new(expr)),
+                        Some(Mutability::Mut) => Expr::MutReference(Box::new(expr)),
+                        None => expr,
+                    }
+                });
+                let filed_ty = match mutability {
+                    Some(m) => Type::reference(&filed_ty, m),
+                    None => filed_ty,
+                };
+                Some((filed_ty, exprs))
+            })
+        })
+        .filter_map(move |(ty, exprs)| unify(db, &ty, &ctx.goal).then_some(exprs))
+        .flatten()
 }
 
-/// # Impl static method (without self type) tactic
+/// # Deref tactic
 ///
-/// Attempts different functions from impl blocks that take no self parameter.
+/// Attempts dereferencing references and types implementing `Deref` to their target (`*foo`)
+///
+/// Only targets that are `Copy` are considered as we cannot move out of the dereferenced value,
+/// except for owned boxes that the value can be moved out of (`*boxed`). That makes it possible to
+/// reach the inner value of newtypes with private field, and the values behind references without
+/// cloning them. Values of borrowed boxes (`&Box<T>`) are cloned instead if they are `Clone`
+/// (`Clone::clone(&**boxed)`).
+///
+/// Targets of other types implementing `Deref` that cannot be copied out are borrowed with an
+/// explicit `Deref::deref(&wrapper)`, which gives a reference to the target also in the positions
+/// where deref coercion does not apply. Only places are borrowed so that no temporaries are.
 ///
 /// Updates lookup by new types reached and returns iterator that yields
 /// elements that unify with `goal`.
@@ -740,169 +1285,310 @@ pub(super) fn famous_types<'a, DB: HirDatabase>(
 /// * `defs` - Set of items in scope at term search target location
 /// * `lookup` - Lookup table for types
 /// * `should_continue` - Function that indicates when to stop iterating
-pub(super) fn impl_static_method<'a, DB: HirDatabase>(
-    ctx: &'a TermSearchCtx<'a, DB>,
-    _defs: &'a FxHashSet<ScopeDef>,
+pub(super) fn deref<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
     lookup: &'a mut LookupTable,
     should_continue: &'a dyn std::ops::Fn() -> bool,
 ) -> impl Iterator<Item = Expr> + 'a {
-    let db = ctx.sema.db;
-    let module = ctx.scope.module();
-    lookup
-        .types_wishlist()
-        .clone()
-        .into_iter()
-        .chain(iter::once(ctx.goal.clone()))
-        .filter(|_| should_continue())
-        .flat_map(|ty| {
-            Impl::all_for_type(db, ty.clone()).into_iter().map(move |imp| (ty.clone(), imp))
-        })
-        .filter(|(_, imp)| !imp.is_unsafe(db))
-        .flat_map(|(ty, imp)| imp.items(db).into_iter().map(move |item| (imp, ty.clone(), item)))
-        .filter_map(|(imp, ty, it)| match it {
-            AssocItem::Function(f) => Some((imp, ty, f)),
-            _ => None,
-        })
-        .filter_map(move |(imp, ty, it)| {
-            let fn_generics = GenericDef::from(it);
-            let imp_generics = GenericDef::from(imp);
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
 
-            // Ignore const params for now
-            let imp_type_params = imp_generics
-                .type_or_const_params(db)
-                .into_iter()
-                .map(|it| it.as_type_param(db))
-                .collect::<Option<Vec<TypeParam>>>()?;
-
-            // Ignore const params for now
-            let fn_type_params = fn_generics
-                .type_or_const_params(db)
-                .into_iter()
-                .map(|it| it.as_type_param(db))
-                .collect::<Option<Vec<TypeParam>>>()?;
+    let impls = lookup.impls();
+    let clone = ctx.lang_trait("clone", "Clone").and_then(|trait_| {
+        let func = trait_.items(db).into_iter().find_map(|it| match it {
+            AssocItem::Function(f) if f.name(db).as_str() == Some("clone") => Some(f),
+            _ => None,
+        })?;
+        Some((trait_, func))
+    });
+    let deref_fn = db
+        .lang_item(ctx.scope.krate().into(), LangItem::Deref)
+        .and_then(|it| it.as_trait())
+        .and_then(|it| {
+            Trait::from(it).items(db).into_iter().find_map(|it| match it {
+                AssocItem::Function(f) if f.name(db).as_str() == Some("deref") => Some(f),
+                _ => None,
+            })
+        });
 
-            // Ignore all functions that have something to do with lifetimes as we don't check them
-            if !fn_generics.lifetime_params(db).is_empty()
-                || !imp_generics.lifetime_params(db).is_empty()
+    let derefs: Vec<(Type, Vec<Expr>)> = lookup
+        .new_types(NewTypesKey::Deref)
+        .into_iter()
+        .filter(|_| should_continue())
+        // Mutable references are meant to be written through, so only shared ones are read
+        .filter(|ty| ty.as_adt().is_some() || ty.is_reference() && !ty.is_mutable_reference())
+        .filter_map(|ty| {
+            let exprs: Vec<Expr> =
+                lookup.find(db, &ty)?.into_iter().filter(|expr| !expr.is_many()).collect();
+            let target = ty.autoderef(db).nth(1)?;
+            let deref = |expr, ty: &Type| Expr::Deref { expr: Box::new(expr), ty: ty.clone() };
+
+            // Sized values can be moved out of owned boxes, other pointers only allow copying
+            // them out
+            if target.is_copy(db) || is_lang_adt(db, &ty, LangItem::OwnedBox) && target.is_sized(db)
             {
-                return None;
+                let exprs: Vec<Expr> = exprs.into_iter().map(|expr| deref(expr, &target)).collect();
+                return Some((target, exprs));
             }
 
-            // Ignore functions with self param
-            if it.has_self_param(db) {
-                return None;
+            // Targets of user types are borrowed instead
+            if !ty.is_reference() && !is_lang_adt(db, &ty, LangItem::OwnedBox) {
+                let func = deref_fn?;
+                let exprs = exprs
+                    .into_iter()
+                    .filter(is_place)
+                    .map(|expr| Expr::Method {
+                        func,
+                        generics: Vec::new(),
+                        target: Box::new(expr),
+                        params: Vec::new(),
+                    })
+                    .collect();
+                return Some((Type::reference(&target, Mutability::Shared), exprs));
             }
 
-            // Filter out private and unsafe functions
-            if !it.is_visible_from(db, module) || it.is_unsafe_to_call(db) || it.is_unstable(db) {
+            // Values behind borrowed boxes can only be cloned out of them
+            if !ty.is_reference() || !is_lang_adt(db, &target, LangItem::OwnedBox) {
                 return None;
             }
-
-            // Only account for stable type parameters for now, unstable params can be default
-            // tho, for example in `Box<T, #[unstable] A: Allocator>`
-            if imp_type_params.iter().any(|it| it.is_unstable(db) && it.default(db).is_none())
-                || fn_type_params.iter().any(|it| it.is_unstable(db) && it.default(db).is_none())
-            {
+            let (trait_, func) = clone?;
+            let inner = target.autoderef(db).nth(1)?;
+            if inner.contains_unknown() || !impls.impls_trait(db, &inner, trait_, &[]) {
                 return None;
             }
+            let exprs = exprs
+                .into_iter()
+                .map(|expr| Expr::Method {
+                    func,
+                    generics: Vec::new(),
+                    target: Box::new(deref(deref(expr, &target), &inner)),
+                    params: Vec::new(),
+                })
+                .collect();
+            Some((inner, exprs))
+        })
+        .filter(|(_, exprs)| !exprs.is_empty())
+        .collect();
 
-            // Double check that we have fully known type
-            if ty.type_arguments().any(|it| it.contains_unknown()) {
-                return None;
-            }
+    for (ty, exprs) in &derefs {
+        lookup.insert(db, ty.clone(), exprs.iter().cloned());
+    }
 
-            let non_default_fn_type_params_len =
-                fn_type_params.iter().filter(|it| it.default(db).is_none()).count();
+    derefs
+        .into_iter()
+        .filter_map(move |(ty, exprs)| unify(db, &ty, &ctx.goal).then_some(exprs))
+        .flatten()
+}
 
-            // Ignore functions with generics for now as they kill the performance
-            // Also checking bounds for generics is problematic
-            if non_default_fn_type_params_len > 0 {
-                return None;
+/// # Trait object tactic
+///
+/// Attempts unsizing coercion of reached types to trait object goal (such as `&dyn Trait` or
+/// `Box<dyn Trait + Send>`)
+///
+/// Coercion is only offered if the type implements all the traits of the trait object, including
+/// auto traits such as `Send` or `Sync`.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn trait_object<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let impls = lookup.impls();
+    let unify = lookup.unify.clone();
+
+    let new_types = lookup.new_types(NewTypesKey::TraitObject);
+    let implements_all = |ty: &Type, traits: &[Trait]| {
+        !traits.is_empty() && traits.iter().all(|&it| impls.impls_trait(db, ty, it, &[]))
+    };
+
+    let exprs: Vec<Expr> = match ctx.goal.remove_ref().and_then(|it| it.as_dyn_traits()) {
+        // `&dyn Trait` from reference to any type implementing the traits
+        Some(traits) => new_types
+            .into_iter()
+            .filter(|_| should_continue())
+            .filter(|ty| !ty.is_reference() && implements_all(ty, &traits))
+            .filter_map(|ty| lookup.find(db, &ty))
+            .flatten()
+            .filter(|expr| !expr.is_many())
+            .map(|expr| Expr::Reference(Box::new(expr)))
+            .collect(),
+        // Smart pointers such as `Box<dyn Trait>` from the same pointer to type implementing
+        // the traits
+        None => {
+            let goal_args: Vec<Option<Vec<Trait>>> =
+                ctx.goal.type_arguments().map(|it| it.as_dyn_traits()).collect();
+            if goal_args.iter().all(Option::is_none) {
+                return Vec::new().into_iter();
             }
-
-            let generic_params = lookup
-                .iter_types()
-                .collect::<Vec<_>>() // Force take ownership
+            new_types
                 .into_iter()
-                .permutations(non_default_fn_type_params_len);
-
-            let exprs: Vec<_> = generic_params
                 .filter(|_| should_continue())
-                .filter_map(|generics| {
-                    // Insert default type params
-                    let mut g = generics.into_iter();
-                    let generics: Vec<_> = ty
-                        .type_arguments()
-                        .map(Some)
-                        .chain(fn_type_params.iter().map(|it| match it.default(db) {
-                            Some(ty) => Some(ty),
-                            None => {
-                                let generic = g.next().expect("Missing type param");
-                                it.trait_bounds(db)
-                                    .into_iter()
-                                    .all(|bound| generic.impls_trait(db, bound, &[]));
-                                // Filter out generics that do not unify due to trait bounds
-                                it.ty(db).could_unify_with(db, &generic).then_some(generic)
-                            }
-                        }))
-                        .collect::<Option<_>>()?;
-
-                    let ret_ty = it.ret_type_with_args(
-                        db,
-                        ty.type_arguments().chain(generics.iter().cloned()),
-                    );
-                    // Filter out functions that return references
-                    if ctx.config.enable_borrowcheck && ret_ty.contains_reference(db)
-                        || ret_ty.is_raw_ptr()
-                    {
-                        return None;
-                    }
+                .filter(|ty| ty.as_adt().is_some() && ty.as_adt() == ctx.goal.as_adt())
+                .filter(|ty| !unify(db, ty, &ctx.goal) && ty.could_coerce_to(db, &ctx.goal))
+                .filter(|ty| {
+                    ty.type_arguments().zip(goal_args.iter()).all(|(arg, traits)| match traits {
+                        Some(traits) => implements_all(&arg, traits),
+                        None => true,
+                    })
+                })
+                .filter_map(|ty| lookup.find(db, &ty))
+                .flatten()
+                .filter(|expr| !expr.is_many())
+                .collect()
+        }
+    };
 
-                    // Ignore functions that do not change the type
-                    // if ty.could_unify_with_deeply(db, &ret_ty) {
-                    //     return None;
-                    // }
+    if !exprs.is_empty() {
+        lookup.insert(db, ctx.goal.clone(), exprs.iter().cloned());
+    }
+    exprs.into_iter()
+}
 
-                    // Early exit if some param cannot be filled from lookup
-                    let param_exprs: Vec<Vec<Expr>> = it
-                        .params_without_self_with_args(
-                            db,
-                            ty.type_arguments().chain(generics.iter().cloned()),
-                        )
-                        .into_iter()
-                        .map(|field| lookup.find_autoref(db, field.ty()))
-                        .collect::<Option<_>>()?;
+/// # Cow tactic
+///
+/// Attempts to fill `Cow<'_, B>` goal with reached borrowed (`Cow::Borrowed(&B)`) or owned
+/// (`Cow::Owned(<B as ToOwned>::Owned)`) values
+///
+/// Regular type constructor tactic does not handle `Cow` as it has lifetime parameter and the
+/// owned variant is behind associated type.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn cow<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
 
-                    // Note that we need special case for 0 param constructors because of multi cartesian
-                    // product
-                    let fn_exprs: Vec<Expr> = if param_exprs.is_empty() {
-                        vec![Expr::Function { func: it, generics, params: Vec::new() }]
-                    } else {
-                        param_exprs
-                            .into_iter()
-                            .multi_cartesian_product()
-                            .map(|params| Expr::Function {
-                                func: it,
-                                generics: generics.clone(),
-                                params,
-                            })
-                            .collect()
-                    };
+    let cow = match ctx.goal.as_adt() {
+        Some(Adt::Enum(it)) if is_cow(db, it) => it,
+        _ => return Vec::new().into_iter(),
+    };
+    let generics: Vec<Type> = ctx.goal.type_arguments().collect();
 
-                    lookup.insert(ret_ty.clone(), fn_exprs.iter().cloned());
-                    Some((ret_ty, fn_exprs))
+    let exprs: Vec<Expr> = cow
+        .variants(db)
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter_map(|variant| {
+            let field = variant.fields(db).into_iter().next()?;
+            let field_ty = field.ty_with_args(db, generics.iter().cloned()).normalize(db);
+            let params = lookup.find(db, &field_ty)?;
+            let exprs: Vec<Expr> = params
+                .into_iter()
+                .map(|param| Expr::Variant {
+                    variant,
+                    generics: generics.clone(),
+                    params: vec![param],
                 })
                 .collect();
             Some(exprs)
         })
         .flatten()
-        .filter_map(|(ty, exprs)| ty.could_unify_with_deeply(db, &ctx.goal).then_some(exprs))
+        .collect();
+
+    if !exprs.is_empty() {
+        lookup.insert(db, ctx.goal.clone(), exprs.iter().cloned());
+    }
+    exprs.into_iter()
+}
+
+/// Check if enum is `Cow` from standard library
+fn is_cow(db: &dyn HirDatabase, it: Enum) -> bool {
+    is_std_item(db, &Adt::Enum(it).ty(db), &["borrow", "Cow"])
+}
+
+/// # To string tactic
+///
+/// Attempts to fill `String` goal by converting reached values that implement `Display` with
+/// `ToString::to_string`.
+///
+/// `Display` is checked through `ToString` as it is implemented for all `Display` types.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn to_string<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let impls = lookup.impls();
+
+    if !is_string(db, &ctx.goal) {
+        return Vec::new().into_iter();
+    }
+    let Some(to_string_trait) = ctx.lang_trait("string", "ToString") else {
+        return Vec::new().into_iter();
+    };
+    let Some(func) = to_string_trait.items(db).into_iter().find_map(|it| match it {
+        AssocItem::Function(f) if f.name(db).as_str() == Some("to_string") => Some(f),
+        _ => None,
+    }) else {
+        return Vec::new().into_iter();
+    };
+
+    let exprs: Vec<Expr> = lookup
+        .iter_types()
+        .collect::<Vec<_>>() // Force take ownership
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter(|ty| {
+            !ty.contains_unknown()
+                && !is_string(db, ty)
+                && impls.impls_trait(db, ty, to_string_trait, &[])
+        })
+        .filter_map(|ty| lookup.find(db, &ty))
         .flatten()
+        .map(|target| Expr::Method {
+            func,
+            generics: Vec::new(),
+            target: Box::new(target),
+            params: Vec::new(),
+        })
+        .collect();
+
+    if !exprs.is_empty() {
+        lookup.insert(db, ctx.goal.clone(), exprs.iter().cloned());
+    }
+    exprs.into_iter()
 }
 
-/// # Make tuple tactic
+/// # String concatenation tactic
 ///
-/// Attempts to create tuple types if any are listed in types wishlist
+/// Attempts to fill `String` goal by appending reached strings to reached `String` values with
+/// `+` (`a + &b`). Values that are not references are borrowed for the right hand side, which has
+/// to match the `Add` impl of `String` after dereferencing (`&String` is passed as `&str`).
+/// Results of concatenations are not concatenated again.
+///
+/// Only enabled with `TermSearchConfig::enable_string_concat` as the values are rarely meant to be
+/// concatenated.
 ///
 /// Updates lookup by new types reached and returns iterator that yields
 /// elements that unify with `goal`.
@@ -912,52 +1598,1955 @@ pub(super) fn impl_static_method<'a, DB: HirDatabase>(
 /// * `defs` - Set of items in scope at term search target location
 /// * `lookup` - Lookup table for types
 /// * `should_continue` - Function that indicates when to stop iterating
-pub(super) fn make_tuple<'a, DB: HirDatabase>(
-    ctx: &'a TermSearchCtx<'a, DB>,
-    _defs: &'a FxHashSet<ScopeDef>,
+pub(super) fn string_concat<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
     lookup: &'a mut LookupTable,
     should_continue: &'a dyn std::ops::Fn() -> bool,
 ) -> impl Iterator<Item = Expr> + 'a {
-    let db = ctx.sema.db;
-    let module = ctx.scope.module();
+    let db = ctx.sema.db();
+    let impls = lookup.impls();
 
-    lookup
-        .types_wishlist()
-        .clone()
+    if !ctx.config.enable_string_concat || !is_string(db, &ctx.goal) {
+        return Vec::new().into_iter();
+    }
+    let add = db
+        .lang_item(ctx.scope.krate().into(), LangItem::Add)
+        .and_then(|it| it.as_trait())
+        .map(Trait::from);
+    let Some((add, output)) = add.and_then(|trait_| {
+        let output = trait_.items(db).into_iter().find_map(|it| match it {
+            AssocItem::TypeAlias(it) if it.name(db).as_str() == Some("Output") => Some(it),
+            _ => None,
+        })?;
+        Some((trait_, output))
+    }) else {
+        return Vec::new().into_iter();
+    };
+    let Some(lhs) = lookup.find(db, &ctx.goal) else {
+        return Vec::new().into_iter();
+    };
+    let lhs: Vec<Expr> =
+        lhs.into_iter().filter(|it| !it.is_many() && !matches!(it, Expr::Add { .. })).collect();
+    let concats = |rhs_ty: &Type| {
+        impls.impls_trait(db, &ctx.goal, add, std::slice::from_ref(rhs_ty))
+            && ctx
+                .goal
+                .normalize_trait_assoc_type(db, std::slice::from_ref(rhs_ty), output)
+                .is_some_and(|it| it.could_unify_with(db, &ctx.goal))
+    };
+
+    let rhs: Vec<Expr> = lookup
+        .iter_types()
+        .collect::<Vec<_>>() // Force take ownership
         .into_iter()
         .filter(|_| should_continue())
-        .filter(|ty| ty.is_tuple())
-        .filter_map(move |ty| {
-            // Double check to not contain unknown
-            if ty.contains_unknown() {
+        .filter(|ty| !ty.contains_unknown() && !ty.is_mutable_reference())
+        .filter_map(|ty| {
+            let borrowed = !ty.is_reference();
+            let pointee = ty.remove_ref().unwrap_or_else(|| ty.clone());
+            if !pointee.autoderef(db).any(|it| concats(&Type::reference(&it, Mutability::Shared))) {
                 return None;
             }
+            let exprs = lookup
+                .find(db, &ty)?
+                .into_iter()
+                .filter(|it| !it.is_many() && !matches!(it, Expr::Add { .. }));
+            Some(
+                exprs
+                    .map(|it| match borrowed {
+                        true => Expr::Reference(Box::new(it)),
+                        false => it,
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .flatten()
+        .collect();
 
-            // Ignore types that have something to do with lifetimes
-            if ctx.config.enable_borrowcheck && ty.contains_reference(db) {
-                return None;
-            }
+    let exprs: Vec<Expr> = lhs
+        .into_iter()
+        .cartesian_product(rhs)
+        .filter(|(lhs, rhs)| !uses_moved_local(db, &[lhs.clone(), rhs.clone()]))
+        .map(|(lhs, rhs)| Expr::Add {
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+            ty: ctx.goal.clone(),
+        })
+        .collect();
 
-            // Early exit if some param cannot be filled from lookup
-            let param_exprs: Vec<Vec<Expr>> =
-                ty.type_arguments().map(|field| lookup.find(db, &field)).collect::<Option<_>>()?;
+    if !exprs.is_empty() {
+        lookup.insert(db, ctx.goal.clone(), exprs.iter().cloned());
+    }
+    exprs.into_iter()
+}
 
-            let exprs: Vec<Expr> = param_exprs
-                .into_iter()
-                .multi_cartesian_product()
-                .filter(|_| should_continue())
-                .map(|params| {
-                    let tys: Vec<Type> = params.iter().map(|it| it.ty(db)).collect();
-                    let tuple_ty = Type::new_tuple(module.krate().into(), &tys);
+/// # Integer add tactic
+///
+/// Attempts to fill integer goals by adding two reached integers of the goal type with `+`
+/// (`a + b`), as long as the type implements `Add` for itself. Sums are not added again.
+///
+/// Only enabled with `TermSearchConfig::enable_integer_add` as the values are rarely meant to be
+/// added.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn integer_add<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let impls = lookup.impls();
 
-                    let expr = Expr::Tuple { ty: tuple_ty.clone(), params };
-                    lookup.insert(tuple_ty, iter::once(expr.clone()));
-                    expr
-                })
-                .collect();
+    if !ctx.config.enable_integer_add || !ctx.goal.is_int_or_uint() {
+        return Vec::new().into_iter();
+    }
+    let add = db
+        .lang_item(ctx.scope.krate().into(), LangItem::Add)
+        .and_then(|it| it.as_trait())
+        .map(Trait::from);
+    if !add.is_some_and(|it| impls.impls_trait(db, &ctx.goal, it, std::slice::from_ref(&ctx.goal)))
+    {
+        return Vec::new().into_iter();
+    }
+    let Some(operands) = lookup.find(db, &ctx.goal) else {
+        return Vec::new().into_iter();
+    };
+    let operands: Vec<Expr> = operands
+        .into_iter()
+        .filter(|it| !it.is_many() && !matches!(it, Expr::Add { .. }))
+        .collect();
 
-            Some(exprs)
+    let exprs: Vec<Expr> = operands
+        .iter()
+        .cartesian_product(&operands)
+        .filter(|_| should_continue())
+        .map(|(lhs, rhs)| Expr::Add {
+            lhs: Box::new(lhs.clone()),
+            rhs: Box::new(rhs.clone()),
+            ty: ctx.goal.clone(),
         })
+        .collect();
+
+    if !exprs.is_empty() {
+        lookup.insert(db, ctx.goal.clone(), exprs.iter().cloned());
+    }
+    exprs.into_iter()
+}
+
+/// # Try operator tactic
+///
+/// Attempts unwrapping reached `Result`s with the `?` operator (`foo()?`) if the function the hole
+/// is in returns a `Result`, see `TermSearchConfig::enclosing_ret_ty`. The error is propagated as
+/// is or converted with `From`, so only the `Result`s with the returned error type or an error
+/// type it implements `From` for are unwrapped. Returned `Result` goals are reached by wrapping
+/// the unwrapped values back in `Ok` (`Ok(foo()?)`).
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn try_operator<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let impls = lookup.impls();
+    let unify = lookup.unify.clone();
+
+    let Some(error) = ctx
+        .config
+        .enclosing_ret_ty
+        .as_ref()
+        .filter(|it| is_result(db, it))
+        .and_then(|it| it.type_arguments().nth(1))
+        .filter(|it| !it.contains_unknown())
+    else {
+        return Vec::new().into_iter();
+    };
+    let from = ctx.lang_trait("convert", "From");
+    let propagates = |it: &Type| {
+        unify(db, it, &error)
+            || from.map_or(false, |from| impls.impls_trait(db, &error, from, &[it.clone()]))
+    };
+
+    let unwrapped: Vec<(Type, Vec<Expr>)> = lookup
+        .new_types(NewTypesKey::TryOperator)
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter(|ty| is_result(db, ty) && !ty.contains_unknown())
+        .filter_map(|ty| {
+            let (ok, err) = ty.type_arguments().collect_tuple()?;
+            if !propagates(&err) {
+                return None;
+            }
+            let exprs: Vec<Expr> = lookup
+                .find(db, &ty)?
+                .into_iter()
+                // `Ok(x)?` is just `x` and `Err(e)?` always returns
+                .filter(|it| !it.is_many() && !matches!(it, Expr::Variant { .. }))
+                .map(|it| Expr::Try { expr: Box::new(it), ty: ok.clone() })
+                .collect();
+            Some((ok, exprs))
+        })
+        .filter(|(_, exprs)| !exprs.is_empty())
+        .collect();
+
+    for (ty, exprs) in &unwrapped {
+        lookup.insert(db, ty.clone(), exprs.iter().cloned());
+    }
+
+    unwrapped
+        .into_iter()
+        .filter(|(ty, _)| unify(db, ty, &ctx.goal))
+        .flat_map(|(_, exprs)| exprs)
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Check if type is `Result` from standard library
+fn is_result(db: &dyn HirDatabase, ty: &Type) -> bool {
+    is_std_item(db, ty, &["result", "Result"])
+}
+
+/// Check if type is `Option` from standard library
+fn is_option(db: &dyn HirDatabase, ty: &Type) -> bool {
+    is_std_item(db, ty, &["option", "Option"])
+}
+
+/// Check if type is `Option` or `Result` from standard library
+fn is_option_or_result(db: &dyn HirDatabase, ty: &Type) -> bool {
+    is_option(db, ty) || is_result(db, ty)
+}
+
+/// Check if type is `String` from standard library
+pub(super) fn is_string(db: &dyn HirDatabase, ty: &Type) -> bool {
+    is_std_item(db, ty, &["string", "String"])
+}
+
+/// # Famous types tactic
+///
+/// Attempts different values of well known types such as `true` or `false`, as well as the ones
+/// from `TermSearchConfig::extra_known_values`.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// _Note that there is no point of calling it iteratively as the output is always the same_
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+pub(super) fn famous_types<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+    let module = ctx.scope.module();
+    [
+        Expr::FamousType { ty: Type::new(db, module.id, TyBuilder::bool()), value: "true" },
+        Expr::FamousType { ty: Type::new(db, module.id, TyBuilder::bool()), value: "false" },
+        Expr::FamousType { ty: Type::new(db, module.id, TyBuilder::unit()), value: "()" },
+    ]
+    .into_iter()
+    .map(|expr| (expr.ty(db), expr))
+    .chain(ctx.config.extra_known_values.iter().cloned())
+    .map(|(ty, expr)| {
+        lookup.insert(db, ty.clone(), std::iter::once(expr.clone()));
+        (ty, expr)
+    })
+    .filter(move |(ty, _)| unify(db, ty, &ctx.goal))
+    .map(|(_, expr)| expr)
+}
+
+/// # Impl static method (without self type) tactic
+///
+/// Attempts different functions from impl blocks that take no self parameter.
+///
+/// For trait impls the functions provided by the trait and not overridden in the impl (such as
+/// `fn make_pair() -> (Self, Self)` with default body) are tried as well, as long as the type
+/// implements the trait. Type parameters (such as the goal `T` of `fn f<T: Default>() -> T`) are
+/// never opaque, the functions of their trait bounds are tried for them (`T::default()`).
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn impl_static_method<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+    let module = ctx.scope.module();
+    let impls = lookup.impls();
+    lookup
+        .types_wishlist()
+        .clone()
+        .into_iter()
+        .chain(iter::once(ctx.goal.clone()))
+        .filter(|_| should_continue())
+        .flat_map(move |ty| {
+            let mut fns: Vec<(Option<Impl>, Function, Vec<Type>)> =
+                Impl::all_for_type(db, ty.clone())
+                    .into_iter()
+                    .filter(|imp| !imp.is_unsafe(db))
+                    .flat_map(|imp| {
+                        impl_fns(db, &impls, &ty, imp)
+                            .into_iter()
+                            .map(move |(f, self_generics)| (Some(imp), f, self_generics))
+                    })
+                    .collect();
+            fns.extend(bound_fns(db, &ty).into_iter().map(|(f, generics)| (None, f, generics)));
+            fns.into_iter().map(move |(imp, f, self_generics)| (imp, ty.clone(), f, self_generics))
+        })
+        .filter_map(move |(imp, ty, it, self_generics)| {
+            let fn_generics = GenericDef::from(it);
+            let imp_generics = imp.map(GenericDef::from);
+
+            // Ignore const params for now
+            let Some(imp_type_params) = imp_generics
+                .map_or_else(Vec::new, |it| it.type_or_const_params(db))
+                .into_iter()
+                .map(|it| it.as_type_param(db))
+                .collect::<Option<Vec<TypeParam>>>()
+            else {
+                return lookup.reject(RejectReason::UnsupportedGenerics, it);
+            };
+
+            // Ignore const params for now
+            let Some(fn_type_params) = fn_generics
+                .type_or_const_params(db)
+                .into_iter()
+                .map(|it| it.as_type_param(db))
+                .collect::<Option<Vec<TypeParam>>>()
+            else {
+                return lookup.reject(RejectReason::UnsupportedGenerics, it);
+            };
+
+            // Ignore all functions that have something to do with lifetimes as we don't check them
+            if !fn_generics.lifetime_params(db).is_empty()
+                || imp_generics.map_or(false, |it| !it.lifetime_params(db).is_empty())
+            {
+                return lookup.reject(RejectReason::UnsupportedGenerics, it);
+            }
+
+            // Ignore functions with self param
+            if it.has_self_param(db) {
+                return None;
+            }
+
+            // Ignore functions with many params as they explode the amount of combinations
+            if it.num_params(db) > ctx.config.max_fn_args {
+                return lookup.reject(RejectReason::TooManyArgs, it);
+            }
+
+            // Filter out private, unsafe, blocked and panicking functions
+            if let Some(reason) = assoc_fn_reject_reason(ctx, it, module) {
+                return lookup.reject(reason, it);
+            }
+
+            // Only account for stable type parameters for now, unstable params can be default
+            // tho, for example in `Box<T, #[unstable] A: Allocator>`
+            if imp_type_params.iter().any(|it| it.is_unstable(db) && it.default(db).is_none())
+                || fn_type_params.iter().any(|it| it.is_unstable(db) && it.default(db).is_none())
+            {
+                return lookup.reject(RejectReason::Unstable, it);
+            }
+
+            // Double check that we have fully known type
+            if ty.type_arguments().any(|it| it.contains_unknown()) {
+                return None;
+            }
+
+            let non_default_fn_type_params_len =
+                fn_type_params.iter().filter(|it| it.default(db).is_none()).count();
+
+            // Ignore functions with generics for now as they kill the performance
+            // Also checking bounds for generics is problematic
+            if non_default_fn_type_params_len > 0 {
+                return None;
+            }
+
+            let generic_params = lookup
+                .iter_types()
+                .collect::<Vec<_>>() // Force take ownership
+                .into_iter()
+                .permutations(non_default_fn_type_params_len);
+
+            let exprs: Vec<_> = generic_params
+                .filter(|_| should_continue())
+                .filter_map(|generics| {
+                    // Insert default type params
+                    let mut g = generics.into_iter();
+                    let generics: Vec<_> = self_generics
+                        .iter()
+                        .cloned()
+                        .map(Some)
+                        .chain(fn_type_params.iter().map(|it| match it.default(db) {
+                            Some(ty) => Some(ty),
+                            None => {
+                                let generic = g.next().expect("Missing type param");
+                                it.trait_bounds(db)
+                                    .into_iter()
+                                    .all(|bound| generic.impls_trait(db, bound, &[]));
+                                // Filter out generics that do not unify due to trait bounds
+                                it.ty(db).could_unify_with(db, &generic).then_some(generic)
+                            }
+                        }))
+                        .collect::<Option<_>>()?;
+
+                    let ret_ty = it.ret_type_with_args(db, generics.iter().cloned()).normalize(db);
+                    // Filter out functions that return references
+                    if ctx.config.enable_borrowcheck && ret_ty.contains_reference(db)
+                        || ret_ty.is_raw_ptr()
+                    {
+                        return lookup.reject(RejectReason::ReturnsReference, it);
+                    }
+
+                    // Ignore functions that do not change the type
+                    // if ty.could_unify_with_deeply(db, &ret_ty) {
+                    //     return None;
+                    // }
+
+                    // Early exit if some param cannot be filled from lookup
+                    let Some(param_exprs) = it
+                        .params_without_self_with_args(db, generics.iter().cloned())
+                        .into_iter()
+                        .map(|field| lookup.find_autoref(db, field.ty()))
+                        .collect::<Option<Vec<Vec<Expr>>>>()
+                    else {
+                        return lookup.reject(RejectReason::MissingArguments, it);
+                    };
+
+                    let fn_exprs: Vec<Expr> = bounded_cartesian_product(param_exprs)
+                        .map(|params| Expr::Function {
+                            func: it,
+                            generics: generics.clone(),
+                            params,
+                        })
+                        .collect();
+
+                    lookup.insert(db, ret_ty.clone(), fn_exprs.iter().cloned());
+                    Some((ret_ty, fn_exprs))
+                })
+                .collect();
+            Some(exprs)
+        })
+        .flatten()
+        .filter_map(move |(ty, exprs)| unify(db, &ty, &ctx.goal).then_some(exprs))
+        .flatten()
+}
+
+/// Tuples with more elements are not built as the amount of combinations explodes
+const MAX_TUPLE_LEN: usize = 4;
+/// Maximum amount of trees tried for a single tuple element
+const MAX_TUPLE_ELEM_VARIATIONS: usize = 3;
+
+/// # Make tuple tactic
+///
+/// Attempts to create tuple types if any are listed in types wishlist
+///
+/// Elements are filled independently of each other, so the element types may differ
+/// (`(i32, String, bool)`). Only tuples of up to `MAX_TUPLE_LEN` elements are built and at most
+/// `MAX_TUPLE_ELEM_VARIATIONS` trees are tried for each element. Tuples moving a local that is
+/// also used by another element are left out as they do not borrow check.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn make_tuple<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+    let module = ctx.scope.module();
+
+    lookup
+        .types_wishlist()
+        .clone()
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter(|ty| ty.is_tuple())
+        .filter_map(move |ty| {
+            // Double check to not contain unknown
+            if ty.contains_unknown() {
+                return None;
+            }
+
+            // Ignore types that have something to do with lifetimes
+            if ctx.config.enable_borrowcheck && ty.contains_reference(db) {
+                return None;
+            }
+
+            if ty.tuple_fields(db).len() > MAX_TUPLE_LEN {
+                return None;
+            }
+
+            // Early exit if some param cannot be filled from lookup
+            let param_exprs: Vec<Vec<Expr>> = ty
+                .type_arguments()
+                .map(|field| {
+                    let exprs = lookup.find(db, &field)?;
+                    Some(exprs.into_iter().take(MAX_TUPLE_ELEM_VARIATIONS).collect())
+                })
+                .collect::<Option<_>>()?;
+
+            let exprs: Vec<Expr> = bounded_cartesian_product(param_exprs)
+                .filter(|_| should_continue())
+                .filter(|params| !uses_moved_local(db, params))
+                .map(|params| {
+                    let tys: Vec<Type> = params.iter().map(|it| it.ty(db)).collect();
+                    let tuple_ty = Type::new_tuple(module.krate().into(), &tys);
+
+                    let expr = Expr::Tuple { ty: tuple_ty.clone(), params };
+                    lookup.insert(db, tuple_ty, iter::once(expr.clone()));
+                    expr
+                })
+                .collect();
+
+            Some(exprs)
+        })
+        .flatten()
+        .filter_map(move |expr| unify(db, &expr.ty(db), &ctx.goal).then_some(expr))
+}
+
+/// Maximum amount of argument combinations built for a single function, method or tuple
+pub(super) const MAX_ARG_COMBINATIONS: usize = 256;
+
+/// Cartesian product of the alternatives for each argument, bounded by `MAX_ARG_COMBINATIONS`
+///
+/// The amount of combinations is computed with checked arithmetic as it overflows `usize` for
+/// enough arguments with many alternatives each. If there are too many of them, the longest lists
+/// of alternatives are halved until the product fits, so that only the combinations of the first
+/// (simplest) alternatives are built.
+///
+/// Unlike `multi_cartesian_product`, no arguments at all yield the single empty combination.
+pub(super) fn bounded_cartesian_product<T: Clone>(
+    mut args: Vec<Vec<T>>,
+) -> impl Iterator<Item = Vec<T>> {
+    if args.is_empty() {
+        return itertools::Either::Left(iter::once(Vec::new()));
+    }
+    let combinations =
+        |args: &[Vec<T>]| args.iter().try_fold(1usize, |acc, it| acc.checked_mul(it.len()));
+    while combinations(&args).map_or(true, |it| it > MAX_ARG_COMBINATIONS) {
+        let Some(longest) = args.iter_mut().max_by_key(|it| it.len()) else { break };
+        longest.truncate(longest.len().div_ceil(2));
+    }
+    itertools::Either::Right(args.into_iter().multi_cartesian_product())
+}
+
+/// Check if any of the expressions moves a local that is also used by another one of them
+///
+/// Locals of `Copy` types and the ones only used behind references are never moved.
+fn uses_moved_local(db: &dyn HirDatabase, exprs: &[Expr]) -> bool {
+    // Locals used by each of the expressions and whether they are moved
+    let uses: Vec<Vec<(Local, bool)>> = exprs
+        .iter()
+        .map(|expr| {
+            let mut uses = Vec::new();
+            let mut stack = vec![(expr, false)];
+            while let Some((expr, borrowed)) = stack.pop() {
+                match expr {
+                    Expr::Local(it) => uses.push((*it, !borrowed && !it.ty(db).is_copy(db))),
+                    Expr::Reference(it) | Expr::MutReference(it) => stack.push((it, true)),
+                    _ => stack.extend(expr.children().into_iter().map(|it| (it, borrowed))),
+                }
+            }
+            uses
+        })
+        .collect();
+    uses.iter().enumerate().any(|(idx, used)| {
+        used.iter().filter(|(_, moved)| *moved).any(|(local, _)| {
+            uses.iter()
+                .enumerate()
+                .any(|(other, it)| other != idx && it.iter().any(|(l, _)| l == local))
+        })
+    })
+}
+
+/// # Repeat array tactic
+///
+/// Attempts to create array types listed in types wishlist by repeating a single element
+/// (`[elem; N]`), this requires the element type to be `Copy`. The length is either known from the
+/// array type or given by a constant generic parameter in scope, arrays of lengths that cannot be
+/// evaluated are skipped.
+///
+/// Elements that cannot be reached are filled with `Default::default()` if
+/// `TermSearchConfig::enable_default_fields` is set.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn repeat_array<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+
+    lookup
+        .types_wishlist()
+        .clone()
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter_map(move |ty| {
+            let (elem_ty, len) = match ty.as_array(db) {
+                Some((elem_ty, len)) => (elem_ty, ArrayLen::Known(len)),
+                None => {
+                    let (elem_ty, param) = ty.as_array_with_const_param_len(db)?;
+                    let in_scope = ScopeDef::GenericParam(GenericParam::ConstParam(param));
+                    if !defs.contains(&in_scope) {
+                        return None;
+                    }
+                    (elem_ty, ArrayLen::Param(param))
+                }
+            };
+
+            // Double check to not contain unknown
+            if ty.contains_unknown() || !elem_ty.is_copy(db) {
+                return None;
+            }
+
+            let elems = lookup
+                .find(db, &elem_ty)
+                .or_else(|| ctx.default_value(lookup, &elem_ty).map(|it| vec![it]))?;
+            let exprs: Vec<Expr> = elems
+                .into_iter()
+                .map(|elem| Expr::ArrayRepeat { ty: ty.clone(), elem: Box::new(elem), len })
+                .collect();
+            lookup.insert(db, ty, exprs.iter().cloned());
+
+            Some(exprs)
+        })
+        .flatten()
+        .filter_map(move |expr| unify(db, &expr.ty(db), &ctx.goal).then_some(expr))
+}
+
+/// Maximum length of the arrays built by listing their elements
+const MAX_ARRAY_LITERAL_LEN: usize = 4;
+
+/// # Array literal tactic
+///
+/// Attempts to create array types listed in types wishlist by listing distinct elements
+/// (`[a, b]`). Only arrays of 2 to `MAX_ARRAY_LITERAL_LEN` elements are built, arrays of a single
+/// repeated element are left to the repeat array tactic.
+///
+/// Elements are coerced to the element type in the literal, so values of all the reached types
+/// that coerce to it are used, borrowing them if only the reference coerces. This way arrays of
+/// trait objects such as `[&dyn Display; 2]` are filled with values of different types
+/// implementing the trait (`[&a, &b]`). The first elements found are used so that only one
+/// literal is built per array type, elements moving the same local are left out.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn array_literal<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+
+    lookup
+        .types_wishlist()
+        .clone()
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter_map(move |ty| {
+            let (elem_ty, len) = ty.as_array(db)?;
+
+            // Double check to not contain unknown
+            if ty.contains_unknown() || !(2..=MAX_ARRAY_LITERAL_LEN).contains(&len) {
+                return None;
+            }
+
+            let reached: Vec<Type> =
+                lookup.iter_types().filter(|it| !it.contains_unknown()).collect();
+            let candidates = reached.into_iter().filter_map(|it| {
+                let borrow = if lookup.could_unify(db, &it, &elem_ty)
+                    || it.could_coerce_to(db, &elem_ty)
+                {
+                    false
+                } else if Type::reference(&it, Mutability::Shared).could_coerce_to(db, &elem_ty) {
+                    true
+                } else {
+                    return None;
+                };
+                let exprs = lookup.find(db, &it)?;
+                Some(exprs.into_iter().filter(|expr| !expr.is_many()).map(
+                    move |expr| match borrow {
+                        true => Expr::Reference(Box::new(expr)),
+                        false => expr,
+                    },
+                ))
+            });
+
+            let mut elems: Vec<Expr> = Vec::new();
+            for candidate in candidates.flatten() {
+                if elems.len() == len {
+                    break;
+                }
+                if elems.contains(&candidate) {
+                    continue;
+                }
+                elems.push(candidate);
+                if uses_moved_local(db, &elems) {
+                    elems.pop();
+                }
+            }
+            if elems.len() < len {
+                return None;
+            }
+
+            let expr = Expr::Array { ty: ty.clone(), elems };
+            lookup.insert(db, ty, iter::once(expr.clone()));
+            Some(expr)
+        })
+        .filter(move |expr| unify(db, &expr.ty(db), &ctx.goal))
+}
+
+/// # Single element collection tactic
+///
+/// Attempts to create collections listed in types wishlist from a single element. `Vec` is built
+/// with the `vec!` macro of standard library if it is in scope (`vec![x]`), other collections
+/// implementing `FromIterator` for their first type argument with `iter::once(x).collect()`.
+///
+/// The element is moved into the collection, so each tree uses it only once.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn single_element_collection<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+    let vec_macro = vec_macro(ctx);
+    let once_collect = once_and_collect(ctx);
+
+    lookup
+        .types_wishlist()
+        .clone()
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter(|ty| ty.as_adt().is_some() && !ty.contains_unknown())
+        .filter_map(move |ty| {
+            let elem_ty = ty.type_arguments().next()?;
+            let exprs: Vec<Expr> = match vec_macro {
+                Some(mac) if is_vec(db, &ty) => lookup
+                    .find(db, &elem_ty)?
+                    .into_iter()
+                    .map(|elem| Expr::VecMacro { mac, ty: ty.clone(), elem: Box::new(elem) })
+                    .collect(),
+                _ => {
+                    let (once, collect) = once_collect?;
+                    let once_ty = once.ret_type_with_args(db, iter::once(elem_ty.clone()));
+                    let args = [once_ty, ty.clone()];
+                    let ret_ty = collect.ret_type_with_args(db, args.iter().cloned());
+                    // `collect` is only callable for collections implementing `FromIterator`
+                    if !ret_ty.could_unify_with_deeply(db, &ty)
+                        || !collect.predicates_hold_with_args(db, args.iter().cloned())
+                    {
+                        return None;
+                    }
+                    lookup
+                        .find(db, &elem_ty)?
+                        .into_iter()
+                        .map(|elem| Expr::Method {
+                            func: collect,
+                            generics: vec![ty.clone()],
+                            target: Box::new(Expr::Function {
+                                func: once,
+                                generics: vec![elem_ty.clone()],
+                                params: vec![elem],
+                            }),
+                            params: Vec::new(),
+                        })
+                        .collect()
+                }
+            };
+            lookup.insert(db, ty, exprs.iter().cloned());
+            Some(exprs)
+        })
+        .flatten()
+        .filter_map(move |expr| unify(db, &expr.ty(db), &ctx.goal).then_some(expr))
+}
+
+/// Check if type is `Vec` from standard library
+pub(super) fn is_vec(db: &dyn HirDatabase, ty: &Type) -> bool {
+    is_std_item(db, ty, &["vec", "Vec"])
+}
+
+/// The `vec!` macro of standard library if it is in scope
+fn vec_macro(ctx: &TermSearchCtx<'_>) -> Option<Macro> {
+    let db = ctx.sema.db();
+    let mut res = None;
+    ctx.scope.process_all_names(&mut |name, def| match def {
+        ScopeDef::ModuleDef(ModuleDef::Macro(it))
+            if name.as_str() == Some("vec") && it.module(db).krate().origin(db).is_lang() =>
+        {
+            res = Some(it)
+        }
+        _ => (),
+    });
+    res
+}
+
+/// `iter::once` and `Iterator::collect` of standard library
+fn once_and_collect(ctx: &TermSearchCtx<'_>) -> Option<(Function, Function)> {
+    let db = ctx.sema.db();
+    let iterator = ctx.lang_trait("iterator", "Iterator")?;
+    let collect = iterator.items(db).into_iter().find_map(|it| match it {
+        AssocItem::Function(f) if f.name(db).as_str() == Some("collect") => Some(f),
+        _ => None,
+    })?;
+    let iter_module = iterator
+        .module(db)
+        .krate()
+        .root_module()
+        .children(db)
+        .find(|it| it.name(db).is_some_and(|it| it.as_str() == Some("iter")))?;
+    let once = iter_module.scope(db, None).into_iter().find_map(|(name, def)| match def {
+        ScopeDef::ModuleDef(ModuleDef::Function(f)) if name.as_str() == Some("once") => Some(f),
+        _ => None,
+    })?;
+    Some((once, collect))
+}
+
+/// # Fieldless enum cast tactic
+///
+/// Attempts to cast values of fieldless (C-like) enums to integer goal types with `as`.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn fieldless_enum_cast<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+
+    let enum_tys: Vec<Type> = match ctx.goal.is_int_or_uint() {
+        true => lookup
+            .iter_types()
+            .filter(|ty| match ty.as_adt() {
+                Some(Adt::Enum(it)) => !it.is_data_carrying(db),
+                _ => false,
+            })
+            .collect(),
+        false => Vec::new(),
+    };
+
+    let exprs: Vec<Expr> = enum_tys
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter_map(|ty| lookup.find(db, &ty))
+        .flatten()
+        .filter(|expr| !expr.is_many())
+        .map(|expr| Expr::Cast { expr: Box::new(expr), ty: ctx.goal.clone() })
+        .collect();
+
+    if !exprs.is_empty() {
+        lookup.insert(db, ctx.goal.clone(), exprs.iter().cloned());
+    }
+    exprs.into_iter()
+}
+
+/// # Raw pointer tactic
+///
+/// Attempts to fill raw pointer goals (`*const T` and `*mut T`) by casting references with `as`.
+/// References to `T` are cast as is (`r as *const T`), places of type `T` are referenced first
+/// (`&x as *const T`). Only mutable references and `mut` locals are used for `*mut T`.
+///
+/// Only enabled with `TermSearchConfig::enable_raw_pointers` as raw pointers are rarely wanted
+/// outside of unsafe code.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn raw_pointer<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let pointee = match ctx.config.enable_raw_pointers {
+        true => ctx.goal.remove_raw_ptr(),
+        false => None,
+    };
+    let Some(pointee) = pointee.filter(|it| !it.contains_unknown()) else {
+        return Vec::new().into_iter();
+    };
+    let mutability = match ctx.goal.is_mutable_raw_ptr() {
+        true => Mutability::Mut,
+        false => Mutability::Shared,
+    };
+
+    let references: Vec<Expr> = iter::once(Type::reference(&pointee, Mutability::Mut))
+        .chain((mutability == Mutability::Shared).then(|| Type::reference(&pointee, mutability)))
+        .filter_map(|ty| lookup.find(db, &ty))
+        .flatten()
+        .collect();
+    let places = lookup
+        .find(db, &pointee)
+        .into_iter()
+        .flatten()
+        .filter(|expr| match (mutability, expr) {
+            (Mutability::Shared, _) => is_place(expr),
+            (Mutability::Mut, Expr::Local(it)) => it.is_mut(db),
+            (Mutability::Mut, _) => false,
+        })
+        .map(|expr| match mutability {
+            Mutability::Shared => Expr::Reference(Box::new(expr)),
+            Mutability::Mut => Expr::MutReference(Box::new(expr)),
+        });
+
+    let exprs: Vec<Expr> = references
+        .into_iter()
+        .chain(places)
+        .filter(|_| should_continue())
+        .filter(|expr| !expr.is_many())
+        .map(|expr| Expr::Cast { expr: Box::new(expr), ty: ctx.goal.clone() })
+        .collect();
+
+    if !exprs.is_empty() {
+        lookup.insert(db, ctx.goal.clone(), exprs.iter().cloned());
+    }
+    exprs.into_iter()
+}
+
+/// # Box pin tactic
+///
+/// Attempts to fill `Pin<Box<T>>` goals by pinning reached values with `Box::pin(..)`.
+/// This includes pinned trait objects such as `Pin<Box<dyn Future<Output = T>>>` that are reached
+/// through unsizing coercion of the pinned box.
+///
+/// Besides the goal, only the types other tactics wish for are filled, so that `Pin<Box<T>>` can be
+/// used as an argument. Pinned boxes are never pinned again to keep the nesting bounded.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn box_pin<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+
+    let new_types = lookup.new_types(NewTypesKey::BoxPin);
+    let targets: Vec<(Type, Type, Function)> = lookup
+        .types_wishlist()
+        .iter()
+        .chain(iter::once(&ctx.goal))
+        .unique()
+        .filter_map(|ty| box_pin_fn(db, ty).map(|(boxed, func)| (ty.clone(), boxed, func)))
+        .collect();
+    if targets.is_empty() {
+        return Vec::new().into_iter();
+    }
+
+    let mut exprs = Vec::new();
+    for (target, boxed, func) in targets {
+        let pinned: Vec<Expr> = new_types
+            .iter()
+            .filter(|_| should_continue())
+            .filter(|ty| !ty.is_reference() && box_pin_fn(db, ty).is_none())
+            .filter_map(|ty| {
+                let generics: Vec<Type> =
+                    iter::once(ty.clone()).chain(boxed.type_arguments().skip(1)).collect();
+                let ret_ty = func.ret_type_with_args(db, generics.iter().cloned());
+                if !unify(db, &ret_ty, &target) && !ret_ty.could_coerce_to(db, &target) {
+                    return None;
+                }
+                let params = lookup.find(db, ty)?;
+                Some(params.into_iter().filter(|it| !it.is_many()).map(move |param| {
+                    Expr::Function { func, generics: generics.clone(), params: vec![param] }
+                }))
+            })
+            .flatten()
+            .collect();
+        if pinned.is_empty() {
+            continue;
+        }
+        lookup.insert(db, target.clone(), pinned.iter().cloned());
+        if unify(db, &target, &ctx.goal) {
+            exprs.extend(pinned);
+        }
+    }
+    exprs.into_iter()
+}
+
+/// Get the `Box` type and `Box::pin` function if type is `Pin<Box<T>>` from standard library
+fn box_pin_fn(db: &dyn HirDatabase, ty: &Type) -> Option<(Type, Function)> {
+    if !is_lang_adt(db, ty, LangItem::Pin) {
+        return None;
+    }
+    let boxed = ty.type_arguments().next()?;
+    if !is_lang_adt(db, &boxed, LangItem::OwnedBox) {
+        return None;
+    }
+    let func = Impl::all_for_type(db, boxed.clone())
+        .into_iter()
+        .flat_map(|imp| imp.items(db))
+        .find_map(|it| match it {
+            AssocItem::Function(f)
+                if f.name(db).as_str() == Some("pin") && !f.has_self_param(db) =>
+            {
+                Some(f)
+            }
+            _ => None,
+        })?;
+    Some((boxed, func))
+}
+
+/// # Closure tactic
+///
+/// Attempts to fill closure trait object goals (`Box<dyn Fn(A) -> B>`, `&dyn Fn(A) -> B` or
+/// `&mut dyn FnMut(A) -> B`) with closures (`Box::new(|x| ..)` and `&|x| ..`). The body of the
+/// closure is found by searching for the return type with the parameters as the only locals, so
+/// the closure captures nothing and implements all of `Fn`, `FnMut` and `FnOnce`. As the body
+/// cannot capture the locals either, it also lives as long as any trait object needs it to.
+///
+/// Besides the goal, only the types other tactics wish for are filled, so that closures can be
+/// passed as arguments (such as callbacks stored in struct fields). Every type is only searched
+/// for once.
+///
+/// Closures returning closures (`Box::new(|x| Box::new(move |y| ..))`) are built by the tactic
+/// running again in the search for the body, up to `TermSearchConfig::max_closure_depth` (at most
+/// [`MAX_CLOSURE_DEPTH`]) levels deep. The inner closures capture the parameters of the outer
+/// ones by value.
+///
+/// Reached `Option<T>` and `Result<T, E>` values are also unwrapped with closures returning
+/// fallback values (`r.unwrap_or_else(|_| x)`), see [`unwrap_or_else`].
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn closure<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+
+    if lookup.closure_depth >= ctx.config.max_closure_depth.min(MAX_CLOSURE_DEPTH) {
+        return Vec::new().into_iter();
+    }
+    let mut exprs = unwrap_or_else(ctx, lookup, should_continue);
+    let targets: Vec<Type> =
+        lookup.types_wishlist().iter().chain(iter::once(&ctx.goal)).unique().cloned().collect();
+
+    for target in targets {
+        if !should_continue() {
+            break;
+        }
+        if !lookup.closure_goals.insert(target.clone()) {
+            continue;
+        }
+        let Some((wrap, object)) = closure_object(db, &target) else { continue };
+        let Some(callable) = object.as_callable(db) else { continue };
+        let params: Vec<Type> = callable.params(db).into_iter().map(|(_, ty)| ty).collect();
+        let ret = callable.return_type();
+        if params.iter().chain(iter::once(&ret)).any(|it| it.contains_unknown()) {
+            continue;
+        }
+        let Some(params) = closure_params(ctx, params) else { continue };
+
+        let closures: Vec<Expr> =
+            super::closure_bodies(ctx, lookup, should_continue, &params, ret, false)
+                .into_iter()
+                .map(|body| Expr::Closure {
+                    params: params.clone(),
+                    body: Box::new(body),
+                    ty: object.clone(),
+                })
+                .map(|it| match wrap {
+                    ClosureWrap::Ref => Expr::Reference(Box::new(it)),
+                    ClosureWrap::MutRef => Expr::MutReference(Box::new(it)),
+                    ClosureWrap::Box(func) => {
+                        Expr::Function { func, generics: vec![object.clone()], params: vec![it] }
+                    }
+                })
+                .collect();
+        if closures.is_empty() {
+            continue;
+        }
+        lookup.insert(db, target.clone(), closures.iter().cloned());
+        if unify(db, &target, &ctx.goal) {
+            exprs.extend(closures);
+        }
+    }
+    exprs.into_iter()
+}
+
+/// Maximum amount of closures nested in each other built by the closure tactic
+const MAX_CLOSURE_DEPTH: usize = 2;
+
+/// Maximum amount of receiver types unwrapped with `unwrap_or_else` in a round
+const MAX_UNWRAP_TYPES: usize = 4;
+
+/// Unwrap the new reached `Option<T>` and `Result<T, E>` types with `unwrap_or_else` and closures
+/// returning fallback values of `T` (`o.unwrap_or_else(|| x)` or `r.unwrap_or_else(|_| x)`),
+/// returns the unwrapped values that unify with the goal
+///
+/// The bodies are found the same way as for the other closures, but as these closures are called
+/// right away they capture the locals. Fallbacks using the locals the receiver is built from are
+/// skipped, and so are the receivers built from variants (`Some(x).unwrap_or_else(..)` is just
+/// `x`). Every receiver type takes a nested search, so at most `MAX_UNWRAP_TYPES` of them are
+/// tried in a round and the rest are left for the next one.
+fn unwrap_or_else(
+    ctx: &TermSearchCtx<'_>,
+    lookup: &mut LookupTable,
+    should_continue: &dyn std::ops::Fn() -> bool,
+) -> Vec<Expr> {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+
+    let locals = |expr: &Expr| {
+        let mut res = Vec::new();
+        let mut stack = vec![expr];
+        while let Some(expr) = stack.pop() {
+            match expr {
+                Expr::Local(it) => res.push(*it),
+                _ => stack.extend(expr.children()),
+            }
+        }
+        res
+    };
+
+    let mut receiver_tys: Vec<Type> = lookup
+        .new_types(NewTypesKey::Closure)
+        .into_iter()
+        .filter(|ty| is_option_or_result(db, ty) && !ty.contains_unknown())
+        .unique()
+        .collect();
+    let deferred = receiver_tys.split_off(receiver_tys.len().min(MAX_UNWRAP_TYPES));
+    lookup.new_types.entry(NewTypesKey::Closure).or_default().extend(deferred);
+
+    let mut unwrapped: FxIndexMap<Type, Vec<Expr>> = FxIndexMap::default();
+    for ty in receiver_tys {
+        if !should_continue() {
+            break;
+        }
+        let Some(func) = ty.as_adt().and_then(|adt| {
+            Impl::all_for_type(db, adt.ty(db))
+                .into_iter()
+                .filter(|imp| imp.trait_(db).is_none())
+                .flat_map(|imp| imp.items(db))
+                .find_map(|it| match it {
+                    AssocItem::Function(f)
+                        if f.name(db).as_str() == Some("unwrap_or_else")
+                            && f.has_self_param(db) =>
+                    {
+                        Some(f)
+                    }
+                    _ => None,
+                })
+        }) else {
+            continue;
+        };
+        let receivers: Vec<Expr> = lookup
+            .find(db, &ty)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|it| !it.is_many() && !matches!(it, Expr::Variant { .. }))
+            .collect();
+        if receivers.is_empty() {
+            continue;
+        }
+        let mut args = ty.type_arguments();
+        let Some(value_ty) = args.next() else { continue };
+        // `Result` passes the error to the closure
+        let Some(params) = closure_params(ctx, args.collect()) else { continue };
+        // The type of the closure cannot be named, it is left to be inferred from the argument
+        let Some(closure_ty) = func
+            .params_without_self_with_args(db, ty.type_arguments())
+            .first()
+            .map(|it| it.ty().clone())
+        else {
+            continue;
+        };
+
+        let closures: Vec<Expr> =
+            super::closure_bodies(ctx, lookup, should_continue, &params, value_ty.clone(), true)
+                .into_iter()
+                .map(|body| Expr::Closure {
+                    params: params.clone(),
+                    body: Box::new(body),
+                    ty: closure_ty.clone(),
+                })
+                .collect();
+        let exprs = bounded_cartesian_product(vec![receivers, closures]).filter_map(|it| {
+            let [target, closure] = <[Expr; 2]>::try_from(it).ok()?;
+            let receiver_locals = locals(&target);
+            if locals(&closure).iter().any(|it| receiver_locals.contains(it)) {
+                return None;
+            }
+            Some(Expr::Method {
+                func,
+                generics: Vec::new(),
+                target: Box::new(target),
+                params: vec![closure],
+            })
+        });
+        unwrapped.entry(value_ty).or_default().extend(exprs);
+    }
+
+    let mut exprs = Vec::new();
+    for (ty, group) in unwrapped {
+        if group.is_empty() {
+            continue;
+        }
+        lookup.insert(db, ty.clone(), group.iter().cloned());
+        if unify(db, &ty, &ctx.goal) {
+            exprs.extend(group);
+        }
+    }
+    exprs
+}
+
+/// How closure is turned into the closure trait object goal
+#[derive(Clone, Copy)]
+enum ClosureWrap {
+    /// `&|x| ..`
+    Ref,
+    /// `&mut |x| ..`
+    MutRef,
+    /// `Box::new(|x| ..)` with the `Box::new` function
+    Box(Function),
+}
+
+/// Get the way to build the type from closure and the closure trait object it points to, if type
+/// is reference or `Box` of trait object with only closure traits (`Fn`, `FnMut` or `FnOnce`) and
+/// auto traits
+fn closure_object(db: &dyn HirDatabase, ty: &Type) -> Option<(ClosureWrap, Type)> {
+    let (wrap, object) = match ty.remove_ref() {
+        Some(object) if ty.is_mutable_reference() => (ClosureWrap::MutRef, object),
+        Some(object) => (ClosureWrap::Ref, object),
+        None if is_lang_adt(db, ty, LangItem::OwnedBox) => {
+            let boxed = ty.as_adt()?.ty(db);
+            let func = Impl::all_for_type(db, boxed)
+                .into_iter()
+                .filter(|imp| imp.trait_(db).is_none())
+                .flat_map(|imp| imp.items(db))
+                .find_map(|it| match it {
+                    AssocItem::Function(f)
+                        if f.name(db).as_str() == Some("new") && !f.has_self_param(db) =>
+                    {
+                        Some(f)
+                    }
+                    _ => None,
+                })?;
+            (ClosureWrap::Box(func), ty.type_arguments().next()?)
+        }
+        None => return None,
+    };
+    let traits = object.as_dyn_traits()?;
+    let is_closure_trait = |it: &Trait| {
+        matches!(
+            db.lang_attr(AttrDefId::TraitId(it.id)),
+            Some(LangItem::Fn | LangItem::FnMut | LangItem::FnOnce)
+        )
+    };
+    if !traits.iter().any(is_closure_trait)
+        || !traits.iter().all(|it| is_closure_trait(it) || it.is_auto(db))
+    {
+        return None;
+    }
+    Some((wrap, object))
+}
+
+/// Name the parameters of closure, `None` if there are not enough names that are not used in
+/// scope or by the parameters of the enclosing closures
+fn closure_params(ctx: &TermSearchCtx<'_>, tys: Vec<Type>) -> Option<Vec<(&'static str, Type)>> {
+    const NAMES: [&str; 6] = ["x", "y", "z", "a", "b", "c"];
+    let mut taken: Vec<&'static str> = ctx
+        .config
+        .extra_known_values
+        .iter()
+        .filter_map(|(_, it)| match it {
+            Expr::ClosureParam { name, .. } => Some(*name),
+            _ => None,
+        })
+        .collect();
+    ctx.scope.process_all_names(&mut |name, _| {
+        if let Some(it) = NAMES.iter().find(|it| name.as_str() == Some(**it)) {
+            taken.push(it);
+        }
+    });
+    let mut names = NAMES.into_iter().filter(|it| !taken.contains(it));
+    tys.into_iter().map(|ty| Some((names.next()?, ty))).collect()
+}
+
+/// Check if type is ADT defined at `path` in standard library, the path is relative to the
+/// crate root (`["vec", "Vec"]` for `alloc::vec::Vec`) so that it matches the reexports of the
+/// ADT in `std` as well
+fn is_std_item(db: &dyn HirDatabase, ty: &Type, path: &[&str]) -> bool {
+    match ty.as_adt().and_then(|it| def_path(db, ModuleDef::Adt(it))) {
+        Some((krate, segments)) => {
+            krate.origin(db).is_lang()
+                && segments.iter().map(|it| it.as_str()).eq(path.iter().map(|it| Some(*it)))
+        }
+        None => false,
+    }
+}
+
+/// Check if type is ADT marked with lang item `item`
+pub(super) fn is_lang_adt(db: &dyn HirDatabase, ty: &Type, item: LangItem) -> bool {
+    match ty.as_adt() {
+        Some(adt) => db.lang_attr(AttrDefId::AdtId(adt.into())) == Some(item),
+        None => false,
+    }
+}
+
+/// # Conversion tactic
+///
+/// Attempts to fill the goal by converting reached values with `Into::into`.
+///
+/// With `TermSearchConfig::enable_panicking` values without `Into` impl to the goal are also
+/// converted with `TryInto::try_into` and unwrapped. Infallible conversions are never unwrapped as
+/// they always have an `Into` impl that is preferred.
+///
+/// Only conversions to the goal are tried as there are too many `From` impls to try them all.
+/// With `TermSearchConfig::enable_conversion_chains` values can also be converted to the types the
+/// goal implements `From` for first, so that chains of two conversions such as
+/// `Into::<C>::into(Into::<B>::into(a))` are found. Values already converted are never converted
+/// to the intermediate types again to keep the chains bounded to length of two.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn conversion<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let impls = lookup.impls();
+    let unify = lookup.unify.clone();
+
+    let Some(into_trait) = ctx.lang_trait("convert", "Into") else {
+        return Vec::new().into_iter();
+    };
+    let Some(func) = into_trait.items(db).into_iter().find_map(|it| match it {
+        AssocItem::Function(f) if f.name(db).as_str() == Some("into") => Some(f),
+        _ => None,
+    }) else {
+        return Vec::new().into_iter();
+    };
+
+    // Types the goal can be converted from, found through `impl From<B> for Goal`
+    let intermediates: Vec<Type> = match ctx.config.enable_conversion_chains {
+        true => from_impl_sources(db, &ctx.goal),
+        false => Vec::new(),
+    };
+    let is_converted = |expr: &Expr| matches!(expr, Expr::Method { func: it, .. } if *it == func);
+
+    let new_types = lookup.new_types(NewTypesKey::Conversion);
+    let mut exprs = Vec::new();
+    for target in iter::once(&ctx.goal).chain(&intermediates) {
+        let converted: Vec<Expr> = new_types
+            .iter()
+            .filter(|_| should_continue())
+            .filter(|ty| {
+                !unify(db, ty, target) && impls.impls_trait(db, ty, into_trait, &[target.clone()])
+            })
+            .filter_map(|ty| lookup.find(db, ty))
+            .flatten()
+            .filter(|expr| !expr.is_many())
+            .filter(|expr| *target == ctx.goal || !is_converted(expr))
+            .map(|expr| Expr::Method {
+                func,
+                generics: vec![target.clone()],
+                target: Box::new(expr),
+                params: Vec::new(),
+            })
+            .collect();
+        if converted.is_empty() {
+            continue;
+        }
+        lookup.insert(db, target.clone(), converted.iter().cloned());
+        if unify(db, target, &ctx.goal) {
+            exprs.extend(converted);
+        }
+    }
+
+    // Fallible conversions are unwrapped, infallible ones are already covered by `Into` as
+    // `TryFrom` is implemented for all `Into` impls with `Infallible` error.
+    if let Some(((try_into, error, try_into_fn), unwrap_fn)) =
+        ctx.config.enable_panicking.then(|| fallible_conversion(ctx)).flatten()
+    {
+        let unwrapped: Vec<Expr> = new_types
+            .iter()
+            .filter(|_| should_continue())
+            .filter(|ty| {
+                !unify(db, ty, &ctx.goal)
+                    && !impls.impls_trait(db, ty, into_trait, &[ctx.goal.clone()])
+            })
+            .filter(|ty| {
+                impls.impls_trait(db, ty, try_into, &[ctx.goal.clone()])
+                    && ty
+                        .normalize_trait_assoc_type(db, &[ctx.goal.clone()], error)
+                        .map_or(false, |it| !it.contains_unknown() && !is_infallible(db, &it))
+            })
+            .filter_map(|ty| lookup.find(db, ty))
+            .flatten()
+            .filter(|expr| !expr.is_many())
+            .map(|expr| Expr::Method {
+                func: unwrap_fn,
+                generics: Vec::new(),
+                target: Box::new(Expr::Method {
+                    func: try_into_fn,
+                    generics: vec![ctx.goal.clone()],
+                    target: Box::new(expr),
+                    params: Vec::new(),
+                }),
+                params: Vec::new(),
+            })
+            .collect();
+        if !unwrapped.is_empty() {
+            lookup.insert(db, ctx.goal.clone(), unwrapped.iter().cloned());
+            exprs.extend(unwrapped);
+        }
+    }
+    exprs.into_iter()
+}
+
+/// Get `TryInto` trait with its `Error` type and `try_into` method as well as `Result::unwrap`
+/// for unwrapping the result of the conversion
+fn fallible_conversion(
+    ctx: &TermSearchCtx<'_>,
+) -> Option<((Trait, TypeAlias, Function), Function)> {
+    let db = ctx.sema.db();
+    let trait_ = ctx.lang_trait("convert", "TryInto")?;
+    let items = trait_.items(db);
+    let error = items.iter().find_map(|it| match it {
+        AssocItem::TypeAlias(it) if it.name(db).as_str() == Some("Error") => Some(*it),
+        _ => None,
+    })?;
+    let try_into = items.iter().find_map(|it| match it {
+        AssocItem::Function(it) if it.name(db).as_str() == Some("try_into") => Some(*it),
+        _ => None,
+    })?;
+    let result_ty =
+        try_into.ret_type_with_args(db, [ctx.goal.clone(), ctx.goal.clone()].into_iter());
+    Impl::all_for_type(db, result_ty)
+        .into_iter()
+        .filter(|imp| imp.trait_(db).is_none())
+        .flat_map(|imp| imp.items(db))
+        .find_map(|it| match it {
+            AssocItem::Function(f)
+                if f.name(db).as_str() == Some("unwrap") && f.has_self_param(db) =>
+            {
+                Some(f)
+            }
+            _ => None,
+        })
+        .map(|unwrap| ((trait_, error, try_into), unwrap))
+}
+
+/// Check if type is `Infallible` from standard library
+fn is_infallible(db: &dyn HirDatabase, ty: &Type) -> bool {
+    is_std_item(db, ty, &["convert", "Infallible"])
+}
+
+/// Get the types `B` of `impl From<B> for T` impls of the type
+fn from_impl_sources(db: &dyn HirDatabase, ty: &Type) -> Vec<Type> {
+    Impl::all_for_type(db, ty.clone())
+        .into_iter()
+        .filter_map(|imp| imp.trait_ref(db))
+        .filter(|it| {
+            let name = it.trait_().name(db);
+            name.as_str() == Some("From")
+                && def_path(db, ModuleDef::Trait(it.trait_()))
+                    .map_or(false, |(krate, _)| krate.origin(db).is_lang())
+        })
+        .filter_map(|it| it.get_type_argument(1))
+        .filter(|it| it != ty && !it.contains_unknown() && it.as_type_param(db).is_none())
+        .unique()
+        .collect()
+}
+
+/// # First element tactic
+///
+/// Attempts to fill `Option` goals with the first element of reached collections, either
+/// borrowed (`v.iter().next()` for `Option<&T>`) or owned (`v.into_iter().next()` for
+/// `Option<T>`).
+///
+/// `iter` has to be an inherent method taking only `&self` and returning an `Iterator`, only
+/// types that are not iterators themselves are converted with `IntoIterator`. The item type of
+/// the iterator has to match the goal.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn first_element<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+    let impls = lookup.impls();
+
+    if !is_option(db, &ctx.goal) {
+        return Vec::new().into_iter();
+    }
+    let Some(iterator) = ctx.lang_trait("iterator", "Iterator") else {
+        return Vec::new().into_iter();
+    };
+    let trait_fn = |trait_: Trait, name: &str| {
+        trait_.items(db).into_iter().find_map(|it| match it {
+            AssocItem::Function(f) if f.name(db).as_str() == Some(name) => Some(f),
+            _ => None,
+        })
+    };
+    let Some(next) = trait_fn(iterator, "next") else {
+        return Vec::new().into_iter();
+    };
+    let into_iter = ctx
+        .lang_trait("collect", "IntoIterator")
+        .and_then(|it| Some((it, trait_fn(it, "into_iter")?)));
+
+    let iterators: Vec<Expr> = lookup
+        .new_types(NewTypesKey::FirstElement)
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter(|ty| !ty.contains_unknown() && !impls.impls_trait(db, ty, iterator, &[]))
+        .filter_map(|ty| {
+            let targets: Vec<Expr> =
+                lookup.find(db, &ty)?.into_iter().filter(|it| !it.is_many()).collect();
+            Some(iterate_collection(ctx, &impls, into_iter, &ty, &targets))
+        })
+        .flatten()
+        .filter(|it| {
+            let ty = it.ty(db);
+            !ty.contains_unknown() && impls.impls_trait(db, &ty, iterator, &[])
+        })
+        .collect();
+
+    // `next` returns `Option` of the item type, so this also checks the item type
+    let exprs: Vec<Expr> = iterators
+        .into_iter()
+        .map(|it| Expr::Method {
+            func: next,
+            generics: Vec::new(),
+            target: Box::new(it),
+            params: Vec::new(),
+        })
+        .filter(|it| unify(db, &it.ty(db), &ctx.goal))
+        .collect();
+
+    if !exprs.is_empty() {
+        lookup.insert(db, ctx.goal.clone(), exprs.iter().cloned());
+    }
+    exprs.into_iter()
+}
+
+/// Iterate the collection `ty` (that is not an iterator itself) given by `targets`, either
+/// borrowing it with inherent `iter(&self)` (`v.iter()`) or consuming it with `IntoIterator`
+/// (`v.into_iter()`)
+///
+/// The calls are not checked to return iterators, `iter` methods of slices called on arrays do not
+/// even have known type.
+fn iterate_collection(
+    ctx: &TermSearchCtx<'_>,
+    impls: &ImplsCache,
+    into_iter: Option<(Trait, Function)>,
+    ty: &Type,
+    targets: &[Expr],
+) -> Vec<Expr> {
+    let db = ctx.sema.db();
+    let module = ctx.scope.module();
+    let method = |func, target: &Expr| Expr::Method {
+        func,
+        generics: Vec::new(),
+        target: Box::new(target.clone()),
+        params: Vec::new(),
+    };
+
+    let iter_name = Name::new_text_dont_use("iter".into());
+    let iter_fn = ty.iterate_method_candidates(db, ctx.scope, None, Some(&iter_name), |f| {
+        let is_inherent = f.as_assoc_item(db)?.implemented_trait(db).is_none();
+        let takes_ref = f.self_param(db)?.access(db) == crate::Access::Shared;
+        (is_inherent
+            && takes_ref
+            && f.params_without_self(db).is_empty()
+            && f.is_visible_from(db, module))
+        .then_some(f)
+    });
+    let into_iter_fn = into_iter
+        .filter(|(trait_, _)| impls.impls_trait(db, ty, *trait_, &[]))
+        .map(|(_, func)| func);
+    iter_fn
+        .into_iter()
+        .chain(into_iter_fn)
+        .flat_map(|func| targets.iter().map(move |target| method(func, target)))
+        .collect()
+}
+
+/// # Iterator adapter tactic
+///
+/// Attempts to reach new iterators by chaining `map`, `filter` and `cloned` adapters to the
+/// reached iterators and collections (`v.iter().cloned()` or `v.into_iter().map(parse)`), for
+/// example to collect them into the goal afterwards.
+///
+/// Functions are only mapped over and filtered with by name, so they have to be free functions
+/// in scope taking the item of the iterator (reference to it for `filter`) as the only argument.
+/// At most [`MAX_ADAPTERS`] adapters are chained, the chains get longer only with every
+/// new iterator type reached.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn iterator_adapter<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let unify = lookup.unify.clone();
+    let impls = lookup.impls();
+
+    let Some(iterator) = ctx.lang_trait("iterator", "Iterator") else {
+        return Vec::new().into_iter();
+    };
+    let Some(item) = iterator.items(db).into_iter().find_map(|it| match it {
+        AssocItem::TypeAlias(it) if it.name(db).as_str() == Some("Item") => Some(it),
+        _ => None,
+    }) else {
+        return Vec::new().into_iter();
+    };
+    let trait_fn = |trait_: Trait, name: &str| {
+        trait_.items(db).into_iter().find_map(|it| match it {
+            AssocItem::Function(f) if f.name(db).as_str() == Some(name) => Some(f),
+            _ => None,
+        })
+    };
+    let map = trait_fn(iterator, "map");
+    let filter = trait_fn(iterator, "filter");
+    let cloned = trait_fn(iterator, "cloned").zip(ctx.lang_trait("clone", "Clone"));
+    let into_iter = ctx
+        .lang_trait("collect", "IntoIterator")
+        .and_then(|it| Some((it, trait_fn(it, "into_iter")?)));
+    let adapters: Vec<Function> =
+        map.into_iter().chain(filter).chain(cloned.map(|(it, _)| it)).collect();
+    let chained = |mut expr: &Expr| {
+        let mut res = 0;
+        while let Expr::Method { func, target, .. } = expr {
+            res += usize::from(adapters.contains(func));
+            expr = target;
+        }
+        res
+    };
+
+    // Free functions in scope taking single argument with their argument and return types
+    let fns: Vec<(Function, Type, Type)> = defs
+        .iter()
+        .filter_map(|def| match def {
+            ScopeDef::ModuleDef(ModuleDef::Function(it)) => Some(*it),
+            _ => None,
+        })
+        .filter(|it| {
+            it.as_assoc_item(db).is_none()
+                && !it.is_unsafe_to_call(db)
+                && !it.is_async(db)
+                && GenericDef::from(*it).type_or_const_params(db).is_empty()
+        })
+        .filter_map(|it| {
+            let [param] = it.params_without_self(db).try_into().ok()?;
+            Some((it, param.ty().clone(), it.ret_type(db)))
+        })
+        .filter(|(_, param, ret)| !param.contains_unknown() && !ret.contains_unknown())
+        .collect();
+
+    let iterators: Vec<Expr> = lookup
+        .new_types(NewTypesKey::IteratorAdapter)
+        .into_iter()
+        .filter(|_| should_continue())
+        .filter(|ty| !ty.contains_unknown())
+        .filter_map(|ty| {
+            let targets: Vec<Expr> = lookup
+                .find(db, &ty)?
+                .into_iter()
+                .filter(|it| !it.is_many() && chained(it) < MAX_ADAPTERS)
+                .collect();
+            match impls.impls_trait(db, &ty, iterator, &[]) {
+                true => Some(targets),
+                false => Some(iterate_collection(ctx, &impls, into_iter, &ty, &targets)),
+            }
+        })
+        .flatten()
+        .filter(|it| {
+            let ty = it.ty(db);
+            !ty.contains_unknown() && impls.impls_trait(db, &ty, iterator, &[])
+        })
+        .collect();
+
+    let mut adapted = Vec::new();
+    for target in iterators {
+        let Some(item_ty) = target.ty(db).normalize_trait_assoc_type(db, &[], item) else {
+            continue;
+        };
+        let mut adapt = |func, generics: Vec<Type>, params: Vec<Expr>| {
+            let args = expr::method_args(db, func, &target, &generics);
+            if func.predicates_hold_with_args(db, args.into_iter()) {
+                adapted.push(Expr::Method {
+                    func,
+                    generics,
+                    target: Box::new(target.clone()),
+                    params,
+                });
+            }
+        };
+        if let Some(map) = map {
+            for (func, param, ret) in &fns {
+                if unify(db, param, &item_ty) {
+                    adapt(map, vec![ret.clone(), func.ty(db)], vec![Expr::FnItem(*func)]);
+                }
+            }
+        }
+        if let Some(filter) = filter {
+            for (func, param, ret) in &fns {
+                let takes_item = !param.is_mutable_reference()
+                    && param.remove_ref().map_or(false, |it| unify(db, &it, &item_ty));
+                // Filtering again with the same predicate changes nothing
+                let filtered = iter::successors(Some(&target), |it| match it {
+                    Expr::Method { target, .. } => Some(target),
+                    _ => None,
+                })
+                .any(|it| matches!(it, Expr::Method { params, .. } if params == &[Expr::FnItem(*func)]));
+                if takes_item && !filtered && ret.is_bool() {
+                    adapt(filter, vec![func.ty(db)], vec![Expr::FnItem(*func)]);
+                }
+            }
+        }
+        if let Some((cloned, clone)) = cloned {
+            if let Some(pointee) = item_ty.remove_ref().filter(|_| !item_ty.is_mutable_reference())
+            {
+                if impls.impls_trait(db, &pointee, clone, &[]) {
+                    adapt(cloned, vec![pointee], Vec::new());
+                }
+            }
+        }
+    }
+
+    let mut groups: FxIndexMap<Type, Vec<Expr>> = FxIndexMap::default();
+    for it in adapted {
+        groups.entry(it.ty(db)).or_default().push(it);
+    }
+    let mut exprs = Vec::new();
+    for (ty, group) in groups {
+        if ty.contains_unknown() {
+            continue;
+        }
+        lookup.insert(db, ty.clone(), group.iter().cloned());
+        if unify(db, &ty, &ctx.goal) {
+            exprs.extend(group);
+        }
+    }
+    exprs.into_iter()
+}
+
+/// Maximum amount of adapters chained by the iterator adapter tactic
+const MAX_ADAPTERS: usize = 2;
+
+/// # Index tactic
+///
+/// Attempts to fill the goal by indexing reached containers with reached values (`v[i]`).
+/// The container has to implement `Index<Idx>` for the type of the index, the `Output` type of
+/// the impl is the type reached. Outputs that are not `Copy` cannot be moved out of the container,
+/// so they are only borrowed (`&v[i]`) to reach `&Output` instead.
+///
+/// Only enabled with `TermSearchConfig::enable_panicking` as indexing panics for indices out of
+/// bounds. Containers are indexed with the types reached so far once they are first reached.
+///
+/// Updates lookup by new types reached and returns iterator that yields
+/// elements that unify with `goal`.
+///
+/// # Arguments
+/// * `ctx` - Context for the term search
+/// * `defs` - Set of items in scope at term search target location
+/// * `lookup` - Lookup table for types
+/// * `should_continue` - Function that indicates when to stop iterating
+pub(super) fn index<'a>(
+    ctx: &'a TermSearchCtx<'a>,
+    _defs: &'a FxIndexSet<ScopeDef>,
+    lookup: &'a mut LookupTable,
+    should_continue: &'a dyn std::ops::Fn() -> bool,
+) -> impl Iterator<Item = Expr> + 'a {
+    let db = ctx.sema.db();
+    let impls = lookup.impls();
+    let unify = lookup.unify.clone();
+
+    if !ctx.config.enable_panicking {
+        return Vec::new().into_iter();
+    }
+    let index_trait = db
+        .lang_item(ctx.scope.krate().into(), LangItem::Index)
+        .and_then(|it| it.as_trait())
+        .map(Trait::from);
+    let Some(index_trait) = index_trait else {
+        return Vec::new().into_iter();
+    };
+    let Some(output) = index_trait.items(db).into_iter().find_map(|it| match it {
+        AssocItem::TypeAlias(it) if it.name(db).as_str() == Some("Output") => Some(it),
+        _ => None,
+    }) else {
+        return Vec::new().into_iter();
+    };
+
+    let is_value = |ty: &Type| !ty.contains_unknown() && !ty.is_reference();
+    let indices: Vec<Type> = lookup.iter_types().filter(is_value).collect();
+    let indexed: Vec<(Type, Vec<Expr>)> = lookup
+        .new_types(NewTypesKey::Index)
+        .into_iter()
+        .filter(is_value)
+        .cartesian_product(indices)
+        .filter(|_| should_continue())
+        .filter(|(container, idx)| {
+            impls.impls_trait(db, container, index_trait, std::slice::from_ref(idx))
+        })
+        .filter_map(|(container, idx)| {
+            let out = container
+                .normalize_trait_assoc_type(db, std::slice::from_ref(&idx), output)
+                .filter(|it| !it.contains_unknown())?;
+            let containers = lookup.find(db, &container)?;
+            let idx_exprs = lookup.find(db, &idx)?;
+            let is_copy = out.is_copy(db);
+            let ty = match is_copy {
+                true => out.clone(),
+                false => Type::reference(&out, Mutability::Shared),
+            };
+            let exprs: Vec<Expr> = containers
+                .into_iter()
+                .filter(|it| !it.is_many())
+                .cartesian_product(idx_exprs)
+                .map(|(expr, index)| {
+                    let expr = Expr::Index {
+                        expr: Box::new(expr),
+                        index: Box::new(index),
+                        ty: out.clone(),
+                    };
+                    match is_copy {
+                        true => expr,
+                        false => Expr::Reference(Box::new(expr)),
+                    }
+                })
+                .collect();
+            (!exprs.is_empty()).then_some((ty, exprs))
+        })
+        .collect();
+
+    for (ty, exprs) in &indexed {
+        lookup.insert(db, ty.clone(), exprs.iter().cloned());
+    }
+
+    indexed
+        .into_iter()
+        .filter_map(move |(ty, exprs)| unify(db, &ty, &ctx.goal).then_some(exprs))
         .flatten()
-        .filter_map(|expr| expr.ty(db).could_unify_with_deeply(db, &ctx.goal).then_some(expr))
+        .collect::<Vec<_>>()
+        .into_iter()
 }
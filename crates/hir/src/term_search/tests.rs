@@ -0,0 +1,1093 @@
+//! Tests driving the term search API directly: statistics, internals and the exact solutions and
+//! their order, which the assist tests only see one suggestion at a time.
+//!
+//! Counters depend on the tactics and on the contents of minicore, so the tests compare them with
+//! each other instead of checking exact values.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use itertools::Itertools;
+use syntax::{algo::find_node_at_offset, ast, AstNode};
+use test_fixture::WithFixture;
+use test_utils::{bench, skip_slow_tests, MiniCore};
+
+use crate::{test_db::TestDB, Crate, HirDisplay, Semantics};
+
+use super::*;
+
+/// Runs `f` with the term search context for the `todo!()` marked with `$0`
+fn with_ctx<R>(
+    ra_fixture: &str,
+    config: TermSearchConfig,
+    f: impl FnOnce(&TermSearchCtx<'_>) -> R,
+) -> R {
+    let (db, position) = TestDB::with_position(ra_fixture);
+    let sema = Semantics::new(&db);
+    let file = sema.parse(position.file_id);
+    let call: ast::MacroCall = find_node_at_offset(file.syntax(), position.offset).unwrap();
+    let parent = call.syntax().parent().unwrap();
+    let scope = sema.scope(&parent).unwrap();
+    let goal = sema.type_of_expr(&ast::Expr::cast(parent).unwrap()).unwrap().adjusted();
+    f(&TermSearchCtx { sema: &sema, scope: &scope, goal, config })
+}
+
+fn stats(ra_fixture: &str, config: TermSearchConfig) -> TermSearchStats {
+    with_ctx(ra_fixture, config, |ctx| term_search_with_stats(ctx).1)
+}
+
+/// Source code of the expression the way the term search assist renders it, holes as `todo!()`
+fn render(ctx: &TermSearchCtx<'_>, expr: &Expr) -> String {
+    expr.gen_source_code(ctx.scope, &mut |_| "todo!()".to_owned(), false, true).unwrap()
+}
+
+/// Solutions found with `config`, in the order they are suggested in
+fn solutions(ra_fixture: &str, config: TermSearchConfig) -> Vec<String> {
+    with_ctx(ra_fixture, config, |ctx| term_search(ctx).iter().map(|it| render(ctx, it)).collect())
+}
+
+/// Definition named `name` in scope of the `todo!()`
+fn scope_def(ctx: &TermSearchCtx<'_>, name: &str) -> ScopeDef {
+    let mut res = None;
+    ctx.scope.process_all_names(&mut |it, def| {
+        if it.as_str() == Some(name) {
+            res = Some(def);
+        }
+    });
+    res.unwrap()
+}
+
+#[test]
+fn saturated_search_stops_early() {
+    let saturated = stats(
+        r#"//- minicore: todo, unimplemented
+struct S(u32);
+fn f() { let s: S = todo$0!(); }"#,
+        TermSearchConfig::default(),
+    );
+    // `wrap` keeps producing new types that never get us closer to `G`
+    let growing = stats(
+        r#"//- minicore: todo, unimplemented
+struct A;
+struct W<T>(T);
+fn wrap<T>(t: T) -> W<T> { W(t) }
+struct G(u8);
+fn f(a: A) { let g: G = todo$0!(); }"#,
+        TermSearchConfig::default(),
+    );
+    assert!(saturated.rounds_run < growing.rounds_run);
+}
+
+#[test]
+fn idle_types_get_exhausted() {
+    let fixture = r#"//- minicore: todo, unimplemented
+struct A;
+struct W<T>(T);
+fn wrap<T>(t: T) -> W<T> { W(t) }
+struct G(u8);
+fn f(a: A) { let g: G = todo$0!(); }"#;
+    let unlimited = stats(fixture, TermSearchConfig::default());
+    let limited =
+        stats(fixture, TermSearchConfig { max_idle_type_rounds: Some(2), ..Default::default() });
+    assert_eq!(unlimited.types_exhausted, 0);
+    assert!(limited.types_exhausted > 0);
+    assert!(limited.rounds_run <= unlimited.rounds_run);
+    assert!(limited.types_reached <= unlimited.types_reached);
+}
+
+#[test]
+fn max_depth_covers_solutions() {
+    let (solutions, stats) = with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct A(u32);
+struct B(A);
+fn f(a: u32) { let b: B = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        term_search_with_stats,
+    );
+    let deepest = solutions.iter().map(Expr::depth).max().unwrap();
+    assert!(deepest > 1);
+    assert!(stats.max_depth >= deepest);
+}
+
+#[test]
+fn trait_solver_calls_cached() {
+    let stats = stats(
+        r#"//- minicore: todo, unimplemented
+//- /main.rs crate:main deps:alloc
+use alloc::string::{String, ToString};
+struct W(i32);
+fn f(n: i32, w: W) { let a: String = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod fmt {
+    pub trait Display {}
+    impl Display for i32 {}
+}
+pub mod string {
+    pub struct String(u8);
+    pub trait ToString { fn to_string(&self) -> String; }
+    impl<T: crate::fmt::Display + ?Sized> ToString for T { fn to_string(&self) -> String { String(0) } }
+}
+"#,
+        TermSearchConfig::default(),
+    );
+    // Without the cache every query would run the trait solver
+    assert!(stats.trait_solver_calls > 0);
+    assert!(stats.trait_cache_hits > 0);
+    assert_eq!(stats.trait_solver_calls + stats.trait_cache_hits, stats.trait_queries);
+}
+
+#[test]
+fn soft_deadline_keeps_trivial_solutions() {
+    let (solutions, stats) = with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct A(u32);
+fn make(n: u32) -> A { A(n) }
+fn f(a: A, n: u32) { let b: A = todo$0!(); }"#,
+        TermSearchConfig { soft_deadline: Some(Duration::ZERO), ..Default::default() },
+        term_search_with_stats,
+    );
+    assert!(stats.timed_out);
+    assert_eq!(stats.rounds_run, 0);
+    assert!(!solutions.is_empty());
+    assert!(solutions.iter().all(|it| matches!(it, Expr::Local(_))));
+}
+
+#[test]
+fn likely_needs_iteration() {
+    let needs_iteration = |ra_fixture| stats(ra_fixture, TermSearchConfig::default());
+    assert!(
+        needs_iteration(
+            r#"//- minicore: todo, unimplemented, iterator
+struct Item { price: u32 }
+fn f(items: [Item; 3]) { let total: u32 = todo$0!(); }"#
+        )
+        .likely_needs_iteration
+    );
+    assert!(
+        !needs_iteration(
+            r#"//- minicore: todo, unimplemented, iterator
+struct Item { price: u32 }
+fn f(items: [Item; 3], base: u32) { let total: u32 = todo$0!(); }"#
+        )
+        .likely_needs_iteration
+    );
+}
+
+#[test]
+fn many_argument_combinations_bounded() {
+    let solutions = with_ctx(
+        r#"//- minicore: todo, unimplemented
+fn g(a: u8, b: u8, c: u8, d: u8, e: u8) -> u32 { 0 }
+fn f(a: u8, b: u8, c: u8, d: u8, e: u8) { let n: u32 = todo$0!(); }"#,
+        TermSearchConfig { many_alternatives_threshold: 10, ..Default::default() },
+        term_search,
+    );
+    // Every argument could be any of the five locals
+    assert!(solutions.len() > 1);
+    assert!(solutions.len() <= tactics::MAX_ARG_COMBINATIONS);
+}
+
+#[test]
+fn constructor_field_combinations_bounded() {
+    let fixture = |ty: &str| {
+        format!(
+            r#"//- minicore: todo, unimplemented
+struct S(u8, u8, u8, u8, u8);
+enum E {{ V(u8, u8, u8, u8, u8) }}
+fn f(a: u8, b: u8, c: u8, d: u8, e: u8) {{ let x: {ty} = todo$0!(); }}"#
+        )
+    };
+    for ty in ["S", "E"] {
+        let solutions = with_ctx(
+            &fixture(ty),
+            TermSearchConfig { many_alternatives_threshold: 10, ..Default::default() },
+            term_search,
+        );
+        // Every field could be any of the five locals
+        assert!(solutions.len() > 1);
+        assert!(solutions.len() <= tactics::MAX_ARG_COMBINATIONS);
+    }
+}
+
+#[test]
+fn argument_combinations_overflowing_usize_bounded() {
+    let combinations = with_ctx(
+        r#"//- minicore: todo, unimplemented
+fn f() { let n: u32 = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            // 2^65 combinations, more than fits `usize`
+            let args = vec![vec![Expr::Many(ctx.goal.clone()); 2]; usize::BITS as usize + 1];
+            tactics::bounded_cartesian_product(args).count()
+        },
+    );
+    assert!(combinations > 1);
+    assert!(combinations <= tactics::MAX_ARG_COMBINATIONS);
+}
+
+#[test]
+fn impl_trait_argument_combinations_bounded() {
+    let solutions = with_ctx(
+        r#"//- minicore: todo, unimplemented
+trait Display {}
+struct A(u8);
+struct B(u8);
+struct C(u8);
+struct D(u8);
+struct E(u8);
+struct F(u8);
+impl Display for A {}
+impl Display for B {}
+impl Display for C {}
+impl Display for D {}
+impl Display for E {}
+impl Display for F {}
+struct S(u8);
+fn show(a: impl Display, b: impl Display, c: impl Display, d: impl Display) -> S { S(0) }
+fn f(a: A, b: B, c: C, d: D, e: E, f: F) { let s: S = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        term_search,
+    );
+    // Every `impl Display` could be any of the six types, 6^4 combinations of them
+    let calls = solutions.iter().filter(|it| matches!(it, Expr::Function { .. })).count();
+    assert!(calls > 1);
+    assert!(calls <= tactics::MAX_ARG_COMBINATIONS);
+}
+
+#[test]
+fn bounded_cartesian_product_of_no_arguments() {
+    let combinations = tactics::bounded_cartesian_product(Vec::<Vec<u8>>::new()).collect_vec();
+    assert_eq!(combinations, vec![Vec::<u8>::new()]);
+}
+
+#[test]
+fn closure_bodies_use_budget_of_parent() {
+    with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct A;
+fn make(a: A) -> u32 { 0 }
+fn f(a: A) { let n: u32 = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            let db = ctx.sema.db();
+            let mut a = None;
+            ctx.scope.process_all_names(&mut |_, def| {
+                if let ScopeDef::Local(it) = def {
+                    a = Some(it.ty(db));
+                }
+            });
+            let params = [("x", a.unwrap())];
+            let parent = LookupTable::new(&ctx.config, ctx.goal.clone(), could_unify_deeply());
+            // `make(x)` is only found in the rounds after the first one
+            let bodies = |parent: &LookupTable, should_continue: &dyn Fn() -> bool| {
+                closure_bodies(ctx, parent, should_continue, &params, ctx.goal.clone(), false)
+                    .into_iter()
+                    .filter(|it| matches!(it, Expr::Function { .. }))
+                    .count()
+            };
+
+            let charged = Cell::new(0);
+            let found = bodies(&parent, &|| {
+                charged.set(charged.get() + 1);
+                true
+            });
+            assert_eq!(found, 1);
+            assert!(charged.get() > 0);
+
+            assert_eq!(bodies(&parent, &|| false), 0);
+
+            let mut timed_out =
+                LookupTable::new(&ctx.config, ctx.goal.clone(), could_unify_deeply());
+            timed_out.deadline = Some(Instant::now());
+            assert_eq!(bodies(&timed_out, &|| true), 0);
+        },
+    );
+}
+
+#[test]
+fn iter_skips_unreachable_goal() {
+    let fixture = r#"//- minicore: todo, unimplemented
+struct Cell(u32);
+fn wrap(n: u32) -> Cell { Cell(n) }
+fn f(c: Cell, n: u32) { let a: Cell = todo$0!(); }"#;
+    with_ctx(fixture, TermSearchConfig::default(), |ctx| {
+        let config =
+            TermSearchConfig { forbidden_types: vec![ctx.goal.clone()], ..ctx.config.clone() };
+        let ctx = TermSearchCtx { goal: ctx.goal.clone(), config, ..*ctx };
+        let (solutions, stats) = term_search_with_stats(&ctx);
+        assert!(solutions.is_empty());
+        assert_eq!(stats.rounds_run, 0);
+        // Same goal precheck as the search collecting all the solutions
+        let mut iter = term_search_iter(&ctx);
+        assert_eq!(iter.by_ref().count(), 0);
+        assert_eq!(iter.search.stats.rounds_run, 0);
+    });
+}
+
+#[test]
+fn stable_keys_of_same_named_trait_methods() {
+    let (solutions, keys) = with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct S(u8);
+trait A { fn foo(&self) -> u8; }
+trait B { fn foo(&self) -> u8; }
+impl A for S { fn foo(&self) -> u8 { 0 } }
+impl B for S { fn foo(&self) -> u8 { 1 } }
+fn f(s: S) { let a: u8 = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            let solutions = term_search(ctx);
+            let keys = solutions.iter().map(|it| it.stable_key(ctx.sema.db())).unique().count();
+            (solutions.len(), keys)
+        },
+    );
+    assert!(solutions > 1);
+    assert_eq!(solutions, keys);
+}
+
+#[cfg(feature = "term-search-dump")]
+#[test]
+fn round_dump_includes_reached_type() {
+    let stats = stats(
+        r#"//- minicore: todo, unimplemented
+struct A(u32);
+fn make(n: u32) -> A { A(n) }
+fn f(n: u32) { let b: A = todo$0!(); }"#,
+        TermSearchConfig::default(),
+    );
+    assert_eq!(stats.round_dumps.len(), stats.rounds_run);
+    assert!(stats.round_dumps[0].lines().any(|it| it.trim().starts_with("A:")));
+}
+
+#[test]
+fn unify_predicate_keeps_state() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let unify: UnifyFn = Arc::new({
+        let calls = calls.clone();
+        move |_, ty: &Type, other: &Type| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            ty == other
+        }
+    });
+    let solutions = with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct S(u32);
+fn f(a: S) { let s: S = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| term_search_with_unify(ctx, unify),
+    );
+    assert!(solutions.iter().any(|it| matches!(it, Expr::Local(_))));
+    assert!(calls.load(Ordering::Relaxed) > 0);
+}
+
+#[test]
+fn unify_predicate_not_reflexive() {
+    let fixture = r#"//- minicore: todo, unimplemented
+struct S { n: u32 }
+impl S { fn get(&self) -> u32 { self.n } }
+struct T(u32);
+fn f(s: S) { let t: T = todo$0!(); }"#;
+    // Reached types are looked up with the predicate too, so the tactics must not rely on them
+    // matching themselves
+    let solutions = with_ctx(fixture, TermSearchConfig::default(), |ctx| {
+        term_search_with_unify(ctx, Arc::new(|_, _, _| false))
+    });
+    assert!(solutions.is_empty());
+    let rejects_s: UnifyFn = Arc::new(|db, ty: &Type, other: &Type| {
+        let is_s = |it: &Type| it.as_adt().and_then(|it| it.name(db).as_str().map(|it| it == "S"));
+        is_s(ty) != Some(true) && ty.could_unify_with_deeply(db, other)
+    });
+    let solutions = with_ctx(fixture, TermSearchConfig::default(), |ctx| {
+        term_search_with_unify(ctx, rejects_s)
+    });
+    assert!(solutions.is_empty());
+}
+
+#[test]
+fn merged_lookup_tables() {
+    with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct A;
+struct B;
+fn f(a1: A, a2: A, a3: A, b: B) { let g: u8 = todo$0!(); }"#,
+        TermSearchConfig { many_alternatives_threshold: 1, ..Default::default() },
+        |ctx| {
+            let db = ctx.sema.db();
+            let mut locals = Vec::new();
+            ctx.scope.process_all_names(&mut |_, def| {
+                if let ScopeDef::Local(it) = def {
+                    locals.push(it);
+                }
+            });
+            let local = |name: &str| {
+                let it = *locals.iter().find(|it| it.name(db).as_str() == Some(name)).unwrap();
+                (it.ty(db), Expr::Local(it))
+            };
+            let table = |name: &str| {
+                let mut table =
+                    LookupTable::new(&ctx.config, ctx.goal.clone(), could_unify_deeply());
+                let (ty, expr) = local(name);
+                table.insert(db, ty, iter::once(expr));
+                table.new_types(NewTypesKey::ImplMethod);
+                table
+            };
+            let (a, _) = local("a1");
+            let (b, _) = local("b");
+            let few_len = |table: &LookupTable, ty: &Type| match &table.data[ty] {
+                AlternativeExprs::Few(it) => Some(it.len()),
+                AlternativeExprs::Many => None,
+            };
+
+            // Overlapping tables keep the trees up to the threshold and reach nothing new, the
+            // table squashes the trees once it has more than the threshold of them
+            let mut merged = table("a1");
+            merged.merge(db, table("a2"));
+            assert_eq!(few_len(&merged, &a), Some(2));
+            assert!(merged.new_types(NewTypesKey::ImplMethod).is_empty());
+            merged.merge(db, table("a3"));
+            assert_eq!(few_len(&merged, &a), None);
+
+            // Disjoint tables reach the types of each other
+            let mut merged = table("a1");
+            merged.merge(db, table("b"));
+            assert_eq!(few_len(&merged, &a), Some(1));
+            assert_eq!(few_len(&merged, &b), Some(1));
+            assert_eq!(merged.new_types(NewTypesKey::ImplMethod), vec![b.clone()]);
+
+            // Squashed trees stay squashed and are new if the type was not reached before
+            let mut many = table("a1");
+            many.merge(db, table("a2"));
+            many.merge(db, table("a3"));
+            let mut merged = table("b");
+            merged.merge(db, many);
+            assert_eq!(few_len(&merged, &a), None);
+            assert_eq!(merged.new_types(NewTypesKey::ImplMethod), vec![a]);
+        },
+    );
+}
+
+impl TermSearchSema for TestDB {
+    fn db(&self) -> &dyn HirDatabase {
+        self
+    }
+}
+
+#[test]
+fn term_search_without_semantics() {
+    let (db, _) = TestDB::with_single_file(
+        r#"
+struct S(u8);
+const DEFAULT: S = S(0);
+fn make() -> S { S(1) }
+"#,
+    );
+    let module = Crate::all(&db)[0].root_module();
+    let goal = module
+        .declarations(&db)
+        .into_iter()
+        .find_map(|it| match it {
+            ModuleDef::Adt(it) => Some(it.ty(&db)),
+            _ => None,
+        })
+        .unwrap();
+    // Scope of the module needs no syntax, so the database is all term search needs
+    let scope = SemanticsScope::for_module(&db, module);
+    let ctx = TermSearchCtx { sema: &db, scope: &scope, goal, config: TermSearchConfig::default() };
+    let solutions: Vec<String> = term_search(&ctx)
+        .iter()
+        .map(|it| it.gen_source_code(&scope, &mut |_| "todo!()".to_owned(), false, true).unwrap())
+        .collect();
+    assert!(solutions.contains(&"DEFAULT".to_owned()));
+    assert!(solutions.contains(&"make()".to_owned()));
+}
+
+#[test]
+fn benchmark_term_search_with_prelude() {
+    if skip_slow_tests() {
+        return;
+    }
+    // Whole minicore is in scope, most of its names are traits and macros no tactic uses
+    let flags = MiniCore::available_flags().join(", ");
+    let fixture = format!(
+        r#"//- minicore: {flags}
+struct A(u32);
+struct B(A, bool);
+struct C(B, Option<A>);
+fn make(n: u32) -> A {{ A(n) }}
+fn f(n: u32, s: &str) {{ let c: C = todo$0!(); }}"#
+    );
+    let solutions = with_ctx(&fixture, TermSearchConfig::default(), |ctx| {
+        let _b = bench("term search with prelude");
+        (0..3).map(|_| term_search(ctx).len()).sum::<usize>()
+    });
+    assert_eq!(solutions, 3);
+}
+
+#[test]
+fn singleton_goal_skips_rounds_only_without_other_values() {
+    let canonical_only = stats(
+        r#"//- minicore: todo, unimplemented
+struct S;
+fn f(a: u8) { let s: S = todo$0!(); }"#,
+        TermSearchConfig::default(),
+    );
+    let with_local = stats(
+        r#"//- minicore: todo, unimplemented
+struct S;
+fn f(a: u8, s: S) { let s: S = todo$0!(); }"#,
+        TermSearchConfig::default(),
+    );
+    assert_eq!(canonical_only.rounds_run, 0);
+    assert!(with_local.rounds_run > 0);
+}
+
+#[test]
+fn unit_goal_skips_must_use_methods() {
+    assert_eq!(
+        solutions(
+            r#"//- minicore: todo, unimplemented
+struct S(u8);
+impl S { #[must_use] fn compute(&self) {} fn effect(&self) {} }
+fn f(s: S) -> u8 { let a: () = todo$0!(); 0 }"#,
+            TermSearchConfig::default(),
+        ),
+        ["()", "s.effect()"],
+    );
+}
+
+#[test]
+fn singleton_goals() {
+    let singleton = |fixture| solutions(fixture, TermSearchConfig::default());
+    assert_eq!(
+        singleton(
+            r#"//- minicore: todo, unimplemented
+struct S(u8);
+fn f(a: S) -> u8 { let x: () = todo$0!(); 0 }"#
+        ),
+        ["()"],
+    );
+    assert_eq!(
+        singleton(
+            r#"//- minicore: todo, unimplemented
+struct S;
+fn f(a: u8) { let s: S = todo$0!(); }"#
+        ),
+        ["S"],
+    );
+    assert_eq!(
+        singleton(
+            r#"//- minicore: todo, unimplemented, phantom_data
+use core::marker::PhantomData;
+fn f(a: u8) { let p: PhantomData<u8> = todo$0!(); }"#
+        ),
+        ["PhantomData::<u8>"],
+    );
+}
+
+#[test]
+fn display_to_string_ranked_last() {
+    // Conversions come after the values of the goal type and its constructors
+    assert_eq!(
+        solutions(
+            r#"//- minicore: todo, unimplemented
+//- /main.rs crate:main deps:alloc
+use alloc::string::{String, ToString};
+fn f(n: i32, s: String) { let a: String = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod fmt {
+    pub trait Display {}
+    impl Display for i32 {}
+}
+pub mod string {
+    pub struct String(());
+    impl String { pub fn new() -> String { String(()) } }
+    pub trait ToString { fn to_string(&self) -> String; }
+    impl<T: crate::fmt::Display + ?Sized> ToString for T { fn to_string(&self) -> String { loop {} } }
+}
+"#,
+            TermSearchConfig::default(),
+        ),
+        ["s", "String::new()", "n.to_string()"],
+    );
+}
+
+#[test]
+fn custom_score_fn() {
+    let fixture = r#"//- minicore: todo, unimplemented
+struct S(u8);
+fn wrap(s: S) -> S { s }
+fn f(a: S) { let s: S = todo$0!(); }"#;
+    assert_eq!(solutions(fixture, TermSearchConfig::default()), ["a", "wrap(a)", "wrap(todo!())"]);
+    let config = TermSearchConfig {
+        score_fn: Some(|_, expr| -(expr.complexity() as f64)),
+        ..Default::default()
+    };
+    assert_eq!(solutions(fixture, config), ["wrap(a)", "wrap(todo!())", "a"]);
+}
+
+#[test]
+fn module_spread() {
+    let fixture = r#"//- minicore: todo, unimplemented
+mod a { pub struct X(u8); pub fn make() -> X { X(0) } pub fn wrap(x: X) -> u32 { 0 } }
+mod b { pub fn wrap(x: crate::a::X) -> u32 { 1 } }
+use a::{make, wrap as wrap_a};
+use b::wrap;
+fn f() { let n: u32 = todo$0!(); }"#;
+    assert_eq!(solutions(fixture, TermSearchConfig::default()), ["wrap(make())", "wrap_a(make())"],);
+    assert_eq!(
+        solutions(fixture, TermSearchConfig { module_spread_weight: 1.0, ..Default::default() }),
+        ["wrap_a(make())", "wrap(make())"],
+    );
+    assert_eq!(
+        solutions(fixture, TermSearchConfig { max_modules: Some(1), ..Default::default() }),
+        ["wrap_a(make())"],
+    );
+}
+
+#[test]
+fn grouped_by_tactic() {
+    let groups = with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct S(u32);
+fn f(a: S, b: u32) { let s: S = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            term_search_grouped(ctx)
+                .into_iter()
+                .flat_map(|(tactic, exprs)| {
+                    exprs.iter().map(|it| format!("{tactic:?}: {}", render(ctx, it))).collect_vec()
+                })
+                .collect::<Vec<_>>()
+        },
+    );
+    assert!(groups.contains(&"Trivial: a".to_owned()));
+    assert!(groups.contains(&"TypeConstructor: S(b)".to_owned()));
+}
+
+#[test]
+fn shortest_first() {
+    let fixture = r#"//- minicore: todo, unimplemented
+struct A(u32);
+struct B(A);
+fn g(x: u32, y: u32, z: u32) -> B { B(A(x)) }
+fn h(x: u32) -> B { B(A(x)) }
+fn f(b: u32) { let s: B = todo$0!(); }"#;
+    let stream = |stream_order| {
+        with_ctx(fixture, TermSearchConfig { stream_order, ..Default::default() }, |ctx| {
+            let exprs = term_search_iter(ctx).collect_vec();
+            let complexities = exprs.iter().map(Expr::complexity).collect_vec();
+            (exprs.iter().map(|it| render(ctx, it)).collect_vec(), complexities)
+        })
+    };
+    let (by_tactic, _) = stream(StreamOrder::Tactic);
+    assert_eq!(by_tactic, ["B(A(b))", "g(b, b, b)", "h(b)"]);
+    let (shortest_first, complexities) = stream(StreamOrder::ShortestFirst { buffer: 16 });
+    assert_eq!(shortest_first, ["h(b)", "B(A(b))", "g(b, b, b)"]);
+    assert!(complexities.windows(2).all(|it| it[0] <= it[1]));
+}
+
+#[test]
+fn continuations_from_receiver() {
+    let continuations = |fixture| {
+        with_ctx(fixture, TermSearchConfig::default(), |ctx| {
+            let ScopeDef::Local(it) = scope_def(ctx, "it") else { unreachable!() };
+            let receiver = it.ty(ctx.sema.db());
+            // Collecting borrowed items needs the borrow checking to be off
+            let config = TermSearchConfig { enable_borrowcheck: false, ..Default::default() };
+            continuations_from(ctx.sema, ctx.scope, receiver, ctx.goal.clone(), config)
+                .into_iter()
+                .filter_map(|it| match it {
+                    Expr::Method { func, params, .. } if params.is_empty() => {
+                        Some(func.name(ctx.sema.db()).as_str()?.to_owned())
+                    }
+                    _ => None,
+                })
+                .collect_vec()
+        })
+    };
+    assert_eq!(
+        continuations(
+            r#"//- minicore: todo, unimplemented
+struct Bag(u32);
+struct Iter(u32);
+impl Iter {
+    fn len(&self) -> u32 { self.0 }
+    fn into_bag(self) -> Bag { Bag(self.0) }
+}
+fn f(it: Iter, x: u32) { let b: Bag = todo$0!(); }"#
+        )[0],
+        "into_bag",
+    );
+    assert_eq!(
+        continuations(
+            r#"//- minicore: todo, unimplemented, slice, iterators
+//- /main.rs crate:main deps:alloc
+use alloc::vec::Vec;
+fn f(it: core::slice::Iter<'_, u32>) { let v: Vec<&u32> = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod vec {
+    pub struct Vec<T>(T);
+    impl<T> core::iter::FromIterator<T> for Vec<T> {
+        fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self { loop {} }
+    }
+}
+"#
+        )[0],
+        "collect",
+    );
+}
+
+#[test]
+fn nearest_reachable_types() {
+    let nearest = with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct S(u8);
+fn f(s: S) { let a: &S = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            assert!(term_search(ctx).is_empty());
+            nearest_reachable(ctx.sema, ctx.scope, ctx.goal.clone(), TermSearchConfig::default())
+                .into_iter()
+                .map(|(ty, distance)| format!("{} ({distance})", ty.display(ctx.sema.db())))
+                .collect_vec()
+        },
+    );
+    assert_eq!(nearest, ["S (1)"]);
+}
+
+#[test]
+fn dedup_reference_levels_keeps_fewest_references() {
+    let (all, kept) = with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct S(u8);
+fn f(x: S, r: &S) { let a: &S = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            let forms = ["x", "r"].into_iter().flat_map(|name| {
+                let ScopeDef::Local(it) = scope_def(ctx, name) else { unreachable!() };
+                let owned = Expr::Local(it);
+                let borrowed = Expr::reference(owned.clone());
+                [owned, borrowed.clone(), Expr::reference(borrowed)]
+            });
+            let render =
+                |exprs: Vec<Expr>| exprs.iter().map(|it| render(ctx, it)).sorted().collect_vec();
+            let all = render(forms.clone().collect());
+            let kept = render(dedup_reference_levels(ctx.sema.db(), &ctx.goal, forms.collect()));
+            (all, kept)
+        },
+    );
+    assert_eq!(all, ["&&r", "&&x", "&r", "&x", "r", "x"]);
+    assert_eq!(kept, ["&x", "r"]);
+}
+
+#[test]
+fn expr_builders() {
+    let code = with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct S { a: u32 }
+fn g(a: &u32) -> u32 { *a }
+fn f(s: S) { let b: u32 = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            let db = ctx.sema.db();
+            let ScopeDef::Local(local) = scope_def(ctx, "s") else { unreachable!() };
+            let ScopeDef::ModuleDef(ModuleDef::Function(func)) = scope_def(ctx, "g") else {
+                unreachable!()
+            };
+            let Some(Adt::Struct(strukt)) = local.ty(db).as_adt() else { unreachable!() };
+            let arg = Expr::field(db, Expr::local(local), strukt.fields(db)[0]).unwrap();
+            // Arity is checked
+            assert!(Expr::call(db, func, vec![], vec![arg.clone(), arg.clone()]).is_none());
+            let expr = Expr::call(db, func, vec![], vec![Expr::reference(arg)]).unwrap();
+            render(ctx, &expr)
+        },
+    );
+    assert_eq!(code, "g(&s.a)");
+}
+
+#[test]
+fn hoist_shared_subexprs() {
+    let code = with_ctx(
+        r#"//- minicore: todo, unimplemented, copy
+fn g(a: u32, b: u32) -> u32 { a }
+fn h(a: u32) -> u32 { a }
+fn f(a: u32) { let b: u32 = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            let db = ctx.sema.db();
+            let ScopeDef::Local(local) = scope_def(ctx, "a") else { unreachable!() };
+            let func = |name| match scope_def(ctx, name) {
+                ScopeDef::ModuleDef(ModuleDef::Function(it)) => it,
+                _ => unreachable!(),
+            };
+            let shared = Expr::call(db, func("h"), vec![], vec![Expr::local(local)]).unwrap();
+            let expr = Expr::call(db, func("g"), vec![], vec![shared.clone(), shared]).unwrap();
+            expr.gen_source_code_hoisted(ctx.scope, &mut |_| String::new(), false, true).unwrap()
+        },
+    );
+    assert_eq!(code, "{ let tmp = h(a); g(tmp, tmp) }");
+}
+
+#[test]
+fn expr_depth() {
+    let depths = with_ctx(
+        r#"//- minicore: todo, unimplemented
+fn g(a: u32, b: u32) -> u32 { a }
+fn h(a: &u32) -> u32 { *a }
+fn f(a: u32) { let b: u32 = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            let db = ctx.sema.db();
+            let ScopeDef::Local(local) = scope_def(ctx, "a") else { unreachable!() };
+            let func = |name| match scope_def(ctx, name) {
+                ScopeDef::ModuleDef(ModuleDef::Function(it)) => it,
+                _ => unreachable!(),
+            };
+            // a, h(&a), h(&h(&a)), g(a, h(&a))
+            let a = Expr::local(local);
+            let ha = Expr::call(db, func("h"), vec![], vec![Expr::reference(a.clone())]).unwrap();
+            let hha = Expr::call(db, func("h"), vec![], vec![Expr::reference(ha.clone())]).unwrap();
+            let gaha = Expr::call(db, func("g"), vec![], vec![a.clone(), ha.clone()]).unwrap();
+            [&a, &ha, &hha, &gaha].map(Expr::depth)
+        },
+    );
+    assert_eq!(depths, [1, 3, 5, 4]);
+}
+
+#[test]
+fn render_all_shares_paths() {
+    let rendered = with_ctx(
+        r#"//- minicore: todo, unimplemented
+mod m {
+    pub struct S(pub u8);
+    pub struct T(pub S);
+    pub fn wrap(s: S) -> T { T(s) }
+}
+use m::{wrap, S, T};
+fn f(n: u8) { let t: T = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            let exprs = term_search(ctx);
+            Expr::render_all(&exprs, ctx.scope, &mut |_| String::new(), false, true)
+                .into_iter()
+                .map(|it| {
+                    let (code, items) = it.unwrap();
+                    format!("{code} ({} items)", items.len())
+                })
+                .collect_vec()
+        },
+    );
+    assert_eq!(rendered, ["T(S(n)) (2 items)", "wrap(S(n)) (2 items)"]);
+}
+
+#[cfg(feature = "serde")]
+fn path(kind: PathKind, segments: &[&str]) -> ModPath {
+    ModPath::from_segments(kind, segments.iter().map(|it| Name::new_text_dont_use((*it).into())))
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn settings_serde_round_trip() {
+    let mut settings = TermSearchConfig::default().settings();
+    settings.fuel = 100;
+    settings.blocklist = vec!["crate::foo::bad".to_owned(), "core::mem".to_owned()];
+    settings.ranking_hints.insert("::core::mem::take".to_owned(), 2.0);
+    settings.stream_order = StreamOrder::ShortestFirst { buffer: 8 };
+    settings.soft_deadline_ms = Some(250);
+
+    let json = serde_json::to_string(&settings).unwrap();
+    let parsed: TermSearchSettings = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, settings);
+
+    let config = TermSearchConfig::from_parts(parsed, Vec::new(), None, Vec::new());
+    assert_eq!(config.blocklist[0], path(PathKind::Crate, &["foo", "bad"]));
+    assert_eq!(config.soft_deadline, Some(Duration::from_millis(250)));
+    assert_eq!(config.settings(), settings);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn settings_serde_defaults() {
+    let settings: TermSearchSettings = serde_json::from_str(r#"{ "fuel": 10 }"#).unwrap();
+    assert_eq!(settings, TermSearchSettings { fuel: 10, ..Default::default() });
+}
+
+/// Reasons the term search discarded the items named `secret` for
+#[cfg(feature = "term-search-dump")]
+fn secret_rejections(ra_fixture: &str, config: TermSearchConfig) -> Vec<String> {
+    with_ctx(ra_fixture, config, |ctx| {
+        let mut rejections = Vec::new();
+        term_search_with_rejections(ctx, &mut |reason, def| {
+            if def.name(ctx.sema.db()).as_ref().and_then(|it| it.as_str()) == Some("secret") {
+                rejections.push(format!("{reason:?}"));
+            }
+        });
+        rejections
+    })
+}
+
+#[cfg(feature = "term-search-dump")]
+#[test]
+fn reject_reason_visibility() {
+    let rejections = secret_rejections(
+        r#"//- minicore: todo, unimplemented
+mod m {
+    pub struct S(u8);
+    impl S { fn secret(&self) -> u32 { 0 } }
+}
+fn f(s: m::S) { let a: u32 = todo$0!(); }"#,
+        TermSearchConfig::default(),
+    );
+    assert_eq!(rejections, ["Visibility"]);
+}
+
+#[cfg(feature = "term-search-dump")]
+#[test]
+fn reject_reason_out_of_fuel() {
+    let rejections = secret_rejections(
+        r#"//- minicore: todo, unimplemented
+fn secret(a: u8) -> u32 { 0 }
+fn f(a: u8) { let n: u32 = todo$0!(); }"#,
+        TermSearchConfig { fuel: 0, ..Default::default() },
+    );
+    assert_eq!(rejections, ["OutOfFuel"]);
+}
+
+#[cfg(feature = "term-search-dump")]
+#[test]
+fn reject_reason_too_many_alternatives() {
+    let rejections = secret_rejections(
+        r#"//- minicore: todo, unimplemented
+struct W(u32);
+fn first() -> u32 { 0 }
+fn second() -> u32 { 0 }
+fn secret() -> u32 { 0 }
+fn f() { let w: W = todo$0!(); }"#,
+        TermSearchConfig::default(),
+    );
+    assert_eq!(rejections, ["TooManyAlternatives"]);
+}
+
+#[test]
+fn always_include_todo() {
+    let config = || TermSearchConfig { always_include_todo: true, ..Default::default() };
+    assert_eq!(
+        solutions(
+            r#"//- minicore: todo, unimplemented
+struct S(u8);
+fn f(s: S) { let a: S = todo$0!(); }"#,
+            config(),
+        ),
+        ["s", "todo!()"],
+    );
+    assert_eq!(
+        solutions(
+            r#"//- minicore: todo, unimplemented
+struct S(u8);
+mod m { pub struct T(u8); }
+fn f(s: S) { let a: m::T = todo$0!(); }"#,
+            config(),
+        ),
+        ["todo!()"],
+    );
+    // Unsized goals have no values, but the `todo!()` is still there
+    assert_eq!(
+        solutions(
+            r#"//- minicore: todo, unimplemented
+fn f(s: &str) { let a: &str = &todo$0!(); }"#,
+            config(),
+        ),
+        ["todo!()"],
+    );
+}
+
+#[test]
+fn builder_chain() {
+    assert_eq!(
+        solutions(
+            r#"//- minicore: todo, unimplemented
+mod cfg {
+    pub struct Config { port: u16, verbose: bool }
+    pub struct ConfigBuilder { port: u16, verbose: bool }
+    impl ConfigBuilder {
+        pub fn with_port(self, port: u16) -> Self { ConfigBuilder { port, ..self } }
+        pub fn with_verbose(self, verbose: bool) -> Self { ConfigBuilder { verbose, ..self } }
+        pub fn build(self) -> Config { Config { port: self.port, verbose: self.verbose } }
+    }
+}
+use cfg::{Config, ConfigBuilder};
+fn f(b: ConfigBuilder, port: u16) { let a: ConfigBuilder = todo$0!(); }"#,
+            TermSearchConfig {
+                many_alternatives_threshold: 4,
+                goal_many_alternatives_threshold: 4,
+                ..Default::default()
+            },
+        ),
+        ["b", "b.with_port(port).with_verbose(true)"],
+    );
+}
+
+#[test]
+fn allocation_penalty() {
+    let ranked = |allocation_penalty| {
+        solutions(
+            r#"//- minicore: todo, unimplemented
+//- /main.rs crate:main deps:alloc
+use alloc::string::String;
+struct Len(usize);
+struct Message(u8);
+fn weigh(s: &str) -> Len { todo!() }
+fn show(len: Len) -> Message { todo!() }
+fn aloud(s: &str) -> String { todo!() }
+fn format_owned(s: String) -> Message { todo!() }
+fn f(name: &str) { let a: Message = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod string {
+    pub struct String(u8);
+}
+"#,
+            TermSearchConfig {
+                allocation_penalty,
+                many_alternatives_threshold: 4,
+                goal_many_alternatives_threshold: 4,
+                ..Default::default()
+            },
+        )
+    };
+    assert_eq!(ranked(0.0), ["format_owned(aloud(name))", "show(weigh(name))"]);
+    assert_eq!(ranked(1.0), ["show(weigh(name))", "format_owned(aloud(name))"]);
+}
+
+#[test]
+fn stable_key_method_and_function_call() {
+    with_ctx(
+        r#"//- minicore: todo, unimplemented
+struct S(u8);
+impl S { fn get(&self) -> u8 { self.0 } }
+fn f(s: S, t: S) { let a: u8 = todo$0!(); }"#,
+        TermSearchConfig::default(),
+        |ctx| {
+            let db = ctx.sema.db();
+            let local = |name| match scope_def(ctx, name) {
+                ScopeDef::Local(it) => it,
+                _ => unreachable!(),
+            };
+            let (s, t) = (local("s"), local("t"));
+            let get = s
+                .ty(db)
+                .iterate_assoc_items(db, ctx.scope.krate(), |it| match it {
+                    AssocItem::Function(f) => Some(f),
+                    _ => None,
+                })
+                .unwrap();
+            let method = |local| Expr::method(db, get, vec![], Expr::local(local), vec![]).unwrap();
+            let function =
+                |param| Expr::Function { func: get, generics: vec![], params: vec![param] };
+            let key = method(s).stable_key(db);
+            assert_eq!(key, function(Expr::reference(Expr::local(s))).stable_key(db));
+            assert_ne!(key, method(t).stable_key(db));
+            // Taking `s` by value is not the same call
+            assert_ne!(key, function(Expr::local(s)).stable_key(db));
+        },
+    );
+}
@@ -0,0 +1,73 @@
+//! Database used for testing `hir`.
+
+use std::{fmt, panic};
+
+use base_db::{
+    salsa::{self, Durability},
+    AnchoredPath, CrateId, FileId, FileLoader, FileLoaderDelegate, Upcast,
+};
+use hir_def::db::DefDatabase;
+use hir_expand::db::ExpandDatabase;
+use hir_ty::db::HirDatabase;
+use triomphe::Arc;
+
+#[salsa::database(
+    base_db::SourceDatabaseExtStorage,
+    base_db::SourceDatabaseStorage,
+    hir_expand::db::ExpandDatabaseStorage,
+    hir_def::db::InternDatabaseStorage,
+    hir_def::db::DefDatabaseStorage,
+    hir_ty::db::HirDatabaseStorage
+)]
+pub(crate) struct TestDB {
+    storage: salsa::Storage<TestDB>,
+}
+
+impl Default for TestDB {
+    fn default() -> Self {
+        let mut this = Self { storage: Default::default() };
+        this.setup_syntax_context_root();
+        this.set_expand_proc_attr_macros_with_durability(true, Durability::HIGH);
+        this
+    }
+}
+
+impl fmt::Debug for TestDB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestDB").finish()
+    }
+}
+
+impl Upcast<dyn ExpandDatabase> for TestDB {
+    fn upcast(&self) -> &(dyn ExpandDatabase + 'static) {
+        self
+    }
+}
+
+impl Upcast<dyn DefDatabase> for TestDB {
+    fn upcast(&self) -> &(dyn DefDatabase + 'static) {
+        self
+    }
+}
+
+impl Upcast<dyn HirDatabase> for TestDB {
+    fn upcast(&self) -> &(dyn HirDatabase + 'static) {
+        self
+    }
+}
+
+impl salsa::Database for TestDB {}
+
+impl panic::RefUnwindSafe for TestDB {}
+
+impl FileLoader for TestDB {
+    fn file_text(&self, file_id: FileId) -> Arc<str> {
+        FileLoaderDelegate(self).file_text(file_id)
+    }
+    fn resolve_path(&self, path: AnchoredPath<'_>) -> Option<FileId> {
+        FileLoaderDelegate(self).resolve_path(path)
+    }
+    fn relevant_crates(&self, file_id: FileId) -> Arc<[CrateId]> {
+        FileLoaderDelegate(self).relevant_crates(file_id)
+    }
+}
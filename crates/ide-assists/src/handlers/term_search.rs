@@ -6,11 +6,53 @@ use ide_db::{
 };
 
 use itertools::Itertools;
-use syntax::{ast, AstNode};
+use syntax::{
+    ast::{self, BlockModifier},
+    match_ast, AstNode, SyntaxNode,
+};
 
 use crate::assist_context::{AssistContext, Assists};
 
 pub(crate) fn term_search(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<()> {
+    let enclosing_ret_ty = ctx
+        .find_node_at_offset::<ast::MacroCall>()
+        .and_then(|it| enclosing_ret_ty(ctx, it.syntax()));
+    let config = TermSearchConfig {
+        fuel: ctx.config.term_search_fuel,
+        enclosing_ret_ty,
+        ..Default::default()
+    };
+    term_search_with_config(acc, ctx, config)
+}
+
+/// Return type of the function `node` is in, `None` if the `?` operator at `node` would return
+/// from a closure or a block instead
+fn enclosing_ret_ty(ctx: &AssistContext<'_>, node: &SyntaxNode) -> Option<hir::Type> {
+    for it in node.ancestors() {
+        match_ast! {
+            match it {
+                ast::Fn(it) => return Some(ctx.sema.to_def(&it)?.ret_type(ctx.db())),
+                ast::ClosureExpr(_) => return None,
+                ast::BlockExpr(it) => {
+                    if matches!(
+                        it.modifier(),
+                        Some(BlockModifier::Async(_) | BlockModifier::Try(_) | BlockModifier::Const(_))
+                    ) {
+                        return None;
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+    None
+}
+
+fn term_search_with_config(
+    acc: &mut Assists,
+    ctx: &AssistContext<'_>,
+    config: TermSearchConfig,
+) -> Option<()> {
     let unexpanded = ctx.find_node_at_offset::<ast::MacroCall>()?;
     let syntax = unexpanded.syntax();
     let goal_range = syntax.text_range();
@@ -30,12 +72,7 @@ pub(crate) fn term_search(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<
 
     let target_ty = ctx.sema.type_of_expr(&ast::Expr::cast(parent.clone())?)?.adjusted();
 
-    let term_search_ctx = TermSearchCtx {
-        sema: &ctx.sema,
-        scope: &scope,
-        goal: target_ty,
-        config: TermSearchConfig { fuel: ctx.config.term_search_fuel, ..Default::default() },
-    };
+    let term_search_ctx = TermSearchCtx { sema: &ctx.sema, scope: &scope, goal: target_ty, config };
     let paths = hir::term_search::term_search(&term_search_ctx);
 
     if paths.is_empty() {
@@ -77,7 +114,15 @@ pub(crate) fn term_search(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<
 
 #[cfg(test)]
 mod tests {
-    use crate::tests::{check_assist, check_assist_not_applicable};
+    use hir::{term_search::Expr, ModPath, Name, PathKind};
+
+    use crate::{
+        handlers::Handler,
+        tests::{
+            check_assist, check_assist_by_label, check_assist_not_applicable,
+            check_assist_not_applicable_by_label,
+        },
+    };
 
     use super::*;
 
@@ -135,11 +180,12 @@ fn f() { let a = A { x: 1, y: true }; let b: i32 = a.x; }"#,
 
     #[test]
     fn test_enum_with_generics() {
-        check_assist(
+        check_assist_by_label(
             term_search,
             r#"//- minicore: todo, unimplemented, option
 fn f() { let a: i32 = 1; let b: Option<i32> = todo$0!(); }"#,
             r#"fn f() { let a: i32 = 1; let b: Option<i32> = None; }"#,
+            "Replace todo!() with None",
         )
     }
 
@@ -273,4 +319,3079 @@ fn f() { let a = 1; let b = 0.0; let c: (i32, (i32, f64)) = todo$0!(); }"#,
             r#"fn f() { let a = 1; let b = 0.0; let c: (i32, (i32, f64)) = (a, (a, b)); }"#,
         )
     }
+
+    fn path(kind: PathKind, segments: &[&str]) -> ModPath {
+        ModPath::from_segments(
+            kind,
+            segments.iter().map(|it| Name::new_text_dont_use((*it).into())),
+        )
+    }
+
+    #[test]
+    fn test_blocklist_fn() {
+        let term_search_blocked: Handler = |acc, ctx| {
+            let blocklist = vec![path(PathKind::Crate, &["foo", "bad"])];
+            term_search_with_config(acc, ctx, TermSearchConfig { blocklist, ..Default::default() })
+        };
+
+        let before = r#"//- minicore: todo, unimplemented
+mod foo { pub fn bad() -> u32 { 0 } pub fn good() -> u32 { 1 } }
+use foo::{bad, good};
+fn f() { let a: u32 = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"mod foo { pub fn bad() -> u32 { 0 } pub fn good() -> u32 { 1 } }
+use foo::{bad, good};
+fn f() { let a: u32 = bad(); }"#,
+            "Replace todo!() with bad()",
+        );
+        check_assist_by_label(
+            term_search_blocked,
+            before,
+            r#"mod foo { pub fn bad() -> u32 { 0 } pub fn good() -> u32 { 1 } }
+use foo::{bad, good};
+fn f() { let a: u32 = good(); }"#,
+            "Replace todo!() with good()",
+        );
+        check_assist_not_applicable_by_label(
+            term_search_blocked,
+            before,
+            "Replace todo!() with bad()",
+        );
+    }
+
+    #[test]
+    fn test_blocklist_module() {
+        check_assist_not_applicable(
+            |acc, ctx| {
+                let blocklist = vec![path(PathKind::Crate, &["foo"])];
+                term_search_with_config(
+                    acc,
+                    ctx,
+                    TermSearchConfig { blocklist, ..Default::default() },
+                )
+            },
+            r#"//- minicore: todo, unimplemented
+mod foo { pub struct Foo; impl Foo { pub fn new() -> Foo { Foo } } pub fn foo() -> Foo { Foo } }
+use foo::{foo, Foo};
+fn f() { let a: Foo = todo$0!(); }"#,
+        );
+    }
+
+    #[test]
+    fn test_blocklist_variant() {
+        let term_search_blocked: Handler = |acc, ctx| {
+            let blocklist = vec![path(PathKind::Crate, &["m", "E", "A"])];
+            term_search_with_config(acc, ctx, TermSearchConfig { blocklist, ..Default::default() })
+        };
+
+        let before = r#"//- minicore: todo, unimplemented
+mod m { pub enum E { A, B } }
+use m::E;
+fn f() { let e: E = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search_blocked,
+            before,
+            r#"mod m { pub enum E { A, B } }
+use m::E;
+fn f() { let e: E = E::B; }"#,
+            "Replace todo!() with E::B",
+        );
+        check_assist_not_applicable_by_label(
+            term_search_blocked,
+            before,
+            "Replace todo!() with E::A",
+        );
+    }
+
+    #[test]
+    fn test_vec_with_capacity() {
+        // Sized constructors are ranked last
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+struct Vec<T> { ptr: *const T }
+impl<T> Vec<T> {
+    pub fn with_capacity(capacity: usize) -> Vec<T> { todo!() }
+    pub fn new() -> Vec<T> { todo!() }
+}
+fn f() { let n: usize = 4; let a: Vec<i32> = todo$0!(); }"#,
+            r#"struct Vec<T> { ptr: *const T }
+impl<T> Vec<T> {
+    pub fn with_capacity(capacity: usize) -> Vec<T> { todo!() }
+    pub fn new() -> Vec<T> { todo!() }
+}
+fn f() { let n: usize = 4; let a: Vec<i32> = Vec::with_capacity(n); }"#,
+        )
+    }
+
+    #[test]
+    fn test_generic_fn_assoc_type_bound() {
+        let before = r#"//- minicore: todo, unimplemented
+trait Foo { type Item; }
+trait Bar {}
+struct Good;
+struct Bad;
+impl Bar for Good {}
+impl Foo for i32 { type Item = Good; }
+impl Foo for u64 { type Item = Bad; }
+fn f<T: Foo>(x: T) -> u8 where T::Item: Bar { 0 }
+fn g() { let a: i32 = 1; let b: u64 = 2; let c: u8 = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"trait Foo { type Item; }
+trait Bar {}
+struct Good;
+struct Bad;
+impl Bar for Good {}
+impl Foo for i32 { type Item = Good; }
+impl Foo for u64 { type Item = Bad; }
+fn f<T: Foo>(x: T) -> u8 where T::Item: Bar { 0 }
+fn g() { let a: i32 = 1; let b: u64 = 2; let c: u8 = f(a); }"#,
+            "Replace todo!() with f(a)",
+        );
+        check_assist_not_applicable_by_label(term_search, before, "Replace todo!() with f(b)");
+    }
+
+    #[test]
+    fn test_fieldless_enum_variant() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+enum Color { Red, Green }
+fn f() { let a: Color = todo$0!(); }"#,
+            r#"enum Color { Red, Green }
+fn f() { let a: Color = Color::Green; }"#,
+            "Replace todo!() with Color::Green",
+        )
+    }
+
+    #[test]
+    fn test_fieldless_enum_cast() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+#[repr(u8)]
+enum Color { Red = 1, Green = 2 }
+fn f() { let c = Color::Red; let a: i32 = todo$0!(); }"#,
+            r#"#[repr(u8)]
+enum Color { Red = 1, Green = 2 }
+fn f() { let c = Color::Red; let a: i32 = c as i32; }"#,
+        )
+    }
+
+    #[test]
+    fn test_data_carrying_enum_no_cast() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+enum Foo { A(u8), B }
+fn f() { let c = Foo::B; let a: i32 = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_unit_goal_must_use() {
+        let before = r#"//- minicore: todo, unimplemented
+#[must_use]
+fn compute() {}
+fn effect() {}
+fn f() { let a: () = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"#[must_use]
+fn compute() {}
+fn effect() {}
+fn f() { let a: () = effect(); }"#,
+            "Replace todo!() with effect()",
+        );
+        check_assist_not_applicable_by_label(term_search, before, "Replace todo!() with compute()");
+    }
+
+    #[test]
+    fn test_newtype_wrap() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+pub struct Id(pub u64);
+fn f(a: u64) { let id: Id = todo$0!(); }"#,
+            r#"pub struct Id(pub u64);
+fn f(a: u64) { let id: Id = Id(a); }"#,
+        )
+    }
+
+    #[test]
+    fn test_newtype_unwrap_pub_field() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+pub struct Id(pub u64);
+fn f(id: Id) { let a: u64 = todo$0!(); }"#,
+            r#"pub struct Id(pub u64);
+fn f(id: Id) { let a: u64 = id.0; }"#,
+        )
+    }
+
+    #[test]
+    fn test_newtype_unwrap_deref() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, deref, copy
+mod m {
+    pub struct Id(u64);
+    impl core::ops::Deref for Id {
+        type Target = u64;
+        fn deref(&self) -> &u64 { &self.0 }
+    }
+}
+fn f(id: m::Id) { let a: u64 = todo$0!(); }"#,
+            r#"mod m {
+    pub struct Id(u64);
+    impl core::ops::Deref for Id {
+        type Target = u64;
+        fn deref(&self) -> &u64 { &self.0 }
+    }
+}
+fn f(id: m::Id) { let a: u64 = *id; }"#,
+        )
+    }
+
+    #[test]
+    fn test_dyn_trait_with_auto_trait() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, coerce_unsized, send
+use core::marker::{Send, Unsize};
+use core::ops::CoerceUnsized;
+#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Box<U>> for Box<T> {}
+unsafe impl<T: ?Sized + Send> Send for Box<T> {}
+trait Error {}
+struct E;
+impl Error for E {}
+fn f(e: Box<E>) { let a: Box<dyn Error + Send> = todo$0!(); }"#,
+            r#"use core::marker::{Send, Unsize};
+use core::ops::CoerceUnsized;
+#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Box<U>> for Box<T> {}
+unsafe impl<T: ?Sized + Send> Send for Box<T> {}
+trait Error {}
+struct E;
+impl Error for E {}
+fn f(e: Box<E>) { let a: Box<dyn Error + Send> = e; }"#,
+        )
+    }
+
+    #[test]
+    fn test_dyn_trait_missing_auto_trait() {
+        check_assist_not_applicable_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, coerce_unsized, send
+use core::marker::{Send, Unsize};
+use core::ops::CoerceUnsized;
+#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Box<U>> for Box<T> {}
+unsafe impl<T: ?Sized + Send> Send for Box<T> {}
+trait Error {}
+struct E;
+impl Error for E {}
+impl !Send for E {}
+fn f(e: Box<E>) { let a: Box<dyn Error + Send> = todo$0!(); }"#,
+            "Replace todo!() with e",
+        )
+    }
+
+    #[test]
+    fn test_dyn_trait_reference() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, coerce_unsized, send
+trait Tr {}
+struct S(u32);
+impl Tr for S {}
+fn f(s: S) { let a: &(dyn Tr + Send) = todo$0!(); }"#,
+            r#"trait Tr {}
+struct S(u32);
+impl Tr for S {}
+fn f(s: S) { let a: &(dyn Tr + Send) = &s; }"#,
+        )
+    }
+
+    #[test]
+    fn test_option_unwrap_or() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, option
+fn f(a: Option<i32>, b: i32) { let c: i32 = todo$0!(); }"#,
+            r#"fn f(a: Option<i32>, b: i32) { let c: i32 = a.unwrap_or(b); }"#,
+            "Replace todo!() with a.unwrap_or(b)",
+        )
+    }
+
+    #[test]
+    fn test_option_unwrap_or_default() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, option, default, builtin_impls
+fn f(a: Option<i32>) { let c: i32 = todo$0!(); }"#,
+            r#"fn f(a: Option<i32>) { let c: i32 = a.unwrap_or_default(); }"#,
+            "Replace todo!() with a.unwrap_or_default()",
+        );
+        // Bound on the method is only checked when validating
+        check_assist_not_applicable_by_label(
+            |acc, ctx| {
+                term_search_with_config(
+                    acc,
+                    ctx,
+                    TermSearchConfig { validate: true, ..Default::default() },
+                )
+            },
+            r#"//- minicore: todo, unimplemented, option, default
+struct S(u8);
+fn f(a: Option<S>) { let c: S = todo$0!(); }"#,
+            "Replace todo!() with a.unwrap_or_default()",
+        );
+    }
+
+    #[test]
+    fn test_result_ok_method() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, option, result
+struct Error;
+fn f(r: Result<i32, Error>) { let a: Option<i32> = todo$0!(); }"#,
+            r#"struct Error;
+fn f(r: Result<i32, Error>) { let a: Option<i32> = r.ok(); }"#,
+            "Replace todo!() with r.ok()",
+        );
+    }
+
+    #[test]
+    fn test_option_unwrap_opt_in() {
+        let term_search_panicking: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { enable_panicking: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented, option
+fn f(a: Option<i32>) { let c: i32 = todo$0!(); }"#;
+        check_assist_not_applicable_by_label(
+            term_search,
+            before,
+            "Replace todo!() with a.unwrap()",
+        );
+        check_assist_by_label(
+            term_search_panicking,
+            before,
+            r#"fn f(a: Option<i32>) { let c: i32 = a.unwrap(); }"#,
+            "Replace todo!() with a.unwrap()",
+        );
+    }
+
+    #[test]
+    fn test_no_new_imports() {
+        let term_search_no_imports: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { no_new_imports: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented
+mod foo { pub struct S; impl S { pub fn new() -> S { S } } }
+fn f(s: foo::S) { let a: foo::S = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"mod foo { pub struct S; impl S { pub fn new() -> S { S } } }
+fn f(s: foo::S) { let a: foo::S = foo::S::new(); }"#,
+            "Replace todo!() with foo::S::new()",
+        );
+        check_assist_not_applicable_by_label(
+            term_search_no_imports,
+            before,
+            "Replace todo!() with foo::S::new()",
+        );
+        check_assist_by_label(
+            term_search_no_imports,
+            before,
+            r#"mod foo { pub struct S; impl S { pub fn new() -> S { S } } }
+fn f(s: foo::S) { let a: foo::S = s; }"#,
+            "Replace todo!() with s",
+        );
+    }
+
+    #[test]
+    fn test_assoc_type_projection_goal() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait Tr { type Item; }
+struct S;
+impl Tr for S { type Item = i32; }
+fn f(x: i32) { let a: <S as Tr>::Item = todo$0!(); }"#,
+            r#"trait Tr { type Item; }
+struct S;
+impl Tr for S { type Item = i32; }
+fn f(x: i32) { let a: <S as Tr>::Item = x; }"#,
+        )
+    }
+
+    #[test]
+    fn test_assoc_type_projection_goal_cast() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait Tr { type Item; }
+struct S;
+impl Tr for S { type Item = i32; }
+enum E { A, B }
+fn f(e: E) { let a: <S as Tr>::Item = todo$0!(); }"#,
+            r#"trait Tr { type Item; }
+struct S;
+impl Tr for S { type Item = i32; }
+enum E { A, B }
+fn f(e: E) { let a: <S as Tr>::Item = e as i32; }"#,
+        )
+    }
+
+    #[test]
+    fn test_max_fn_args() {
+        let before = r#"//- minicore: todo, unimplemented
+struct S(u32);
+fn g(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8) -> S { S(0) }
+fn f(x: u8) { let a: S = todo$0!(); }"#;
+        check_assist_not_applicable(term_search, before);
+        check_assist(
+            |acc, ctx| {
+                term_search_with_config(
+                    acc,
+                    ctx,
+                    TermSearchConfig { max_fn_args: 7, ..Default::default() },
+                )
+            },
+            before,
+            r#"struct S(u32);
+fn g(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8) -> S { S(0) }
+fn f(x: u8) { let a: S = g(x, x, x, x, x, x, x); }"#,
+        );
+    }
+
+    #[test]
+    fn test_cow_borrowed() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+//- /main.rs crate:main deps:alloc
+use alloc::borrow::Cow;
+fn f(s: &str) { let a: Cow<str> = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod borrow {
+    pub trait ToOwned { type Owned; }
+    impl ToOwned for str { type Owned = crate::string::String; }
+    pub enum Cow<'a, B: ?Sized + ToOwned + 'a> { Borrowed(&'a B), Owned(<B as ToOwned>::Owned) }
+}
+pub mod string { pub struct String; }
+"#,
+            r#"use alloc::borrow::Cow;
+fn f(s: &str) { let a: Cow<str> = Cow::Borrowed(s); }
+"#,
+        )
+    }
+
+    #[test]
+    fn test_cow_owned() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+//- /main.rs crate:main deps:alloc
+use alloc::{borrow::Cow, string::String};
+fn f(s: String) { let a: Cow<str> = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod borrow {
+    pub trait ToOwned { type Owned; }
+    impl ToOwned for str { type Owned = crate::string::String; }
+    pub enum Cow<'a, B: ?Sized + ToOwned + 'a> { Borrowed(&'a B), Owned(<B as ToOwned>::Owned) }
+}
+pub mod string { pub struct String; }
+"#,
+            r#"use alloc::{borrow::Cow, string::String};
+fn f(s: String) { let a: Cow<str> = Cow::Owned(s); }
+"#,
+        )
+    }
+
+    #[test]
+    fn test_validate_impl_bounds() {
+        let term_search_validated: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { validate: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented, copy
+mod m {
+    pub struct W<T>(T);
+    impl<T: Copy> W<T> { pub fn get(self) -> T { self.0 } }
+}
+struct S(u8);
+fn f(w: m::W<S>, x: u8) { let a: S = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"mod m {
+    pub struct W<T>(T);
+    impl<T: Copy> W<T> { pub fn get(self) -> T { self.0 } }
+}
+struct S(u8);
+fn f(w: m::W<S>, x: u8) { let a: S = w.get(); }"#,
+            "Replace todo!() with w.get()",
+        );
+        check_assist_not_applicable_by_label(
+            term_search_validated,
+            before,
+            "Replace todo!() with w.get()",
+        );
+        check_assist_by_label(
+            term_search_validated,
+            before,
+            r#"mod m {
+    pub struct W<T>(T);
+    impl<T: Copy> W<T> { pub fn get(self) -> T { self.0 } }
+}
+struct S(u8);
+fn f(w: m::W<S>, x: u8) { let a: S = S(x); }"#,
+            "Replace todo!() with S(x)",
+        );
+    }
+
+    #[test]
+    fn test_default_fields() {
+        let term_search_default_fields: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { enable_default_fields: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented, default, builtin_impls
+struct Config { a: u32, b: u64 }
+fn f() { let c: Config = todo$0!(); }"#;
+        check_assist_not_applicable(term_search, before);
+        check_assist(
+            term_search_default_fields,
+            before,
+            r#"struct Config { a: u32, b: u64 }
+fn f() { let c: Config = Config { a: Default::default(), b: Default::default() }; }"#,
+        );
+        check_assist_not_applicable(
+            term_search_default_fields,
+            r#"//- minicore: todo, unimplemented, default, builtin_impls
+struct NoDefault(u8);
+struct Config { a: u32, b: NoDefault }
+fn f() { let c: Config = todo$0!(); }"#,
+        );
+    }
+
+    #[test]
+    fn test_display_to_string() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+//- /main.rs crate:main deps:alloc
+use alloc::string::{String, ToString};
+fn f(n: i32) { let a: String = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod fmt {
+    pub trait Display {}
+    impl Display for i32 {}
+}
+pub mod string {
+    pub struct String;
+    pub trait ToString { fn to_string(&self) -> String; }
+    impl<T: crate::fmt::Display + ?Sized> ToString for T { fn to_string(&self) -> String { String } }
+}
+"#,
+            r#"use alloc::string::{String, ToString};
+fn f(n: i32) { let a: String = n.to_string(); }
+"#,
+            "Replace todo!() with n.to_string()",
+        )
+    }
+
+    #[test]
+    fn test_multiple_generics_inferred_jointly() {
+        let before = r#"//- minicore: todo, unimplemented
+mod collections {
+    pub struct HashMap<K, V>(K, V);
+    impl<K, V> HashMap<K, V> { pub fn new() -> Self { loop {} } }
+    pub fn from_entry<K, V>(k: K, v: V) -> HashMap<K, V> { HashMap(k, v) }
+}
+use collections::{from_entry, HashMap};
+struct Key(u8);
+fn f(k: Key, v: i32) { let m: HashMap<Key, i32> = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"mod collections {
+    pub struct HashMap<K, V>(K, V);
+    impl<K, V> HashMap<K, V> { pub fn new() -> Self { loop {} } }
+    pub fn from_entry<K, V>(k: K, v: V) -> HashMap<K, V> { HashMap(k, v) }
+}
+use collections::{from_entry, HashMap};
+struct Key(u8);
+fn f(k: Key, v: i32) { let m: HashMap<Key, i32> = HashMap::new(); }"#,
+            "Replace todo!() with HashMap::new()",
+        );
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"mod collections {
+    pub struct HashMap<K, V>(K, V);
+    impl<K, V> HashMap<K, V> { pub fn new() -> Self { loop {} } }
+    pub fn from_entry<K, V>(k: K, v: V) -> HashMap<K, V> { HashMap(k, v) }
+}
+use collections::{from_entry, HashMap};
+struct Key(u8);
+fn f(k: Key, v: i32) { let m: HashMap<Key, i32> = from_entry(k, v); }"#,
+            "Replace todo!() with from_entry(k, v)",
+        );
+    }
+
+    #[test]
+    fn test_field_of_borrowed_struct() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+struct Inner(u8);
+struct S { f: Inner }
+fn g(s: &S) { let a: &Inner = todo$0!(); }"#,
+            r#"struct Inner(u8);
+struct S { f: Inner }
+fn g(s: &S) { let a: &Inner = &s.f; }"#,
+        )
+    }
+
+    #[test]
+    fn test_field_of_mutably_borrowed_struct() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+struct Inner(u8);
+struct S { f: Inner }
+fn g(s: &mut S) { let a: &mut Inner = todo$0!(); }"#,
+            r#"struct Inner(u8);
+struct S { f: Inner }
+fn g(s: &mut S) { let a: &mut Inner = &mut s.f; }"#,
+        )
+    }
+
+    #[test]
+    fn test_unsized_goal() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, sized
+fn f(s: &str) { let a: str = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_unsized_field_of_borrowed_struct() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, sized
+struct S { len: u8, data: str }
+fn g(s: &S) { let a: &str = todo$0!(); }"#,
+            r#"struct S { len: u8, data: str }
+fn g(s: &S) { let a: &str = &s.data; }"#,
+        )
+    }
+
+    #[test]
+    fn test_goal_many_alternatives_threshold() {
+        let term_search_goal_threshold: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { goal_many_alternatives_threshold: 5, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented
+struct S(u8);
+fn wrap(s: S) -> S { s }
+fn f(a: S, b: S) { let s: S = todo$0!(); }"#;
+        check_assist_not_applicable_by_label(term_search, before, "Replace todo!() with wrap(a)");
+        check_assist_by_label(
+            term_search_goal_threshold,
+            before,
+            r#"struct S(u8);
+fn wrap(s: S) -> S { s }
+fn f(a: S, b: S) { let s: S = wrap(a); }"#,
+            "Replace todo!() with wrap(a)",
+        );
+    }
+
+    #[test]
+    fn test_double_reference_param() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+struct Count(u8);
+fn count(x: &&i32) -> Count { Count(0) }
+fn f(n: i32) { let c: Count = todo$0!(); }"#,
+            r#"struct Count(u8);
+fn count(x: &&i32) -> Count { Count(0) }
+fn f(n: i32) { let c: Count = count(&&n); }"#,
+        )
+    }
+
+    #[test]
+    fn test_double_reference_param_no_temporary() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+struct Count(u8);
+fn count(x: &&i32) -> Count { Count(0) }
+fn num() -> i32 { 0 }
+fn f() { let c: Count = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_shuffle_seed() {
+        check_assist(
+            |acc, ctx| {
+                term_search_with_config(
+                    acc,
+                    ctx,
+                    TermSearchConfig { shuffle_seed: Some(42), ..Default::default() },
+                )
+            },
+            r#"//- minicore: todo, unimplemented
+struct A(u8);
+struct B(A);
+fn f(a: A) { let b: B = todo$0!(); }"#,
+            r#"struct A(u8);
+struct B(A);
+fn f(a: A) { let b: B = B(a); }"#,
+        )
+    }
+
+    #[test]
+    fn test_repeat_array() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, copy
+fn f(b: u8) { let buf: [u8; 16] = todo$0!(); }"#,
+            r#"fn f(b: u8) { let buf: [u8; 16] = [b; 16]; }"#,
+        )
+    }
+
+    #[test]
+    fn test_repeat_array_not_copy() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, copy
+struct S(u8);
+fn f(s: S) { let buf: [S; 4] = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_ranking_hints() {
+        let term_search_hinted: Handler = |acc, ctx| {
+            let ranking_hints =
+                [(path(PathKind::Crate, &["foo", "second"]), 1.0)].into_iter().collect();
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { ranking_hints, ..Default::default() },
+            )
+        };
+
+        // Hinted item is ranked first, so last assist is the other one
+        let before = r#"//- minicore: todo, unimplemented
+mod foo { pub fn first() -> u32 { 0 } pub fn second() -> u32 { 1 } }
+use foo::{first, second};
+fn f() { let a: u32 = todo$0!(); }"#;
+        check_assist(
+            term_search,
+            before,
+            r#"mod foo { pub fn first() -> u32 { 0 } pub fn second() -> u32 { 1 } }
+use foo::{first, second};
+fn f() { let a: u32 = second(); }"#,
+        );
+        check_assist(
+            term_search_hinted,
+            before,
+            r#"mod foo { pub fn first() -> u32 { 0 } pub fn second() -> u32 { 1 } }
+use foo::{first, second};
+fn f() { let a: u32 = first(); }"#,
+        );
+    }
+
+    #[test]
+    fn test_method_generics_inferred_from_goal() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait FromStr { fn from_str(s: &str) -> Self; }
+impl FromStr for i32 { fn from_str(s: &str) -> Self { 0 } }
+struct Text(u32);
+impl Text { fn parse<T: FromStr>(&self) -> T { todo!() } }
+fn f(s: Text) { let a: i32 = todo$0!(); }"#,
+            r#"trait FromStr { fn from_str(s: &str) -> Self; }
+impl FromStr for i32 { fn from_str(s: &str) -> Self { 0 } }
+struct Text(u32);
+impl Text { fn parse<T: FromStr>(&self) -> T { todo!() } }
+fn f(s: Text) { let a: i32 = s.parse::<i32>(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_method_generics_bounds_checked() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait FromStr { fn from_str(s: &str) -> Self; }
+struct Text(u32);
+impl Text { fn parse<T: FromStr>(&self) -> T { todo!() } }
+fn f(s: Text) { let a: i32 = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_trait_method_generics_inferred_from_goal() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait FromStr { fn from_str(s: &str) -> Self; }
+impl FromStr for i32 { fn from_str(s: &str) -> Self { 0 } }
+struct Text(u32);
+trait Parse { fn parse<T: FromStr>(&self) -> T; }
+impl Parse for Text { fn parse<T: FromStr>(&self) -> T { todo!() } }
+fn f(s: Text) { let a: i32 = todo$0!(); }"#,
+            r#"trait FromStr { fn from_str(s: &str) -> Self; }
+impl FromStr for i32 { fn from_str(s: &str) -> Self { 0 } }
+struct Text(u32);
+trait Parse { fn parse<T: FromStr>(&self) -> T; }
+impl Parse for Text { fn parse<T: FromStr>(&self) -> T { todo!() } }
+fn f(s: Text) { let a: i32 = s.parse::<i32>(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_box_pin_future() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, future, coerce_unsized
+//- /main.rs crate:main deps:alloc
+use alloc::boxed::Box;
+use core::{future::Future, pin::Pin, task::{Context, Poll}};
+struct Fut(u32);
+impl Future for Fut {
+    type Output = u32;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> { Poll::Pending }
+}
+fn f(fut: Fut) { let a: Pin<Box<dyn Future<Output = u32>>> = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod boxed {
+    use core::{marker::Unsize, ops::CoerceUnsized, pin::Pin};
+    #[lang = "owned_box"]
+    pub struct Box<T: ?Sized>(*mut T);
+    impl<T> Box<T> {
+        pub fn new(x: T) -> Box<T> { loop {} }
+        pub fn pin(x: T) -> Pin<Box<T>> { loop {} }
+    }
+    impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Box<U>> for Box<T> {}
+}
+"#,
+            r#"use alloc::boxed::Box;
+use core::{future::Future, pin::Pin, task::{Context, Poll}};
+struct Fut(u32);
+impl Future for Fut {
+    type Output = u32;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> { Poll::Pending }
+}
+fn f(fut: Fut) { let a: Pin<Box<dyn Future<Output = u32>>> = Box::pin(fut); }
+"#,
+        )
+    }
+
+    #[test]
+    fn test_box_pin_future_wrong_output() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, future, coerce_unsized
+//- /main.rs crate:main deps:alloc
+use alloc::boxed::Box;
+use core::{future::Future, pin::Pin, task::{Context, Poll}};
+struct Fut(u32);
+impl Future for Fut {
+    type Output = u32;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> { Poll::Pending }
+}
+fn f(fut: Fut) { let a: Pin<Box<dyn Future<Output = bool>>> = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod boxed {
+    use core::{marker::Unsize, ops::CoerceUnsized, pin::Pin};
+    #[lang = "owned_box"]
+    pub struct Box<T: ?Sized>(*mut T);
+    impl<T> Box<T> {
+        pub fn new(x: T) -> Box<T> { loop {} }
+        pub fn pin(x: T) -> Pin<Box<T>> { loop {} }
+    }
+    impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Box<U>> for Box<T> {}
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn test_cfg_test_item_in_test() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+//- /main.rs cfg:test
+#[cfg(test)]
+mod helpers { pub fn make() -> u32 { 0 } }
+mod tests {
+    use crate::helpers::make;
+    #[test]
+    fn t() { let a: u32 = todo$0!(); }
+}
+"#,
+            r#"#[cfg(test)]
+mod helpers { pub fn make() -> u32 { 0 } }
+mod tests {
+    use crate::helpers::make;
+    #[test]
+    fn t() { let a: u32 = make(); }
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn test_cfg_test_item_in_test_module() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+//- /main.rs cfg:test
+#[cfg(test)]
+mod tests {
+    fn make() -> u32 { 0 }
+    fn helper() { let a: u32 = todo$0!(); }
+}
+"#,
+            r#"#[cfg(test)]
+mod tests {
+    fn make() -> u32 { 0 }
+    fn helper() { let a: u32 = make(); }
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn test_cfg_test_item_outside_of_test() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+//- /main.rs cfg:test
+#[cfg(test)]
+mod helpers { pub fn make() -> u32 { 0 } }
+use helpers::make;
+fn f() { let a: u32 = todo$0!(); }
+"#,
+        )
+    }
+
+    #[test]
+    fn test_into_conversion() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, from
+struct A(u32);
+struct B(u32);
+impl From<A> for B { fn from(a: A) -> B { B(a.0) } }
+fn f(a: A) { let b: B = todo$0!(); }"#,
+            r#"struct A(u32);
+struct B(u32);
+impl From<A> for B { fn from(a: A) -> B { B(a.0) } }
+fn f(a: A) { let b: B = Into::<B>::into(a); }"#,
+        )
+    }
+
+    #[test]
+    fn test_into_conversion_chains() {
+        let term_search_chains: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { enable_conversion_chains: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented, from
+struct A(u32);
+struct B(u32);
+struct C(u32);
+trait Tag {}
+impl Tag for A {}
+impl<T: Tag> From<T> for B { fn from(_: T) -> B { B(0) } }
+impl From<B> for C { fn from(b: B) -> C { C(b.0) } }
+fn f(a: A) { let c: C = todo$0!(); }"#;
+        check_assist_not_applicable(term_search, before);
+        check_assist_by_label(
+            term_search_chains,
+            before,
+            r#"struct A(u32);
+struct B(u32);
+struct C(u32);
+trait Tag {}
+impl Tag for A {}
+impl<T: Tag> From<T> for B { fn from(_: T) -> B { B(0) } }
+impl From<B> for C { fn from(b: B) -> C { C(b.0) } }
+fn f(a: A) { let c: C = Into::<C>::into(Into::<B>::into(a)); }"#,
+            "Replace todo!() with Into::<C>::into(Into::<B>::into(a))",
+        );
+    }
+
+    #[test]
+    fn test_raw_pointer_cast() {
+        let term_search_raw_pointers: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { enable_raw_pointers: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented
+fn f(a: i32, r: &i32) { let p: *const i32 = todo$0!(); }"#;
+        check_assist_not_applicable(term_search, before);
+        check_assist_by_label(
+            term_search_raw_pointers,
+            before,
+            r#"fn f(a: i32, r: &i32) { let p: *const i32 = r as *const i32; }"#,
+            "Replace todo!() with r as *const i32",
+        );
+        check_assist_by_label(
+            term_search_raw_pointers,
+            before,
+            r#"fn f(a: i32, r: &i32) { let p: *const i32 = &a as *const i32; }"#,
+            "Replace todo!() with &a as *const i32",
+        );
+        check_assist_not_applicable(
+            term_search_raw_pointers,
+            r#"//- minicore: todo, unimplemented
+fn f(a: i32, r: &i32) { let p: *mut i32 = todo$0!(); }"#,
+        );
+        check_assist(
+            term_search_raw_pointers,
+            r#"//- minicore: todo, unimplemented
+fn f(mut a: i32) { let p: *mut i32 = todo$0!(); }"#,
+            r#"fn f(mut a: i32) { let p: *mut i32 = &mut a as *mut i32; }"#,
+        );
+    }
+
+    #[test]
+    fn test_prelude_items_still_used() {
+        let before = r#"//- minicore: todo, unimplemented, option, drop
+fn wrap(a: i32) -> Option<i32> { Some(a) }
+fn f(a: i32) { let o: Option<i32> = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"fn wrap(a: i32) -> Option<i32> { Some(a) }
+fn f(a: i32) { let o: Option<i32> = Some(a); }"#,
+            "Replace todo!() with Some(a)",
+        );
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"fn wrap(a: i32) -> Option<i32> { Some(a) }
+fn f(a: i32) { let o: Option<i32> = wrap(a); }"#,
+            "Replace todo!() with wrap(a)",
+        );
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"fn wrap(a: i32) -> Option<i32> { Some(a) }
+fn f(a: i32) { let o: Option<i32> = None; }"#,
+            "Replace todo!() with None",
+        );
+    }
+
+    #[test]
+    fn test_lifetimes_do_not_block_unification() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+const NAME: &'static str = "";
+fn f<'a>(a: &'a u8) -> &'a str { todo$0!() }"#,
+            r#"const NAME: &'static str = "";
+fn f<'a>(a: &'a u8) -> &'a str { NAME }"#,
+        );
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+struct S<'a>(&'a u32);
+fn f<'a, 'b>(a: S<'a>) { let b: S<'b> = todo$0!(); }"#,
+            r#"struct S<'a>(&'a u32);
+fn f<'a, 'b>(a: S<'a>) { let b: S<'b> = a; }"#,
+        );
+
+        let term_search_validated: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { validate: true, ..Default::default() },
+            )
+        };
+        check_assist_by_label(
+            term_search_validated,
+            r#"//- minicore: todo, unimplemented
+const NAME: &'static str = "";
+fn g(s: &str) -> u32 { 0 }
+fn f() { let b: u32 = todo$0!(); }"#,
+            r#"const NAME: &'static str = "";
+fn g(s: &str) -> u32 { 0 }
+fn f() { let b: u32 = g(NAME); }"#,
+            "Replace todo!() with g(NAME)",
+        );
+    }
+
+    #[test]
+    fn test_result_ok() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, result
+struct Error;
+fn f(a: i32) { let r: Result<i32, Error> = todo$0!(); }"#,
+            r#"struct Error;
+fn f(a: i32) { let r: Result<i32, Error> = Ok(a); }"#,
+            "Replace todo!() with Ok(a)",
+        );
+    }
+
+    #[test]
+    fn test_result_unwrap_or_else() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, result, fn
+struct Error;
+fn f(r: Result<i32, Error>, a: i32) { let x: i32 = todo$0!(); }"#,
+            r#"struct Error;
+fn f(r: Result<i32, Error>, a: i32) { let x: i32 = r.unwrap_or_else(|_| a); }"#,
+            "Replace todo!() with r.unwrap_or_else(|_| a)",
+        );
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, option, fn
+fn fallback() -> i32 { 0 }
+fn f(o: Option<i32>) { let x: i32 = todo$0!(); }"#,
+            r#"fn fallback() -> i32 { 0 }
+fn f(o: Option<i32>) { let x: i32 = o.unwrap_or_else(|| fallback()); }"#,
+            "Replace todo!() with o.unwrap_or_else(|| fallback())",
+        );
+    }
+
+    #[test]
+    fn test_result_unwrap_or_else_fallback_uses_receiver() {
+        let term_search_panicking: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { enable_panicking: true, ..Default::default() },
+            )
+        };
+        check_assist_not_applicable_by_label(
+            term_search_panicking,
+            r#"//- minicore: todo, unimplemented, result, fn, try_from
+struct Error;
+fn f(r: Result<i32, Error>) { let x: i32 = todo$0!(); }"#,
+            "Replace todo!() with r.unwrap_or_else(|_| r.unwrap())",
+        );
+    }
+
+    #[test]
+    fn test_generics_implicit_bounds() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, phantom_data, sized
+use core::marker::PhantomData;
+struct Tag<T: ?Sized, U>(U, PhantomData<T>);
+fn tag<T, U>(u: U) -> Tag<T, U> { Tag(u, PhantomData) }
+fn f(a: u32) { let t: Tag<str, u32> = todo$0!(); }"#,
+        );
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, phantom_data, sized
+use core::marker::PhantomData;
+struct Tag<T: ?Sized, U>(U, PhantomData<T>);
+fn tag<T: ?Sized, U>(u: U) -> Tag<T, U> { Tag(u, PhantomData) }
+fn f(a: u32) { let t: Tag<str, u32> = todo$0!(); }"#,
+            r#"use core::marker::PhantomData;
+struct Tag<T: ?Sized, U>(U, PhantomData<T>);
+fn tag<T: ?Sized, U>(u: U) -> Tag<T, U> { Tag(u, PhantomData) }
+fn f(a: u32) { let t: Tag<str, u32> = tag(a); }"#,
+        );
+    }
+
+    #[test]
+    fn test_generics_supertrait_bounds() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, phantom_data
+use core::marker::PhantomData;
+trait Super {}
+trait Sub: Super {}
+struct A;
+struct B;
+impl Super for A {}
+impl Sub for A {}
+impl Super for B {}
+struct Tag<T, U>(U, PhantomData<T>);
+fn tag<T: Sub, U>(u: U) -> Tag<T, U> { Tag(u, PhantomData) }
+fn f(a: u32) { let t: Tag<B, u32> = todo$0!(); }"#,
+        );
+        // `X: Super` is implied by `X: Sub`
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, phantom_data
+use core::marker::PhantomData;
+trait Super {}
+trait Sub: Super {}
+struct Tag<T, U>(U, PhantomData<T>);
+fn tag<T: Super, U>(u: U) -> Tag<T, U> { Tag(u, PhantomData) }
+fn f<X: Sub>(a: u32) { let t: Tag<X, u32> = todo$0!(); }"#,
+            r#"use core::marker::PhantomData;
+trait Super {}
+trait Sub: Super {}
+struct Tag<T, U>(U, PhantomData<T>);
+fn tag<T: Super, U>(u: U) -> Tag<T, U> { Tag(u, PhantomData) }
+fn f<X: Sub>(a: u32) { let t: Tag<X, u32> = tag(a); }"#,
+        );
+    }
+
+    #[test]
+    fn test_prefer_method_syntax() {
+        // The last suggestion is the worst ranked one
+        let before = r#"//- minicore: todo, unimplemented
+struct S(u32);
+impl S { fn size(self) -> u64 { 0 } }
+fn size_of(s: S) -> u64 { 0 }
+fn f(a: S) { let b: u64 = todo$0!(); }"#;
+        check_assist(
+            term_search,
+            before,
+            r#"struct S(u32);
+impl S { fn size(self) -> u64 { 0 } }
+fn size_of(s: S) -> u64 { 0 }
+fn f(a: S) { let b: u64 = a.size(); }"#,
+        );
+        check_assist(
+            |acc, ctx| {
+                term_search_with_config(
+                    acc,
+                    ctx,
+                    TermSearchConfig { prefer_method_syntax: true, ..Default::default() },
+                )
+            },
+            before,
+            r#"struct S(u32);
+impl S { fn size(self) -> u64 { 0 } }
+fn size_of(s: S) -> u64 { 0 }
+fn f(a: S) { let b: u64 = size_of(a); }"#,
+        );
+    }
+
+    #[test]
+    fn test_unit_struct_goal() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+struct Unit;
+fn f() { let u: Unit = todo$0!(); }"#,
+            r#"struct Unit;
+fn f() { let u: Unit = Unit; }"#,
+        );
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+mod m { pub struct Unit; }
+use m::Unit;
+fn f() { let u: Unit = todo$0!(); }"#,
+            r#"mod m { pub struct Unit; }
+use m::Unit;
+fn f() { let u: Unit = Unit; }"#,
+        );
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+mod m { pub struct Unit(()); }
+use m::Unit;
+fn f() { let u: Unit = todo$0!(); }"#,
+        );
+    }
+
+    #[test]
+    fn test_const_of_goal_type() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+struct Config(u32);
+const DEFAULT: Config = Config(0);
+fn f() { let c: Config = todo$0!(); }"#,
+            r#"struct Config(u32);
+const DEFAULT: Config = Config(0);
+fn f() { let c: Config = DEFAULT; }"#,
+        );
+    }
+
+    #[test]
+    fn test_exhaustive_module_walk() {
+        let before = r#"//- minicore: todo, unimplemented
+mod foo {
+    pub struct Foo { a: u32 }
+}
+mod builders {
+    pub fn make_foo() -> super::foo::Foo { loop {} }
+}
+fn f() { let a: foo::Foo = todo$0!(); }"#;
+        check_assist_not_applicable(term_search, before);
+        check_assist(
+            |acc, ctx| {
+                term_search_with_config(
+                    acc,
+                    ctx,
+                    TermSearchConfig { exhaustive_module_walk: true, ..Default::default() },
+                )
+            },
+            before,
+            r#"mod foo {
+    pub struct Foo { a: u32 }
+}
+mod builders {
+    pub fn make_foo() -> super::foo::Foo { loop {} }
+}
+fn f() { let a: foo::Foo = builders::make_foo(); }"#,
+        );
+    }
+
+    #[test]
+    fn test_impl_trait_argument() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait Display {}
+impl Display for i32 {}
+struct S(u8);
+fn show(x: impl Display) -> S { S(0) }
+fn f(a: i32) { let s: S = todo$0!(); }"#,
+            r#"trait Display {}
+impl Display for i32 {}
+struct S(u8);
+fn show(x: impl Display) -> S { S(0) }
+fn f(a: i32) { let s: S = show(a); }"#,
+        );
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait Display {}
+struct S(u8);
+fn show(x: impl Display) -> S { S(0) }
+fn f(a: i32) { let s: S = todo$0!(); }"#,
+        );
+    }
+
+    #[test]
+    fn test_impl_trait_arguments_of_method() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait Display {}
+struct A(u8);
+struct B(u8);
+impl Display for A {}
+impl Display for B {}
+struct S(u8);
+struct T(u8);
+impl T { fn show(&self, x: impl Display, y: impl Display) -> S { loop {} } }
+fn f(a: A, b: B, t: T) { let s: S = todo$0!(); }"#,
+            r#"trait Display {}
+struct A(u8);
+struct B(u8);
+impl Display for A {}
+impl Display for B {}
+struct S(u8);
+struct T(u8);
+impl T { fn show(&self, x: impl Display, y: impl Display) -> S { loop {} } }
+fn f(a: A, b: B, t: T) { let s: S = t.show(a, b); }"#,
+            "Replace todo!() with t.show(a, b)",
+        );
+        // Only the explicit generics are spelled out
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait Display {}
+struct A(u8);
+impl Display for A {}
+struct S(u8);
+struct T(u8);
+impl T { fn show<U>(&self, x: impl Display) -> U { loop {} } }
+fn f(a: A, t: T) { let s: S = todo$0!(); }"#,
+            r#"trait Display {}
+struct A(u8);
+impl Display for A {}
+struct S(u8);
+struct T(u8);
+impl T { fn show<U>(&self, x: impl Display) -> U { loop {} } }
+fn f(a: A, t: T) { let s: S = t.show::<S>(a); }"#,
+            "Replace todo!() with t.show::<S>(a)",
+        );
+    }
+
+    #[test]
+    fn test_exhausted_types_still_used() {
+        // Types still get used for as many rounds as allowed
+        let term_search_exhausting: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { max_idle_type_rounds: Some(2), ..Default::default() },
+            )
+        };
+        check_assist_by_label(
+            term_search_exhausting,
+            r#"//- minicore: todo, unimplemented
+mod w {
+    pub struct W<T>(T);
+    pub fn wrap<T>(t: T) -> W<T> { W(t) }
+}
+use w::{wrap, W};
+struct A(u8);
+fn f(a: A) { let g: W<W<A>> = todo$0!(); }"#,
+            r#"mod w {
+    pub struct W<T>(T);
+    pub fn wrap<T>(t: T) -> W<T> { W(t) }
+}
+use w::{wrap, W};
+struct A(u8);
+fn f(a: A) { let g: W<W<A>> = wrap(wrap(a)); }"#,
+            "Replace todo!() with wrap(wrap(a))",
+        );
+    }
+
+    #[test]
+    fn test_qualified_operator_output_goal() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, add, builtin_impls
+use core::ops::Add;
+fn f(a: i32) { let b: <i32 as Add>::Output = todo$0!(); }"#,
+            r#"use core::ops::Add;
+fn f(a: i32) { let b: <i32 as Add>::Output = a; }"#,
+        );
+        // Return types spelled as `Self::Output` match the concrete type too
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, add
+mod num {
+    pub struct V(u8);
+    pub struct R(u8);
+    pub struct W(u8);
+    impl core::ops::Add<R> for V {
+        type Output = W;
+        fn add(self, rhs: R) -> Self::Output { W(self.0 + rhs.0) }
+    }
+}
+use core::ops::Add;
+use num::{R, V};
+fn f(a: V, r: R) { let w: <V as Add<R>>::Output = todo$0!(); }"#,
+            r#"mod num {
+    pub struct V(u8);
+    pub struct R(u8);
+    pub struct W(u8);
+    impl core::ops::Add<R> for V {
+        type Output = W;
+        fn add(self, rhs: R) -> Self::Output { W(self.0 + rhs.0) }
+    }
+}
+use core::ops::Add;
+use num::{R, V};
+fn f(a: V, r: R) { let w: <V as Add<R>>::Output = Add::add(a, r); }"#,
+        );
+    }
+
+    #[test]
+    fn test_unsafe_fn_only_when_allowed() {
+        let before = r#"//- minicore: todo, unimplemented
+mod foo {
+    pub struct Foo { a: u32 }
+    pub unsafe fn make_foo() -> Foo { loop {} }
+}
+use foo::{make_foo, Foo};
+fn f() { let a: Foo = todo$0!(); }"#;
+        check_assist_not_applicable(term_search, before);
+        check_assist(
+            |acc, ctx| {
+                term_search_with_config(
+                    acc,
+                    ctx,
+                    TermSearchConfig { allow_unsafe: true, ..Default::default() },
+                )
+            },
+            before,
+            r#"mod foo {
+    pub struct Foo { a: u32 }
+    pub unsafe fn make_foo() -> Foo { loop {} }
+}
+use foo::{make_foo, Foo};
+fn f() { let a: Foo = unsafe { make_foo() }; }"#,
+        );
+    }
+
+    #[test]
+    fn test_static_mut_only_when_allowed() {
+        let before = r#"//- minicore: todo, unimplemented
+static mut COUNTER: u32 = 0;
+fn f() { let a: u32 = todo$0!(); }"#;
+        check_assist_not_applicable(term_search, before);
+        check_assist(
+            |acc, ctx| {
+                term_search_with_config(
+                    acc,
+                    ctx,
+                    TermSearchConfig { allow_unsafe: true, ..Default::default() },
+                )
+            },
+            before,
+            r#"static mut COUNTER: u32 = 0;
+fn f() { let a: u32 = unsafe { COUNTER }; }"#,
+        );
+    }
+
+    #[test]
+    fn test_tuple_heterogeneous() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+mod m { pub struct S(u8); }
+fn f(a: i32, s: m::S, b: f64) { let t: (i32, m::S, f64) = todo$0!(); }"#,
+            r#"mod m { pub struct S(u8); }
+fn f(a: i32, s: m::S, b: f64) { let t: (i32, m::S, f64) = (a, s, b); }"#,
+        )
+    }
+
+    #[test]
+    fn test_tuple_does_not_move_local_twice() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+mod m { pub struct S(u8); }
+fn f(s: m::S) { let t: (m::S, m::S) = todo$0!(); }"#,
+        );
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+mod m { pub struct S(u8); }
+fn f(s: m::S) { let t: (m::S, &m::S) = todo$0!(); }"#,
+        );
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+#[derive(Clone, Copy)]
+struct S(u8);
+fn f(s: S) { let t: (S, S) = todo$0!(); }"#,
+            r#"#[derive(Clone, Copy)]
+struct S(u8);
+fn f(s: S) { let t: (S, S) = (s, s); }"#,
+        );
+    }
+
+    #[test]
+    fn test_tuple_too_wide() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+fn f(a: i32) { let t: (i32, i32, i32, i32, i32) = todo$0!(); }"#,
+        );
+    }
+
+    #[test]
+    fn test_forbidden_types() {
+        fn term_search_forbidden(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<()> {
+            let parent = ctx.find_node_at_offset::<ast::MacroCall>()?.syntax().parent()?;
+            let scope = ctx.sema.scope(&parent)?;
+            let mut forbidden_types = Vec::new();
+            scope.process_all_names(&mut |name, def| match def {
+                hir::ScopeDef::ModuleDef(hir::ModuleDef::Adt(it))
+                    if name.as_str() == Some("Cell") =>
+                {
+                    forbidden_types.push(it.ty(ctx.db()))
+                }
+                _ => (),
+            });
+            let config = TermSearchConfig { forbidden_types, ..Default::default() };
+            term_search_with_config(acc, ctx, config)
+        }
+
+        let before = r#"//- minicore: todo, unimplemented
+mod m {
+    pub struct Cell<T>(T);
+    pub fn get(c: Cell<i32>) -> u32 { 0 }
+    pub fn good() -> u32 { 1 }
+}
+use m::{get, good, Cell};
+fn f(c: Cell<i32>) { let a: u32 = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"mod m {
+    pub struct Cell<T>(T);
+    pub fn get(c: Cell<i32>) -> u32 { 0 }
+    pub fn good() -> u32 { 1 }
+}
+use m::{get, good, Cell};
+fn f(c: Cell<i32>) { let a: u32 = get(c); }"#,
+            "Replace todo!() with get(c)",
+        );
+        check_assist_by_label(
+            term_search_forbidden,
+            before,
+            r#"mod m {
+    pub struct Cell<T>(T);
+    pub fn get(c: Cell<i32>) -> u32 { 0 }
+    pub fn good() -> u32 { 1 }
+}
+use m::{get, good, Cell};
+fn f(c: Cell<i32>) { let a: u32 = good(); }"#,
+            "Replace todo!() with good()",
+        );
+        check_assist_not_applicable_by_label(
+            term_search_forbidden,
+            before,
+            "Replace todo!() with get(c)",
+        );
+    }
+
+    #[test]
+    fn test_first_element_borrowed() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterator
+mod vec {
+    pub struct Vec<T>(T);
+    pub struct Iter<'a, T>(&'a T);
+    impl<T> Vec<T> { pub fn iter(&self) -> Iter<'_, T> { loop {} } }
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+        fn next(&mut self) -> Option<&'a T> { None }
+    }
+}
+fn f(v: vec::Vec<i32>) { let a: Option<&i32> = todo$0!(); }"#,
+            r#"mod vec {
+    pub struct Vec<T>(T);
+    pub struct Iter<'a, T>(&'a T);
+    impl<T> Vec<T> { pub fn iter(&self) -> Iter<'_, T> { loop {} } }
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+        fn next(&mut self) -> Option<&'a T> { None }
+    }
+}
+fn f(v: vec::Vec<i32>) { let a: Option<&i32> = v.iter().next(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_first_element_owned() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterator
+mod vec {
+    pub struct Vec<T>(T);
+    pub struct IntoIter<T>(T);
+    impl<T> IntoIterator for Vec<T> {
+        type Item = T;
+        type IntoIter = IntoIter<T>;
+        fn into_iter(self) -> IntoIter<T> { loop {} }
+    }
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+        fn next(&mut self) -> Option<T> { None }
+    }
+}
+fn f(v: vec::Vec<i32>) { let a: Option<i32> = todo$0!(); }"#,
+            r#"mod vec {
+    pub struct Vec<T>(T);
+    pub struct IntoIter<T>(T);
+    impl<T> IntoIterator for Vec<T> {
+        type Item = T;
+        type IntoIter = IntoIter<T>;
+        fn into_iter(self) -> IntoIter<T> { loop {} }
+    }
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+        fn next(&mut self) -> Option<T> { None }
+    }
+}
+fn f(v: vec::Vec<i32>) { let a: Option<i32> = IntoIterator::into_iter(v).next(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_first_element_wrong_item_type() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterator
+mod vec {
+    pub struct Vec<T>(T);
+    pub struct Iter<'a, T>(&'a T);
+    impl<T> Vec<T> { pub fn iter(&self) -> Iter<'_, T> { loop {} } }
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+        fn next(&mut self) -> Option<&'a T> { None }
+    }
+}
+fn f(v: vec::Vec<u8>) { let a: Option<&i32> = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_assoc_fn_turbofish_on_receiver() {
+        let before = r#"//- minicore: todo, unimplemented
+mod m {
+    pub struct Stack<T>(T);
+    impl<T> Stack<T> {
+        pub fn new() -> Self { loop {} }
+        pub fn len(&self) -> usize { 0 }
+    }
+}
+fn take(s: m::Stack<i32>) -> usize { 0 }
+fn f() { let n: usize = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"mod m {
+    pub struct Stack<T>(T);
+    impl<T> Stack<T> {
+        pub fn new() -> Self { loop {} }
+        pub fn len(&self) -> usize { 0 }
+    }
+}
+fn take(s: m::Stack<i32>) -> usize { 0 }
+fn f() { let n: usize = m::Stack::<i32>::new().len(); }"#,
+            "Replace todo!() with m::Stack::<i32>::new().len()",
+        );
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"mod m {
+    pub struct Stack<T>(T);
+    impl<T> Stack<T> {
+        pub fn new() -> Self { loop {} }
+        pub fn len(&self) -> usize { 0 }
+    }
+}
+fn take(s: m::Stack<i32>) -> usize { 0 }
+fn f() { let n: usize = take(m::Stack::new()); }"#,
+            "Replace todo!() with take(m::Stack::new())",
+        );
+    }
+
+    #[test]
+    fn test_deref_copy_reference() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, copy, clone
+#[derive(Clone, Copy)]
+struct S(u8);
+fn f(x: &S) { let a: S = todo$0!(); }"#,
+            r#"#[derive(Clone, Copy)]
+struct S(u8);
+fn f(x: &S) { let a: S = *x; }"#,
+        )
+    }
+
+    #[test]
+    fn test_no_deref_of_non_copy_reference() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, copy, clone
+mod m { pub struct S(u8); }
+fn f(x: &m::S) { let a: m::S = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_trait_assoc_fn_from_str() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, result
+mod m {
+    pub trait FromStr: Sized {
+        type Err;
+        fn from_str(s: &str) -> Result<Self, Self::Err>;
+    }
+    pub struct S(u8);
+    pub struct E(u8);
+    impl FromStr for S {
+        type Err = E;
+        fn from_str(_: &str) -> Result<S, E> { Ok(S(0)) }
+    }
+}
+use m::FromStr;
+fn f(text: &str) -> Result<m::S, m::E> { todo$0!() }"#,
+            r#"mod m {
+    pub trait FromStr: Sized {
+        type Err;
+        fn from_str(s: &str) -> Result<Self, Self::Err>;
+    }
+    pub struct S(u8);
+    pub struct E(u8);
+    impl FromStr for S {
+        type Err = E;
+        fn from_str(_: &str) -> Result<S, E> { Ok(S(0)) }
+    }
+}
+use m::FromStr;
+fn f(text: &str) -> Result<m::S, m::E> { m::S::from_str(text) }"#,
+        )
+    }
+
+    #[test]
+    fn test_trait_provided_assoc_fn() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+mod m {
+    pub trait Make: Sized {
+        fn make(n: u8) -> Self;
+        fn make_twice(n: u8) -> (Self, Self) { (Self::make(n), Self::make(n)) }
+    }
+    pub struct S(u8);
+    impl Make for S {
+        fn make(n: u8) -> S { S(n) }
+    }
+}
+use m::Make;
+fn f(n: u8) -> (m::S, m::S) { todo$0!() }"#,
+            r#"mod m {
+    pub trait Make: Sized {
+        fn make(n: u8) -> Self;
+        fn make_twice(n: u8) -> (Self, Self) { (Self::make(n), Self::make(n)) }
+    }
+    pub struct S(u8);
+    impl Make for S {
+        fn make(n: u8) -> S { S(n) }
+    }
+}
+use m::Make;
+fn f(n: u8) -> (m::S, m::S) { m::S::make_twice(n) }"#,
+            "Replace todo!() with m::S::make_twice(n)",
+        )
+    }
+
+    #[test]
+    fn test_trait_provided_assoc_fn_trait_not_in_scope() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+mod m {
+    pub trait Make: Sized {
+        fn make(n: u8) -> Self;
+        fn make_twice(n: u8) -> (Self, Self) { (Self::make(n), Self::make(n)) }
+    }
+    pub struct S(u8);
+    impl Make for S {
+        fn make(n: u8) -> S { S(n) }
+    }
+}
+fn f(n: u8) -> (m::S, m::S) { todo$0!() }"#,
+            r#"mod m {
+    pub trait Make: Sized {
+        fn make(n: u8) -> Self;
+        fn make_twice(n: u8) -> (Self, Self) { (Self::make(n), Self::make(n)) }
+    }
+    pub struct S(u8);
+    impl Make for S {
+        fn make(n: u8) -> S { S(n) }
+    }
+}
+fn f(n: u8) -> (m::S, m::S) { <m::S as m::Make>::make_twice(n) }"#,
+            "Replace todo!() with <m::S as m::Make>::make_twice(n)",
+        )
+    }
+
+    #[test]
+    fn test_exhaustive_goal() {
+        let term_search_exhaustive: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig {
+                    exhaustive_goal: true,
+                    max_solutions: Some(5),
+                    ..Default::default()
+                },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented
+struct S(u8);
+fn wrap(s: S) -> S { s }
+fn f(a: S, b: S, c: S) { let s: S = todo$0!(); }"#;
+        check_assist_not_applicable_by_label(term_search, before, "Replace todo!() with wrap(b)");
+        for code in ["a", "b", "c", "wrap(a)", "wrap(b)"] {
+            check_assist_by_label(
+                term_search_exhaustive,
+                before,
+                &format!(
+                    "struct S(u8);\nfn wrap(s: S) -> S {{ s }}\nfn f(a: S, b: S, c: S) {{ let s: S = {code}; }}"
+                ),
+                &format!("Replace todo!() with {code}"),
+            );
+        }
+        check_assist_not_applicable_by_label(
+            term_search_exhaustive,
+            before,
+            "Replace todo!() with wrap(c)",
+        );
+    }
+
+    #[test]
+    fn test_trait_provided_method_turbofish() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, option
+mod m {
+    pub trait FromIter<A> {
+        fn from_iter<I: Iter<Item = A>>(iter: I) -> Self;
+    }
+    pub trait Iter {
+        type Item;
+        fn next(&mut self) -> Option<Self::Item>;
+        fn collect<B: FromIter<Self::Item>>(self) -> B
+        where
+            Self: Sized,
+        {
+            B::from_iter(self)
+        }
+    }
+    pub struct Bag(u8);
+    impl FromIter<u8> for Bag {
+        fn from_iter<I: Iter<Item = u8>>(_: I) -> Self { Bag(0) }
+    }
+    pub struct Items(u8);
+    impl Iter for Items {
+        type Item = u8;
+        fn next(&mut self) -> Option<u8> { None }
+    }
+    pub struct Store(u8);
+    impl Store {
+        pub fn items(&self) -> Items { Items(self.0) }
+    }
+}
+use m::Iter;
+fn f(s: m::Store) -> m::Bag { todo$0!() }"#,
+            r#"mod m {
+    pub trait FromIter<A> {
+        fn from_iter<I: Iter<Item = A>>(iter: I) -> Self;
+    }
+    pub trait Iter {
+        type Item;
+        fn next(&mut self) -> Option<Self::Item>;
+        fn collect<B: FromIter<Self::Item>>(self) -> B
+        where
+            Self: Sized,
+        {
+            B::from_iter(self)
+        }
+    }
+    pub struct Bag(u8);
+    impl FromIter<u8> for Bag {
+        fn from_iter<I: Iter<Item = u8>>(_: I) -> Self { Bag(0) }
+    }
+    pub struct Items(u8);
+    impl Iter for Items {
+        type Item = u8;
+        fn next(&mut self) -> Option<u8> { None }
+    }
+    pub struct Store(u8);
+    impl Store {
+        pub fn items(&self) -> Items { Items(self.0) }
+    }
+}
+use m::Iter;
+fn f(s: m::Store) -> m::Bag { s.items().collect::<m::Bag>() }"#,
+        )
+    }
+
+    #[test]
+    fn test_vec_macro_single_element() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+//- /main.rs crate:main deps:alloc
+use alloc::{vec, vec::Vec};
+fn f(n: i32) { let v: Vec<i32> = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod vec {
+    pub struct Vec<T>(T);
+}
+#[macro_export]
+macro_rules! vec {
+    ($($x:expr),*) => { $crate::vec::Vec($($x),*) };
+}
+"#,
+            r#"use alloc::{vec, vec::Vec};
+fn f(n: i32) { let v: Vec<i32> = vec![n]; }
+"#,
+        )
+    }
+
+    #[test]
+    fn test_vec_macro_not_in_scope() {
+        check_assist_not_applicable_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+//- /main.rs crate:main deps:alloc
+use alloc::vec::Vec;
+fn f(n: i32) { let v: Vec<i32> = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod vec {
+    pub struct Vec<T>(T);
+}
+#[macro_export]
+macro_rules! vec {
+    ($($x:expr),*) => { $crate::vec::Vec($($x),*) };
+}
+"#,
+            "Replace todo!() with vec![n]",
+        )
+    }
+
+    #[test]
+    fn test_index_vec() {
+        let term_search_panicking: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { enable_panicking: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented, index
+//- /main.rs crate:main deps:alloc
+use alloc::vec::Vec;
+fn f(v: Vec<i32>, i: usize) { let x: i32 = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod vec {
+    pub struct Vec<T>(T);
+    impl<T> core::ops::Index<usize> for Vec<T> {
+        type Output = T;
+        fn index(&self, index: usize) -> &T { &self.0 }
+    }
+}
+"#;
+        check_assist_not_applicable_by_label(term_search, before, "Replace todo!() with v[i]");
+        check_assist_by_label(
+            term_search_panicking,
+            before,
+            r#"use alloc::vec::Vec;
+fn f(v: Vec<i32>, i: usize) { let x: i32 = v[i]; }
+"#,
+            "Replace todo!() with v[i]",
+        );
+    }
+
+    #[test]
+    fn test_singleton_with_other_values() {
+        let before = r#"//- minicore: todo, unimplemented
+struct S;
+fn make() -> S { S }
+fn f(s: S) { let a: S = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"struct S;
+fn make() -> S { S }
+fn f(s: S) { let a: S = s; }"#,
+            "Replace todo!() with s",
+        );
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"struct S;
+fn make() -> S { S }
+fn f(s: S) { let a: S = make(); }"#,
+            "Replace todo!() with make()",
+        );
+    }
+
+    #[test]
+    fn test_max_distinct_calls() {
+        let term_search_limited: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { max_distinct_calls: Some(2), ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented
+mod m {
+    pub struct A(u8); pub struct B(u8); pub struct C(u8); pub struct D(u8);
+    pub fn a(n: u8) -> A { A(n) }
+    pub fn b(x: A) -> B { B(x.0) }
+    pub fn c(x: B) -> C { C(x.0) }
+    pub fn d(x: C) -> D { D(x.0) }
+}
+use m::{a, b, c, d, D};
+fn f(n: u8) { let r: D = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"mod m {
+    pub struct A(u8); pub struct B(u8); pub struct C(u8); pub struct D(u8);
+    pub fn a(n: u8) -> A { A(n) }
+    pub fn b(x: A) -> B { B(x.0) }
+    pub fn c(x: B) -> C { C(x.0) }
+    pub fn d(x: C) -> D { D(x.0) }
+}
+use m::{a, b, c, d, D};
+fn f(n: u8) { let r: D = d(c(b(a(n)))); }"#,
+            "Replace todo!() with d(c(b(a(n))))",
+        );
+        check_assist_not_applicable(term_search_limited, before);
+    }
+
+    #[test]
+    fn test_option_as_deref() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, option, deref
+//- /main.rs crate:main deps:alloc
+use alloc::string::String;
+fn f(a: Option<String>) { let b: Option<&str> = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod string {
+    pub struct String(u8);
+    impl core::ops::Deref for String {
+        type Target = str;
+        fn deref(&self) -> &str { loop {} }
+    }
+}
+"#,
+            r#"use alloc::string::String;
+fn f(a: Option<String>) { let b: Option<&str> = a.as_deref(); }
+"#,
+            "Replace todo!() with a.as_deref()",
+        )
+    }
+
+    #[test]
+    fn test_option_as_ref() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, option, deref
+fn f(a: Option<i32>) { let b: Option<&i32> = todo$0!(); }"#,
+            r#"fn f(a: Option<i32>) { let b: Option<&i32> = a.as_ref(); }"#,
+            "Replace todo!() with a.as_ref()",
+        )
+    }
+
+    #[test]
+    fn test_same_named_trait_methods_disambiguated() {
+        let before = r#"//- minicore: todo, unimplemented
+struct S(i8);
+struct W(i8);
+impl W { fn s(&self) -> S { S(0) } }
+trait A { fn foo(&self) -> u8; }
+trait B { fn foo(&self) -> u8; }
+impl A for S { fn foo(&self) -> u8 { 1 } }
+impl B for S { fn foo(&self) -> u8 { 2 } }
+fn f(w: W) { let n: u8 = todo$0!(); }"#;
+        let after = |code| {
+            format!(
+                r#"struct S(i8);
+struct W(i8);
+impl W {{ fn s(&self) -> S {{ S(0) }} }}
+trait A {{ fn foo(&self) -> u8; }}
+trait B {{ fn foo(&self) -> u8; }}
+impl A for S {{ fn foo(&self) -> u8 {{ 1 }} }}
+impl B for S {{ fn foo(&self) -> u8 {{ 2 }} }}
+fn f(w: W) {{ let n: u8 = {code}; }}"#
+            )
+        };
+        for code in ["A::foo(&w.s())", "B::foo(&w.s())"] {
+            check_assist_by_label(
+                term_search,
+                before,
+                &after(code),
+                &format!("Replace todo!() with {code}"),
+            );
+        }
+        check_assist_not_applicable_by_label(
+            term_search,
+            before,
+            "Replace todo!() with w.s().foo()",
+        );
+    }
+
+    #[test]
+    fn test_try_into_conversion() {
+        let term_search_panicking: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { enable_panicking: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented, try_from
+struct A(u32);
+struct B(u32);
+impl TryFrom<A> for B {
+    type Error = ();
+    fn try_from(a: A) -> Result<B, ()> { Ok(B(a.0)) }
+}
+fn f(a: A) { let b: B = todo$0!(); }"#;
+        check_assist_not_applicable(term_search, before);
+        check_assist_by_label(
+            term_search_panicking,
+            before,
+            r#"struct A(u32);
+struct B(u32);
+impl TryFrom<A> for B {
+    type Error = ();
+    fn try_from(a: A) -> Result<B, ()> { Ok(B(a.0)) }
+}
+fn f(a: A) { let b: B = TryInto::<B>::try_into(a).unwrap(); }"#,
+            "Replace todo!() with TryInto::<B>::try_into(a).unwrap()",
+        );
+    }
+
+    #[test]
+    fn test_try_into_infallible_uses_into() {
+        let term_search_panicking: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { enable_panicking: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented, try_from
+struct A(u32);
+struct B(u32);
+impl From<A> for B { fn from(a: A) -> B { B(a.0) } }
+fn f(a: A) { let b: B = todo$0!(); }"#;
+        check_assist_not_applicable_by_label(
+            term_search_panicking,
+            before,
+            "Replace todo!() with TryInto::<B>::try_into(a).unwrap()",
+        );
+        check_assist_by_label(
+            term_search_panicking,
+            before,
+            r#"struct A(u32);
+struct B(u32);
+impl From<A> for B { fn from(a: A) -> B { B(a.0) } }
+fn f(a: A) { let b: B = Into::<B>::into(a); }"#,
+            "Replace todo!() with Into::<B>::into(a)",
+        );
+    }
+
+    #[test]
+    fn test_settings_blocklist() {
+        let term_search_blocked: Handler = |acc, ctx| {
+            let settings = serde_json::from_str(r#"{ "blocklist": ["crate::foo::bad"] }"#).unwrap();
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig::from_parts(settings, Vec::new(), None, Vec::new()),
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented
+mod foo { pub fn bad() -> u32 { 0 } pub fn good() -> u32 { 1 } }
+use foo::{bad, good};
+fn f() { let a: u32 = todo$0!(); }"#;
+        check_assist_not_applicable_by_label(
+            term_search_blocked,
+            before,
+            "Replace todo!() with bad()",
+        );
+        check_assist_by_label(
+            term_search_blocked,
+            before,
+            r#"mod foo { pub fn bad() -> u32 { 0 } pub fn good() -> u32 { 1 } }
+use foo::{bad, good};
+fn f() { let a: u32 = good(); }"#,
+            "Replace todo!() with good()",
+        );
+    }
+
+    #[test]
+    fn test_array_of_trait_objects() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, fmt
+use core::fmt::{self, Display};
+struct A(u8);
+struct B(u8);
+impl Display for A { fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { todo!() } }
+impl Display for B { fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { todo!() } }
+fn f(a: A, b: B) { let x: [&dyn Display; 2] = todo$0!(); }"#,
+            r#"use core::fmt::{self, Display};
+struct A(u8);
+struct B(u8);
+impl Display for A { fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { todo!() } }
+impl Display for B { fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { todo!() } }
+fn f(a: A, b: B) { let x: [&dyn Display; 2] = [&a, &b]; }"#,
+        )
+    }
+
+    #[test]
+    fn test_array_literal_no_moved_local_twice() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+struct A(u8);
+fn f(a: A) { let x: [A; 2] = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_type_param_goal_default() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, default
+fn f<T: Default>() -> T { todo$0!() }"#,
+            r#"fn f<T: Default>() -> T { T::default() }"#,
+            "Replace todo!() with T::default()",
+        )
+    }
+
+    #[test]
+    fn test_type_param_goal_without_bound() {
+        check_assist_not_applicable_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, default
+fn f<T>() -> T { todo$0!() }"#,
+            "Replace todo!() with T::default()",
+        )
+    }
+
+    #[test]
+    fn test_type_param_bound_method() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait Len { fn len(&self) -> usize; }
+fn f<T: Len>(t: T) -> usize { todo$0!() }"#,
+            r#"trait Len { fn len(&self) -> usize; }
+fn f<T: Len>(t: T) -> usize { t.len() }"#,
+        )
+    }
+
+    #[test]
+    fn test_move_out_of_owned_box() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+struct S(String);
+fn f(b: Box<S>) { let a: S = todo$0!(); }"#,
+            r#"#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+struct S(String);
+fn f(b: Box<S>) { let a: S = *b; }"#,
+        )
+    }
+
+    #[test]
+    fn test_clone_out_of_borrowed_box() {
+        let before = r#"//- minicore: todo, unimplemented, clone
+#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+#[derive(Clone)]
+struct S(String);
+fn f(b: &Box<S>) { let a: S = todo$0!(); }"#;
+        check_assist_not_applicable_by_label(term_search, before, "Replace todo!() with **b");
+        check_assist(
+            term_search,
+            before,
+            r#"#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+#[derive(Clone)]
+struct S(String);
+fn f(b: &Box<S>) { let a: S = Clone::clone(&**b); }"#,
+        )
+    }
+
+    #[test]
+    fn test_borrowed_box_without_clone() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, clone
+#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+struct S(String);
+fn f(b: &Box<S>) { let a: S = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_trait_object_method() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+trait Shape { fn area(&self) -> u32; }
+fn f(s: Box<dyn Shape>) { let a: u32 = todo$0!(); }"#,
+            r#"#[lang = "owned_box"]
+pub struct Box<T: ?Sized>(*mut T);
+trait Shape { fn area(&self) -> u32; }
+fn f(s: Box<dyn Shape>) { let a: u32 = Shape::area(&*s); }"#,
+        )
+    }
+
+    #[test]
+    fn test_trait_object_ref_method() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait Shape { fn area(&self) -> u32; }
+fn f(s: &dyn Shape) { let a: u32 = todo$0!(); }"#,
+            r#"trait Shape { fn area(&self) -> u32; }
+fn f(s: &dyn Shape) { let a: u32 = Shape::area(&*s); }"#,
+        )
+    }
+
+    #[test]
+    fn test_trait_object_generic_method_skipped() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+trait Shape { fn area<T>(&self) -> u32 where Self: Sized; }
+fn f(s: &dyn Shape) { let a: u32 = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_trait_object_sized_method_skipped() {
+        let before = r#"//- minicore: todo, unimplemented, sized
+trait Shape {
+    fn area(&self) -> u32;
+    fn perimeter(&self) -> u32 where Self: Sized;
+}
+fn f(s: &dyn Shape) { let a: u32 = todo$0!(); }"#;
+        check_assist(
+            term_search,
+            before,
+            r#"trait Shape {
+    fn area(&self) -> u32;
+    fn perimeter(&self) -> u32 where Self: Sized;
+}
+fn f(s: &dyn Shape) { let a: u32 = Shape::area(&*s); }"#,
+        );
+        check_assist_not_applicable_by_label(
+            term_search,
+            before,
+            "Replace todo!() with Shape::perimeter(&*s)",
+        );
+    }
+
+    #[test]
+    fn test_array_into_iter() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterator
+struct Total(i32);
+fn total(it: impl Iterator<Item = i32>) -> Total { loop {} }
+fn f(a: [i32; 3]) { let x: Total = todo$0!(); }"#,
+            r#"struct Total(i32);
+fn total(it: impl Iterator<Item = i32>) -> Total { loop {} }
+fn f(a: [i32; 3]) { let x: Total = total(IntoIterator::into_iter(a)); }"#,
+        )
+    }
+
+    #[test]
+    fn test_string_concat() {
+        let before = r#"//- minicore: todo, unimplemented, add, deref
+//- /main.rs crate:main deps:alloc
+use alloc::string::String;
+fn f(a: String, b: String) { let s: String = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod string {
+    pub struct String(u8);
+    impl core::ops::Deref for String {
+        type Target = str;
+        fn deref(&self) -> &str { loop {} }
+    }
+    impl core::ops::Add<&str> for String {
+        type Output = String;
+        fn add(self, other: &str) -> String { self }
+    }
+}
+"#;
+        check_assist_by_label(
+            |acc, ctx| {
+                term_search_with_config(
+                    acc,
+                    ctx,
+                    TermSearchConfig {
+                        enable_string_concat: true,
+                        many_alternatives_threshold: 4,
+                        goal_many_alternatives_threshold: 4,
+                        ..Default::default()
+                    },
+                )
+            },
+            before,
+            r#"use alloc::string::String;
+fn f(a: String, b: String) { let s: String = a + &b; }
+"#,
+            "Replace todo!() with a + &b",
+        );
+        check_assist_not_applicable_by_label(term_search, before, "Replace todo!() with a + &b");
+    }
+
+    #[test]
+    fn test_explicit_deref_of_user_type() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, deref
+use core::ops::Deref;
+struct Inner(u8);
+struct Wrapper { inner: Inner }
+impl Deref for Wrapper {
+    type Target = Inner;
+    fn deref(&self) -> &Inner { &self.inner }
+}
+fn f(w: Wrapper) { let a: &Inner = todo$0!(); }"#,
+            r#"use core::ops::Deref;
+struct Inner(u8);
+struct Wrapper { inner: Inner }
+impl Deref for Wrapper {
+    type Target = Inner;
+    fn deref(&self) -> &Inner { &self.inner }
+}
+fn f(w: Wrapper) { let a: &Inner = w.deref(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_extra_known_values() {
+        fn term_search_known(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<()> {
+            let call = ctx.find_node_at_offset::<ast::MacroCall>()?;
+            let scope = ctx.sema.scope(&call.syntax().parent()?)?;
+            let mut level = None;
+            scope.process_all_names(&mut |name, def| {
+                if let hir::ScopeDef::ModuleDef(hir::ModuleDef::Adt(it)) = def {
+                    if name.as_str() == Some("Level") {
+                        level = Some(it.ty(ctx.db()));
+                    }
+                }
+            });
+            let value = Expr::FamousType { ty: level.clone()?, value: "LOG_LEVEL_DEFAULT" };
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig {
+                    extra_known_values: vec![(level?, value)],
+                    ..Default::default()
+                },
+            )
+        }
+
+        let before = r#"//- minicore: todo, unimplemented
+struct Level(u8);
+struct Logger(u8);
+fn logger(level: Level) -> Logger { Logger(level.0) }
+fn f() { let a: Logger = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search_known,
+            before,
+            r#"struct Level(u8);
+struct Logger(u8);
+fn logger(level: Level) -> Logger { Logger(level.0) }
+fn f() { let a: Logger = logger(LOG_LEVEL_DEFAULT); }"#,
+            "Replace todo!() with logger(LOG_LEVEL_DEFAULT)",
+        );
+        check_assist_not_applicable_by_label(
+            term_search,
+            before,
+            "Replace todo!() with logger(LOG_LEVEL_DEFAULT)",
+        );
+    }
+
+    #[test]
+    fn test_boxed_closure() {
+        // Adds `1` of the parameter type of the closure goal to the known values
+        fn term_search_closure(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<()> {
+            let call = ctx.find_node_at_offset::<ast::MacroCall>()?;
+            let goal = ctx.sema.type_of_expr(&ast::Expr::cast(call.syntax().parent()?)?)?;
+            let goal = goal.adjusted();
+            let object = goal.remove_ref().or_else(|| goal.type_arguments().next())?;
+            let (_, param) = object.as_callable(ctx.db())?.params(ctx.db()).into_iter().next()?;
+            let one = Expr::FamousType { ty: param.clone(), value: "1" };
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig {
+                    enable_integer_add: true,
+                    extra_known_values: vec![(param, one)],
+                    ..Default::default()
+                },
+            )
+        }
+
+        let alloc = r#"
+//- /alloc.rs crate:alloc
+pub mod boxed {
+    use core::{marker::Unsize, ops::CoerceUnsized};
+    #[lang = "owned_box"]
+    pub struct Box<T: ?Sized>(*mut T);
+    impl<T> Box<T> {
+        pub fn new(x: T) -> Box<T> { loop {} }
+    }
+    impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Box<U>> for Box<T> {}
+}"#;
+        let check = |goal: &str, expr: &str| {
+            check_assist_by_label(
+                term_search_closure,
+                &format!(
+                    "//- minicore: todo, unimplemented, fn, add, builtin_impls, coerce_unsized
+//- /main.rs crate:main deps:alloc
+use alloc::boxed::Box;
+fn f(n: i32) {{ let a: {goal} = todo$0!(); }}{alloc}"
+                ),
+                &format!(
+                    "use alloc::boxed::Box;
+fn f(n: i32) {{ let a: {goal} = {expr}; }}
+"
+                ),
+                &format!("Replace todo!() with {expr}"),
+            )
+        };
+        // Closures capturing nothing implement all the closure traits
+        check("Box<dyn Fn(i32) -> i32>", "Box::new(|x| x + 1)");
+        check("Box<dyn FnMut(i32) -> i32>", "Box::new(|x| x + 1)");
+        check("Box<dyn FnOnce(i32) -> i32>", "Box::new(|x| x + 1)");
+        check("&dyn Fn(i32) -> i32", "&|x| x + 1");
+        check("&mut dyn FnMut(i32) -> i32", "&mut |x| x + 1");
+        // Parameters the body does not use are not named
+        check("Box<dyn Fn(i32) -> bool>", "Box::new(|_| true)");
+        // Locals are never captured
+        check_assist_not_applicable_by_label(
+            term_search_closure,
+            &format!(
+                "//- minicore: todo, unimplemented, fn, add, builtin_impls, coerce_unsized
+//- /main.rs crate:main deps:alloc
+use alloc::boxed::Box;
+fn f(n: i32) {{ let a: Box<dyn Fn(i32) -> i32> = todo$0!(); }}{alloc}"
+            ),
+            "Replace todo!() with Box::new(|_| n)",
+        );
+        // Callbacks stored in fields
+        check_assist_by_label(
+            term_search,
+            &format!(
+                "//- minicore: todo, unimplemented, fn, coerce_unsized
+//- /main.rs crate:main deps:alloc
+use alloc::boxed::Box;
+const LIMIT: u32 = 10;
+struct Button {{ on_click: Box<dyn Fn() -> u32> }}
+fn f() {{ let a: Button = todo$0!(); }}{alloc}"
+            ),
+            "use alloc::boxed::Box;
+const LIMIT: u32 = 10;
+struct Button { on_click: Box<dyn Fn() -> u32> }
+fn f() { let a: Button = Button { on_click: Box::new(|| LIMIT) }; }
+",
+            "Replace todo!() with Button { on_click: Box::new(|| LIMIT) }",
+        );
+    }
+
+    #[test]
+    fn test_curried_closure() {
+        fn term_search_curried(acc: &mut Assists, ctx: &AssistContext<'_>) -> Option<()> {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { max_closure_depth: 2, fuel: 1600, ..Default::default() },
+            )
+        }
+
+        let before = r#"//- minicore: todo, unimplemented, fn, copy, builtin_impls, coerce_unsized
+//- /main.rs crate:main deps:alloc
+use alloc::boxed::Box;
+fn scale(a: i32, b: u32) -> i64 { loop {} }
+fn f() { let a: Box<dyn Fn(i32) -> Box<dyn Fn(u32) -> i64>> = todo$0!(); }
+//- /alloc.rs crate:alloc
+pub mod boxed {
+    use core::{marker::Unsize, ops::CoerceUnsized};
+    #[lang = "owned_box"]
+    pub struct Box<T: ?Sized>(*mut T);
+    impl<T> Box<T> {
+        pub fn new(x: T) -> Box<T> { loop {} }
+    }
+    impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<Box<U>> for Box<T> {}
+}"#;
+        // The inner closure captures the parameter of the outer one
+        check_assist_by_label(
+            term_search_curried,
+            before,
+            r#"use alloc::boxed::Box;
+fn scale(a: i32, b: u32) -> i64 { loop {} }
+fn f() { let a: Box<dyn Fn(i32) -> Box<dyn Fn(u32) -> i64>> = Box::new(|x| Box::new(move |y| scale(x, y))); }
+"#,
+            "Replace todo!() with Box::new(|x| Box::new(move |y| scale(x, y)))",
+        );
+        // Closures are not nested by default
+        check_assist_not_applicable_by_label(
+            term_search,
+            before,
+            "Replace todo!() with Box::new(|x| Box::new(move |y| scale(x, y)))",
+        );
+    }
+
+    #[test]
+    fn test_const_param_array_length() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, copy
+fn f<const N: usize>(x: u8) { let a: [u8; N] = todo$0!(); }"#,
+            r#"fn f<const N: usize>(x: u8) { let a: [u8; N] = [x; N]; }"#,
+        )
+    }
+
+    #[test]
+    fn test_uninferrable_array_length() {
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, copy
+const LEN: usize = 2;
+fn f(x: u8) { let a: [u8; MISSING] = todo$0!(); }"#,
+        )
+    }
+
+    #[test]
+    fn test_try_operator_in_result_tail() {
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, result, from
+struct ParseError;
+struct AppError;
+impl From<ParseError> for AppError { fn from(_: ParseError) -> AppError { AppError } }
+struct Config(u8);
+fn parse() -> Result<Config, ParseError> { Err(ParseError) }
+fn load() -> Result<Config, AppError> { todo$0!() }"#,
+            r#"struct ParseError;
+struct AppError;
+impl From<ParseError> for AppError { fn from(_: ParseError) -> AppError { AppError } }
+struct Config(u8);
+fn parse() -> Result<Config, ParseError> { Err(ParseError) }
+fn load() -> Result<Config, AppError> { Ok(parse()?) }"#,
+            "Replace todo!() with Ok(parse()?)",
+        )
+    }
+
+    #[test]
+    fn test_try_operator_not_in_closure() {
+        check_assist_not_applicable_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, result
+struct Config(u8);
+fn parse() -> Result<Config, ()> { Err(()) }
+fn load() -> Result<Config, ()> { let f = || -> Config { todo$0!() }; Ok(f()) }"#,
+            "Replace todo!() with parse()?",
+        )
+    }
+
+    #[test]
+    fn test_transformations_only() {
+        let term_search_transformations: Handler = |acc, ctx| {
+            term_search_with_config(
+                acc,
+                ctx,
+                TermSearchConfig { transformations_only: true, ..Default::default() },
+            )
+        };
+
+        let before = r#"//- minicore: todo, unimplemented
+struct Meters(u32);
+struct Track { length: u32 }
+impl Track { fn meters(&self) -> Meters { Meters(self.length) } }
+fn zero() -> Meters { Meters(0) }
+fn f(track: Track) { let a: Meters = todo$0!(); }"#;
+        check_assist_by_label(
+            term_search,
+            before,
+            r#"struct Meters(u32);
+struct Track { length: u32 }
+impl Track { fn meters(&self) -> Meters { Meters(self.length) } }
+fn zero() -> Meters { Meters(0) }
+fn f(track: Track) { let a: Meters = zero(); }"#,
+            "Replace todo!() with zero()",
+        );
+        check_assist_by_label(
+            term_search_transformations,
+            before,
+            r#"struct Meters(u32);
+struct Track { length: u32 }
+impl Track { fn meters(&self) -> Meters { Meters(self.length) } }
+fn zero() -> Meters { Meters(0) }
+fn f(track: Track) { let a: Meters = track.meters(); }"#,
+            "Replace todo!() with track.meters()",
+        );
+        check_assist_not_applicable_by_label(
+            term_search_transformations,
+            before,
+            "Replace todo!() with zero()",
+        );
+
+        let before = r#"//- minicore: todo, unimplemented
+struct Meters(u32);
+fn f(length: u32) { let a: Meters = todo$0!(); }"#;
+        check_assist(
+            term_search,
+            before,
+            r#"struct Meters(u32);
+fn f(length: u32) { let a: Meters = Meters(length); }"#,
+        );
+        check_assist_not_applicable(term_search_transformations, before);
+    }
+
+    #[test]
+    fn test_builder_chain() {
+        let items = r#"
+mod cfg {
+    pub struct Config { port: u16, verbose: bool }
+    pub struct ConfigBuilder { port: u16, verbose: bool }
+    impl ConfigBuilder {
+        pub fn with_port(self, port: u16) -> Self { ConfigBuilder { port, ..self } }
+        pub fn with_verbose(self, verbose: bool) -> Self { ConfigBuilder { verbose, ..self } }
+        pub fn build(self) -> Config { Config { port: self.port, verbose: self.verbose } }
+    }
+}
+use cfg::{Config, ConfigBuilder};"#;
+        check_assist_by_label(
+            term_search,
+            &format!(
+                "//- minicore: todo, unimplemented{items}
+fn f(b: ConfigBuilder, port: u16) {{ let a: Config = todo$0!(); }}"
+            ),
+            &format!(
+                "{}
+fn f(b: ConfigBuilder, port: u16) {{ let a: Config = b.with_port(port).build(); }}",
+                items.trim_start()
+            ),
+            "Replace todo!() with b.with_port(port).build()",
+        );
+    }
+
+    #[test]
+    fn test_single_variant_enum_payload() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+enum UserId { Id(u32) }
+impl UserId { fn inner(&self) -> u32 { match self { UserId::Id(it) => *it } } }
+fn f(id: UserId) { let a: u32 = todo$0!(); }"#,
+            r#"enum UserId { Id(u32) }
+impl UserId { fn inner(&self) -> u32 { match self { UserId::Id(it) => *it } } }
+fn f(id: UserId) { let a: u32 = id.inner(); }"#,
+        );
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, deref
+enum UserId { Id(u32) }
+impl core::ops::Deref for UserId {
+    type Target = u32;
+    fn deref(&self) -> &u32 { match self { UserId::Id(it) => it } }
+}
+fn f(id: UserId) { let a: u32 = todo$0!(); }"#,
+            r#"enum UserId { Id(u32) }
+impl core::ops::Deref for UserId {
+    type Target = u32;
+    fn deref(&self) -> &u32 { match self { UserId::Id(it) => it } }
+}
+fn f(id: UserId) { let a: u32 = *id; }"#,
+        );
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented
+enum UserId { Id(u32) }
+fn f(id: UserId) { let a: u32 = todo$0!(); }"#,
+        );
+    }
+
+    #[test]
+    fn test_associated_type_goal() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterator
+struct Bag(u32);
+struct BagIter(u32);
+impl Iterator for BagIter {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> { None }
+}
+impl IntoIterator for Bag {
+    type Item = u32;
+    type IntoIter = BagIter;
+    fn into_iter(self) -> Self::IntoIter { BagIter(self.0) }
+}
+fn f(bag: Bag) { let a: <Bag as IntoIterator>::IntoIter = todo$0!(); }"#,
+            r#"struct Bag(u32);
+struct BagIter(u32);
+impl Iterator for BagIter {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> { None }
+}
+impl IntoIterator for Bag {
+    type Item = u32;
+    type IntoIter = BagIter;
+    fn into_iter(self) -> Self::IntoIter { BagIter(self.0) }
+}
+fn f(bag: Bag) { let a: <Bag as IntoIterator>::IntoIter = IntoIterator::into_iter(bag); }"#,
+        );
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterator
+fn f<C: IntoIterator>(items: C) { let a: C::IntoIter = todo$0!(); }"#,
+            r#"fn f<C: IntoIterator>(items: C) { let a: C::IntoIter = IntoIterator::into_iter(items); }"#,
+        );
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterator
+fn f(xs: [i32; 2]) { let a: <[i32; 2] as IntoIterator>::IntoIter = todo$0!(); }"#,
+            r#"fn f(xs: [i32; 2]) { let a: <[i32; 2] as IntoIterator>::IntoIter = IntoIterator::into_iter(xs); }"#,
+        );
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterator
+struct Bag(u32);
+struct BagIter(u32);
+struct Count(usize);
+impl Iterator for BagIter {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> { None }
+}
+impl IntoIterator for Bag {
+    type Item = u32;
+    type IntoIter = BagIter;
+    fn into_iter(self) -> Self::IntoIter { BagIter(self.0) }
+}
+fn count(it: <Bag as IntoIterator>::IntoIter) -> Count { Count(0) }
+fn f(bag: Bag) { let a: Count = todo$0!(); }"#,
+            r#"struct Bag(u32);
+struct BagIter(u32);
+struct Count(usize);
+impl Iterator for BagIter {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> { None }
+}
+impl IntoIterator for Bag {
+    type Item = u32;
+    type IntoIter = BagIter;
+    fn into_iter(self) -> Self::IntoIter { BagIter(self.0) }
+}
+fn count(it: <Bag as IntoIterator>::IntoIter) -> Count { Count(0) }
+fn f(bag: Bag) { let a: Count = count(IntoIterator::into_iter(bag)); }"#,
+        );
+    }
+
+    #[test]
+    fn test_iterator_adapters() {
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterators, fn
+struct Total(u64);
+fn total(it: impl Iterator<Item = u64>) -> Total { loop {} }
+fn widen(x: i32) -> u64 { x as u64 }
+fn f(a: [i32; 3]) { let x: Total = todo$0!(); }"#,
+            r#"struct Total(u64);
+fn total(it: impl Iterator<Item = u64>) -> Total { loop {} }
+fn widen(x: i32) -> u64 { x as u64 }
+fn f(a: [i32; 3]) { let x: Total = total(IntoIterator::into_iter(a).map(widen)); }"#,
+        );
+        check_assist(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterators, fn, clone, builtin_impls
+mod vec {
+    pub struct Vec<T>(T);
+    pub struct Iter<'a, T>(&'a T);
+    impl<T> Vec<T> { pub fn iter(&self) -> Iter<'_, T> { loop {} } }
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+        fn next(&mut self) -> Option<&'a T> { None }
+    }
+}
+struct Total(u64);
+fn total(it: impl Iterator<Item = u64>) -> Total { loop {} }
+fn widen(x: i32) -> u64 { x as u64 }
+fn f(v: vec::Vec<i32>) { let x: Total = todo$0!(); }"#,
+            r#"mod vec {
+    pub struct Vec<T>(T);
+    pub struct Iter<'a, T>(&'a T);
+    impl<T> Vec<T> { pub fn iter(&self) -> Iter<'_, T> { loop {} } }
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+        fn next(&mut self) -> Option<&'a T> { None }
+    }
+}
+struct Total(u64);
+fn total(it: impl Iterator<Item = u64>) -> Total { loop {} }
+fn widen(x: i32) -> u64 { x as u64 }
+fn f(v: vec::Vec<i32>) { let x: Total = total(v.iter().cloned().map(widen)); }"#,
+        );
+        check_assist_by_label(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterators, fn
+struct Total(i32);
+fn total(it: impl Iterator<Item = i32>) -> Total { loop {} }
+fn positive(x: &i32) -> bool { *x > 0 }
+fn f(a: [i32; 3]) { let x: Total = todo$0!(); }"#,
+            r#"struct Total(i32);
+fn total(it: impl Iterator<Item = i32>) -> Total { loop {} }
+fn positive(x: &i32) -> bool { *x > 0 }
+fn f(a: [i32; 3]) { let x: Total = total(IntoIterator::into_iter(a).filter(positive)); }"#,
+            "Replace todo!() with total(IntoIterator::into_iter(a).filter(positive))",
+        );
+        // Only two adapters are chained
+        check_assist_not_applicable(
+            term_search,
+            r#"//- minicore: todo, unimplemented, iterators, fn
+struct Total(u64);
+fn total(it: impl Iterator<Item = u64>) -> Total { loop {} }
+fn to_u8(x: i32) -> u8 { x as u8 }
+fn to_u16(x: u8) -> u16 { x as u16 }
+fn to_u64(x: u16) -> u64 { x as u64 }
+fn f(a: [i32; 3]) { let x: Total = todo$0!(); }"#,
+        );
+    }
 }
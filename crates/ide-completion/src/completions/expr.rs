@@ -354,6 +354,7 @@ pub(crate) fn complete_expr(acc: &mut Completions, ctx: &CompletionContext<'_>)
                 enable_borrowcheck: false,
                 many_alternatives_threshold: 1,
                 fuel: 200,
+                ..Default::default()
             },
         };
         let exprs = hir::term_search::term_search(&term_search_ctx);
@@ -364,8 +365,11 @@ pub(crate) fn complete_expr(acc: &mut Completions, ctx: &CompletionContext<'_>)
                     if target.is_many() =>
                 {
                     let target_ty = target.ty(ctx.db);
-                    let term_search_ctx =
-                        hir::term_search::TermSearchCtx { goal: target_ty, ..term_search_ctx };
+                    let term_search_ctx = hir::term_search::TermSearchCtx {
+                        goal: target_ty,
+                        config: term_search_ctx.config.clone(),
+                        ..term_search_ctx
+                    };
                     let target_exprs = hir::term_search::term_search(&term_search_ctx);
 
                     for expr in target_exprs {
@@ -1851,8 +1851,8 @@ fn f() { A { bar: b$0 }; }
             expect![[r#"
                 fn bar() [type+name]
                 fn baz() [type]
-                ex baz() [type]
                 ex bar() [type]
+                ex baz() [type]
                 ex A { bar: ... }.bar [type]
                 st A []
                 fn f() []
@@ -1991,8 +1991,8 @@ fn main() {
 }
             "#,
             expect![[r#"
-                ex core::ops::Deref::deref(&t) (use core::ops::Deref) [type_could_unify]
-                ex core::ops::Deref::deref(&T(S)) (use core::ops::Deref) [type_could_unify]
+                ex core::ops::Deref::deref(&t) (use core::ops::Deref) [type]
+                ex core::ops::Deref::deref(&T(S)) (use core::ops::Deref) [type]
                 lc m [local]
                 lc t [local]
                 lc &t [type+local]
@@ -2041,8 +2041,8 @@ fn main() {
 }
             "#,
             expect![[r#"
-                ex core::ops::DerefMut::deref_mut(&mut t) (use core::ops::DerefMut) [type_could_unify]
-                ex core::ops::DerefMut::deref_mut(&mut T(S)) (use core::ops::DerefMut) [type_could_unify]
+                ex core::ops::DerefMut::deref_mut(&mut t) (use core::ops::DerefMut) [type]
+                ex core::ops::DerefMut::deref_mut(&mut T(S)) (use core::ops::DerefMut) [type]
                 lc m [local]
                 lc t [local]
                 lc &mut t [type+local]
@@ -2146,8 +2146,8 @@ fn main() {
 }
 "#,
             expect![[r#"
-                ex core::ops::Deref::deref(&T(S)) (use core::ops::Deref) [type_could_unify]
-                ex core::ops::Deref::deref(&bar()) (use core::ops::Deref) [type_could_unify]
+                ex core::ops::Deref::deref(&T(S)) (use core::ops::Deref) [type]
+                ex core::ops::Deref::deref(&bar()) (use core::ops::Deref) [type]
                 st S []
                 st &S [type]
                 st S []
@@ -8357,8 +8357,8 @@ impl Iterator for S {
                                 file_id: FileId(
                                     1,
                                 ),
-                                full_range: 7791..7999,
-                                focus_range: 7856..7862,
+                                full_range: 8086..8294,
+                                focus_range: 8151..8157,
                                 name: "Future",
                                 kind: Trait,
                                 container_name: "future",
@@ -8371,8 +8371,8 @@ impl Iterator for S {
                                 file_id: FileId(
                                     1,
                                 ),
-                                full_range: 8629..9095,
-                                focus_range: 8673..8681,
+                                full_range: 8924..9390,
+                                focus_range: 8968..8976,
                                 name: "Iterator",
                                 kind: Trait,
                                 container_name: "iterator",
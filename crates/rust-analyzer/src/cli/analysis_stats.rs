@@ -362,6 +362,11 @@ impl flags::AnalysisStats {
             total_tail_exprs: u64,
             error_codes: FxHashMap<String, u32>,
             syntax_errors: u32,
+            /// Amount of tail expressions terms were found for and amount of all the tail
+            /// expressions by the shape of their type
+            shapes: FxHashMap<&'static str, (u64, u64)>,
+            /// Biggest depth of the terms found
+            max_term_depth: usize,
         }
 
         let mut acc: Acc = Default::default();
@@ -412,6 +417,7 @@ impl flags::AnalysisStats {
                     None => continue,
                 };
 
+                let shape = goal_shape(db, &target_ty);
                 let ctx = hir::term_search::TermSearchCtx {
                     sema: &sema,
                     scope: &scope,
@@ -421,9 +427,17 @@ impl flags::AnalysisStats {
                         ..Default::default()
                     },
                 };
-                let found_terms = hir::term_search::term_search(&ctx);
+                let (found_terms, stats) = hir::term_search::term_search_with_stats(&ctx);
+                acc.max_term_depth = acc.max_term_depth.max(stats.max_depth);
+
+                let (found, total) = acc.shapes.entry(shape).or_default();
+                *found += !found_terms.is_empty() as u64;
+                *total += 1;
 
                 if found_terms.is_empty() {
+                    if verbosity.is_spammy() {
+                        bar.println(format!("No terms found: {stats:?}"));
+                    }
                     acc.tail_expr_no_term += 1;
                     acc.total_tail_exprs += 1;
                     // println!("\n{}\n", &original_text);
@@ -441,6 +455,9 @@ impl flags::AnalysisStats {
                     let generated =
                         term.gen_source_code(&scope, &mut formatter, false, true).unwrap();
                     syntax_hit_found |= trim(&original_text) == trim(&generated);
+                    if verbosity.is_spammy() {
+                        bar.println(format!("Term of depth {}: {generated}", term.depth()));
+                    }
 
                     // Validate if type-checks
                     let mut txt = file_txt.to_string();
@@ -517,6 +534,15 @@ impl flags::AnalysisStats {
             acc.total_tail_exprs,
             percentage(acc.total_tail_exprs - acc.tail_expr_no_term, acc.total_tail_exprs)
         ));
+        bar.println(format!("Tail Exprs max term depth: {}", acc.max_term_depth));
+        bar.println("Tail Exprs found by type shape:".to_owned());
+        for shape in GOAL_SHAPES {
+            let Some(&(found, total)) = acc.shapes.get(shape) else { continue };
+            bar.println(format!(
+                "    {shape:<14} {found:>6}/{total:<6} ({}%)",
+                percentage(found, total)
+            ));
+        }
         if self.validate_term_search {
             bar.println(format!(
                 "Tail Exprs total errors: {}, syntax errors: {}, error codes:",
@@ -1137,6 +1163,31 @@ fn shuffle<T>(rng: &mut Rand32, slice: &mut [T]) {
     }
 }
 
+/// Shapes of the term search goals in the order they are checked in by `goal_shape`
+const GOAL_SHAPES: [&str; 8] =
+    ["reference", "closure", "trait object", "generic", "primitive", "struct", "enum", "other"];
+
+/// Classify the type for the term search recall report
+fn goal_shape(db: &RootDatabase, ty: &hir::Type) -> &'static str {
+    if ty.is_reference() {
+        "reference"
+    } else if ty.is_closure() || ty.is_fn() {
+        "closure"
+    } else if ty.as_dyn_trait().is_some() || ty.as_impl_traits(db).is_some() {
+        "trait object"
+    } else if ty.as_type_param(db).is_some() || ty.type_arguments().next().is_some() {
+        "generic"
+    } else if ty.as_builtin().is_some() || ty.is_unit() {
+        "primitive"
+    } else {
+        match ty.as_adt() {
+            Some(hir::Adt::Struct(_)) => "struct",
+            Some(hir::Adt::Enum(_)) => "enum",
+            _ => "other",
+        }
+    }
+}
+
 fn percentage(n: u64, total: u64) -> u64 {
     (n * 100).checked_div(total).unwrap_or(100)
 }
@@ -58,6 +58,7 @@
 //!     sync: sized
 //!     transmute:
 //!     try: infallible
+//!     try_from: from, infallible, result, panic
 //!     unpin: sized
 //!     unsize: sized
 //!     todo: panic
@@ -327,6 +328,36 @@ pub mod convert {
     // region:infallible
     pub enum Infallible {}
     // endregion:infallible
+    // region:try_from
+    pub trait TryFrom<T>: Sized {
+        type Error;
+        fn try_from(value: T) -> Result<Self, Self::Error>;
+    }
+    pub trait TryInto<T>: Sized {
+        type Error;
+        fn try_into(self) -> Result<T, Self::Error>;
+    }
+
+    impl<T, U> TryInto<U> for T
+    where
+        U: TryFrom<T>,
+    {
+        type Error = U::Error;
+        fn try_into(self) -> Result<U, U::Error> {
+            U::try_from(self)
+        }
+    }
+
+    impl<T, U> TryFrom<U> for T
+    where
+        U: Into<T>,
+    {
+        type Error = Infallible;
+        fn try_from(value: U) -> Result<Self, Self::Error> {
+            Ok(U::into(value))
+        }
+    }
+    // endregion:try_from
 }
 
 pub mod mem {
@@ -1070,7 +1101,24 @@ pub mod slice {
         pub fn len(&self) -> usize {
             loop {}
         }
+        // region:iterator
+        pub fn iter(&self) -> Iter<'_, T> {
+            loop {}
+        }
+        // endregion:iterator
+    }
+
+    // region:iterator
+    pub struct Iter<'a, T> {
+        slice: &'a [T],
+    }
+    impl<'a, T> crate::iter::Iterator for Iter<'a, T> {
+        type Item = &'a T;
+        fn next(&mut self) -> Option<&'a T> {
+            loop {}
+        }
     }
+    // endregion:iterator
 }
 // endregion:slice
 
@@ -1102,6 +1150,17 @@ pub mod option {
             }
         }
 
+        // region:deref
+        pub fn as_deref(&self) -> Option<&T::Target>
+        where
+            T: crate::ops::Deref,
+        {
+            match self {
+                Some(t) => Some(t.deref()),
+                None => None,
+            }
+        }
+        // endregion:deref
         pub fn and<U>(self, _optb: Option<U>) -> Option<U> {
             loop {}
         }
@@ -1111,6 +1170,17 @@ pub mod option {
                 None => default,
             }
         }
+        // region:default
+        pub fn unwrap_or_default(self) -> T
+        where
+            T: crate::default::Default,
+        {
+            match self {
+                Some(val) => val,
+                None => T::default(),
+            }
+        }
+        // endregion:default
         // region:result
         pub const fn ok_or<E>(self, err: E) -> Result<T, E> {
             match self {
@@ -1158,6 +1228,36 @@ pub mod result {
         #[lang = "Err"]
         Err(E),
     }
+    // region:try_from
+    impl<T, E> Result<T, E> {
+        pub fn unwrap(self) -> T {
+            match self {
+                Ok(val) => val,
+                Err(_) => panic!("called `Result::unwrap()` on an `Err` value"),
+            }
+        }
+    }
+    // endregion:try_from
+    // region:option
+    impl<T, E> Result<T, E> {
+        pub fn ok(self) -> Option<T> {
+            match self {
+                Ok(val) => Some(val),
+                Err(_) => None,
+            }
+        }
+    }
+    // endregion:option
+    // region:fn
+    impl<T, E> Result<T, E> {
+        pub fn unwrap_or_else<F>(self, _f: F) -> T
+        where
+            F: FnOnce(E) -> T,
+        {
+            loop {}
+        }
+    }
+    // endregion:fn
 }
 // endregion:result
 
@@ -1173,6 +1273,9 @@ pub mod pin {
             Pin { pointer }
         }
     }
+    // region:coerce_unsized
+    impl<P, U> crate::ops::CoerceUnsized<Pin<U>> for Pin<P> where P: crate::ops::CoerceUnsized<U> {}
+    // endregion:coerce_unsized
     // region:deref
     impl<P: crate::ops::Deref> crate::ops::Deref for Pin<P> {
         type Target = P::Target;
@@ -1262,8 +1365,57 @@ pub mod iter {
                 loop {}
             }
         }
+
+        pub struct Map<I, F> {
+            iter: I,
+            f: F,
+        }
+        impl<B, I: Iterator, F> Iterator for Map<I, F>
+        where
+            F: FnMut(I::Item) -> B,
+        {
+            type Item = B;
+
+            fn next(&mut self) -> Option<B> {
+                loop {}
+            }
+        }
+
+        pub struct Filter<I, P> {
+            iter: I,
+            predicate: P,
+        }
+        impl<I: Iterator, P> Iterator for Filter<I, P>
+        where
+            P: FnMut(&I::Item) -> bool,
+        {
+            type Item = I::Item;
+
+            fn next(&mut self) -> Option<I::Item> {
+                loop {}
+            }
+        }
+
+        // region:clone
+        pub struct Cloned<I> {
+            it: I,
+        }
+        impl<'a, I, T: 'a + Clone> Iterator for Cloned<I>
+        where
+            I: Iterator<Item = &'a T>,
+        {
+            type Item = T;
+
+            fn next(&mut self) -> Option<T> {
+                loop {}
+            }
+        }
+        // endregion:clone
     }
-    pub use self::adapters::{FilterMap, Take};
+    pub use self::adapters::{Filter, FilterMap, Map, Take};
+    // region:clone
+    pub use self::adapters::Cloned;
+    // endregion:clone
 
     mod sources {
         mod repeat {
@@ -1315,6 +1467,34 @@ pub mod iter {
                 {
                     loop {}
                 }
+                fn map<B, F>(self, _f: F) -> crate::iter::Map<Self, F>
+                where
+                    Self: Sized,
+                    F: FnMut(Self::Item) -> B,
+                {
+                    loop {}
+                }
+                fn filter<P>(self, _predicate: P) -> crate::iter::Filter<Self, P>
+                where
+                    Self: Sized,
+                    P: FnMut(&Self::Item) -> bool,
+                {
+                    loop {}
+                }
+                // region:clone
+                fn cloned<'a, T: 'a + Clone>(self) -> crate::iter::Cloned<Self>
+                where
+                    Self: Sized + Iterator<Item = &'a T>,
+                {
+                    loop {}
+                }
+                // endregion:clone
+                fn collect<B: crate::iter::FromIterator<Self::Item>>(self) -> B
+                where
+                    Self: Sized,
+                {
+                    loop {}
+                }
                 // endregion:iterators
             }
             impl<I: Iterator + ?Sized> Iterator for &mut I {
@@ -1361,10 +1541,21 @@ pub mod iter {
                     loop {}
                 }
             }
+            // region:iterators
+            pub trait FromIterator<A>: Sized {
+                fn from_iter<T: IntoIterator<Item = A>>(iter: T) -> Self;
+            }
+            // endregion:iterators
         }
         pub use self::collect::IntoIterator;
+        // region:iterators
+        pub use self::collect::FromIterator;
+        // endregion:iterators
     }
     pub use self::traits::{IntoIterator, Iterator};
+    // region:iterators
+    pub use self::traits::FromIterator;
+    // endregion:iterators
 }
 // endregion:iterator
 
@@ -1614,6 +1805,7 @@ pub mod prelude {
             cmp::{Ord, PartialOrd},                  // :ord
             convert::AsRef,                          // :as_ref
             convert::{From, Into},                   // :from
+            convert::{TryFrom, TryInto},             // :try_from
             default::Default,                        // :default
             iter::{IntoIterator, Iterator},          // :iterator
             macros::builtin::{derive, derive_const}, // :derive